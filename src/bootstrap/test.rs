@@ -1338,6 +1338,10 @@ impl Step for Compiletest {
             cmd.env("RUSTC_PROFILER_SUPPORT", "1");
         }
 
+        if builder.config.rustc_parallel {
+            cmd.env("RUSTC_PARALLEL_COMPILER", "1");
+        }
+
         cmd.env("RUST_TEST_TMPDIR", builder.out.join("tmp"));
 
         cmd.arg("--adb-path").arg("adb");
@@ -31,6 +31,7 @@ use std::marker::{PhantomData, Send};
 use std::mem;
 use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use alloc::raw_vec::RawVec;
 
@@ -58,13 +59,39 @@ struct TypedArenaChunk<T> {
     entries: usize,
 }
 
+/// The number of bytes currently held by the chunks of all arenas, used by
+/// `-Z memory-limit`.
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of bytes currently allocated by all arenas in the process.
+pub fn total_allocated_bytes() -> usize {
+    ALLOCATED_BYTES.load(Ordering::Relaxed)
+}
+
 impl<T> TypedArenaChunk<T> {
     #[inline]
     unsafe fn new(capacity: usize) -> TypedArenaChunk<T> {
-        TypedArenaChunk {
+        let chunk = TypedArenaChunk {
             storage: RawVec::with_capacity(capacity),
             entries: 0,
-        }
+        };
+        ALLOCATED_BYTES.fetch_add(chunk.allocated_bytes(), Ordering::Relaxed);
+        chunk
+    }
+
+    /// Tries to grow the chunk without moving it, see `RawVec::reserve_in_place`.
+    #[inline]
+    fn reserve_in_place(&mut self, used_capacity: usize, needed_extra_capacity: usize) -> bool {
+        let old_bytes = self.allocated_bytes();
+        let reserved = self.storage.reserve_in_place(used_capacity, needed_extra_capacity);
+        ALLOCATED_BYTES.fetch_add(self.allocated_bytes() - old_bytes, Ordering::Relaxed);
+        reserved
+    }
+
+    /// Returns the size of the storage of this chunk in bytes.
+    #[inline]
+    fn allocated_bytes(&self) -> usize {
+        self.storage.capacity() * mem::size_of::<T>()
     }
 
     /// Destroys this arena chunk.
@@ -102,6 +129,14 @@ impl<T> TypedArenaChunk<T> {
     }
 }
 
+unsafe impl<#[may_dangle] T> Drop for TypedArenaChunk<T> {
+    fn drop(&mut self) {
+        // The storage itself is freed by `RawVec`, and the objects in it are
+        // destroyed by the arena owning this chunk.
+        ALLOCATED_BYTES.fetch_sub(self.allocated_bytes(), Ordering::Relaxed);
+    }
+}
+
 const PAGE: usize = 4096;
 
 impl<T> Default for TypedArena<T> {
@@ -124,6 +159,12 @@ impl<T> TypedArena<T> {
 
         self.chunks.borrow().iter().any(|chunk| chunk.start() <= ptr && ptr < chunk.end())
     }
+
+    /// Returns the number of bytes allocated for the chunks of this arena.
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks.borrow().iter().map(|chunk| chunk.allocated_bytes()).sum()
+    }
+
     /// Allocates an object in the `TypedArena`, returning a reference to it.
     #[inline]
     pub fn alloc(&self, object: T) -> &mut T {
@@ -263,7 +304,7 @@ impl<T> TypedArena<T> {
                 let used_bytes = self.ptr.get() as usize - last_chunk.start() as usize;
                 let currently_used_cap = used_bytes / mem::size_of::<T>();
                 last_chunk.entries = currently_used_cap;
-                if last_chunk.storage.reserve_in_place(currently_used_cap, n) {
+                if last_chunk.reserve_in_place(currently_used_cap, n) {
                     self.end.set(last_chunk.end());
                     return;
                 } else {
@@ -380,6 +421,11 @@ impl DroplessArena {
         self.chunks.borrow().iter().any(|chunk| chunk.start() <= ptr && ptr < chunk.end())
     }
 
+    /// Returns the number of bytes allocated for the chunks of this arena.
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks.borrow().iter().map(|chunk| chunk.allocated_bytes()).sum()
+    }
+
     #[inline]
     fn align(&self, align: usize) {
         let final_address = ((self.ptr.get() as usize) + align - 1) & !(align - 1);
@@ -395,10 +441,7 @@ impl DroplessArena {
             let (chunk, mut new_capacity);
             if let Some(last_chunk) = chunks.last_mut() {
                 let used_bytes = self.ptr.get() as usize - last_chunk.start() as usize;
-                if last_chunk
-                    .storage
-                    .reserve_in_place(used_bytes, needed_bytes)
-                {
+                if last_chunk.reserve_in_place(used_bytes, needed_bytes) {
                     self.end.set(last_chunk.end());
                     return;
                 } else {
@@ -590,6 +633,10 @@ impl SyncDroplessArena {
         self.lock.lock().in_arena(ptr)
     }
 
+    pub fn allocated_bytes(&self) -> usize {
+        self.lock.lock().allocated_bytes()
+    }
+
     #[inline(always)]
     pub fn alloc_raw(&self, bytes: usize, align: usize) -> &mut [u8] {
         // Extend the lifetime of the result since it's limited to the lock guard
@@ -2,6 +2,7 @@ extern crate test;
 use test::Bencher;
 use super::TypedArena;
 use std::cell::Cell;
+use std::mem;
 
 #[allow(dead_code)]
 #[derive(Debug, Eq, PartialEq)]
@@ -117,6 +118,19 @@ pub fn test_typed_arena_clear() {
     }
 }
 
+#[test]
+pub fn test_typed_arena_allocated_bytes() {
+    let mut arena = TypedArena::default();
+    assert_eq!(arena.allocated_bytes(), 0);
+    for _ in 0..10000 {
+        arena.alloc(Point { x: 1, y: 2, z: 3 });
+    }
+    let allocated = arena.allocated_bytes();
+    assert!(allocated >= 10000 * mem::size_of::<Point>());
+    arena.clear();
+    assert!(arena.allocated_bytes() <= allocated);
+}
+
 #[bench]
 pub fn bench_typed_arena_clear(b: &mut Bencher) {
     let mut arena = TypedArena::default();
@@ -137,6 +137,34 @@ macro_rules! declare_arena {
     }
 }
 
+macro_rules! allocated_bytes_for_type {
+    ([][$arena:expr]) => {
+        $arena.allocated_bytes()
+    };
+    ([few$(, $attrs:ident)*][$arena:expr]) => {
+        0
+    };
+    ([$ignore:ident$(, $attrs:ident)*]$args:tt) => {
+        allocated_bytes_for_type!([$($attrs),*]$args)
+    };
+}
+
+macro_rules! impl_arena_allocated_bytes {
+    ([], [$($a:tt $name:ident: $ty:ty,)*], $tcx:lifetime) => {
+        impl<$tcx> Arena<$tcx> {
+            /// Returns the number of bytes allocated by each of the arenas, by name.
+            /// Types using the `few` modifier are accounted for in the `drop` arena.
+            pub fn allocated_bytes(&self) -> Vec<(&'static str, usize)> {
+                vec![
+                    ("dropless", self.dropless.allocated_bytes()),
+                    ("drop", self.drop.arena.allocated_bytes()),
+                    $((stringify!($name), allocated_bytes_for_type!($a[self.$name])),)*
+                ]
+            }
+        }
+    }
+}
+
 macro_rules! which_arena_for_type {
     ([][$arena:expr]) => {
         Some($arena)
@@ -167,6 +195,8 @@ arena_types!(declare_arena, [], 'tcx);
 
 arena_types!(impl_arena_allocatable, [], 'tcx);
 
+arena_types!(impl_arena_allocated_bytes, [], 'tcx);
+
 pub trait ArenaAllocatable {}
 
 impl<T: Copy> ArenaAllocatable for T {}
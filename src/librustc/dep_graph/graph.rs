@@ -78,6 +78,21 @@ struct DepGraphData {
 
     // Used for testing, only populated when -Zquery-dep-graph is specified.
     loaded_from_cache: Lock<FxHashMap<DepNodeIndex, bool>>,
+
+    // Why nodes of the previous dep-graph could not be marked green, only
+    // populated when -Zincremental-explain is specified.
+    red_causes: Lock<FxHashMap<SerializedDepNodeIndex, RedCause>>,
+}
+
+/// Why a node of the previous dep-graph could not be marked green.
+#[derive(Copy, Clone, Debug)]
+enum RedCause {
+    /// The given dependency was red.
+    Dependency(SerializedDepNodeIndex),
+    /// The given dependency refers to an item which doesn't exist anymore.
+    Removed(SerializedDepNodeIndex),
+    /// The given dependency could not be forced, so its color is unknown.
+    NotForced(SerializedDepNodeIndex),
 }
 
 pub fn hash_result<R>(hcx: &mut StableHashingContext<'_>, result: &R) -> Option<Fingerprint>
@@ -105,6 +120,7 @@ impl DepGraph {
                 previous: prev_graph,
                 colors: DepNodeColorMap::new(prev_graph_node_count),
                 loaded_from_cache: Default::default(),
+                red_causes: Default::default(),
             })),
         }
     }
@@ -601,6 +617,49 @@ impl DepGraph {
         }
     }
 
+    /// Explains why `dep_node` has to be recomputed instead of being marked
+    /// green, for `-Zincremental-explain`. Each line describes a link of the
+    /// chain of red dep-nodes leading to the input that changed.
+    pub fn explain_red(&self, tcx: TyCtxt<'_>, dep_node: &DepNode) -> Vec<String> {
+        let data = match self.data {
+            Some(ref data) => data,
+            None => return vec![],
+        };
+        let mut prev_index = match data.previous.node_to_index_opt(dep_node) {
+            Some(prev_index) => prev_index,
+            None => return vec!["it was not computed by the previous session".to_string()],
+        };
+        if dep_node.kind.is_eval_always() {
+            return vec!["it is recomputed by every session".to_string()];
+        }
+
+        let red_causes = data.red_causes.lock();
+        let mut lines = vec![];
+        loop {
+            let node = data.previous.index_to_node(prev_index);
+            let cause = match red_causes.get(&prev_index) {
+                Some(&cause) => cause,
+                None => {
+                    // None of the dependencies of the node is known to be
+                    // red: it is an input, or it was recomputed anyway.
+                    lines.push(describe_change(tcx, &node));
+                    return lines;
+                }
+            };
+            let (dep, what) = match cause {
+                RedCause::Dependency(dep) => (dep, "changed"),
+                RedCause::Removed(dep) => (dep, "doesn't exist anymore"),
+                RedCause::NotForced(dep) => (dep, "couldn't be recomputed"),
+            };
+            lines.push(format!("`{:?}` depends on `{:?}`, which {}",
+                               node, data.previous.index_to_node(dep), what));
+            match cause {
+                RedCause::Dependency(dep) => prev_index = dep,
+                RedCause::Removed(_) | RedCause::NotForced(_) => return lines,
+            }
+        }
+    }
+
     /// Try to mark a dep-node which existed in the previous compilation session as green.
     fn try_mark_previous_green<'tcx>(
         &self,
@@ -626,6 +685,13 @@ impl DepGraph {
 
         let mut current_deps = SmallVec::new();
 
+        let red = |cause| {
+            if unlikely!(tcx.sess.opts.debugging_opts.incremental_explain.is_some()) {
+                data.red_causes.lock().insert(prev_dep_node_index, cause);
+            }
+            None
+        };
+
         for &dep_dep_node_index in prev_deps {
             let dep_dep_node_color = data.colors.get(dep_dep_node_index);
 
@@ -649,7 +715,7 @@ impl DepGraph {
                             immediately red",
                             dep_node,
                             data.previous.index_to_node(dep_dep_node_index));
-                    return None
+                    return red(RedCause::Dependency(dep_dep_node_index))
                 }
                 None => {
                     let dep_dep_node = &data.previous.index_to_node(dep_dep_node_index);
@@ -681,7 +747,7 @@ impl DepGraph {
                                 if dep_dep_node.extract_def_id(tcx).is_none() {
                                     // If the node does not exist anymore, we
                                     // just fail to mark green.
-                                    return None
+                                    return red(RedCause::Removed(dep_dep_node_index))
                                 } else {
                                     // If the node does exist, it should have
                                     // been pre-allocated.
@@ -715,7 +781,7 @@ impl DepGraph {
                                         dependency {:?} was red after forcing",
                                        dep_node,
                                        dep_dep_node);
-                                return None
+                                return red(RedCause::Dependency(dep_dep_node_index))
                             }
                             None => {
                                 if !tcx.sess.has_errors() {
@@ -733,7 +799,7 @@ impl DepGraph {
                         // The DepNode could not be forced.
                         debug!("try_mark_previous_green({:?}) - END - dependency {:?} \
                                 could not be forced", dep_node, dep_dep_node);
-                        return None
+                        return red(RedCause::NotForced(dep_dep_node_index))
                     }
                 }
             }
@@ -895,6 +961,23 @@ impl DepGraph {
     }
 }
 
+/// Describes how `dep_node` changed, when none of its dependencies is known
+/// to be red.
+fn describe_change(tcx: TyCtxt<'_>, dep_node: &DepNode) -> String {
+    let def_id = dep_node.extract_def_id(tcx);
+    match (dep_node.kind, def_id) {
+        (DepKind::Hir, Some(def_id)) | (DepKind::HirBody, Some(def_id)) => {
+            let file = tcx.sess.source_map().span_to_filename(tcx.def_span(def_id));
+            format!("the source code of `{}` in `{}` changed", tcx.def_path_str(def_id), file)
+        }
+        (DepKind::CrateMetadata, Some(def_id)) => {
+            format!("the upstream crate `{}` changed", tcx.crate_name(def_id.krate))
+        }
+        (kind, _) if kind.is_eval_always() => format!("the input `{:?}` changed", dep_node),
+        _ => format!("`{:?}` was recomputed and its result changed", dep_node),
+    }
+}
+
 /// A "work product" is an intermediate result that we save into the
 /// incremental directory for later re-use. The primary example are
 /// the object files that we save for each partition at code
@@ -134,6 +134,7 @@ pub mod ty;
 pub mod util {
     pub mod captures;
     pub mod common;
+    pub mod memory_limit;
     pub mod nodemap;
     pub mod profiling;
     pub mod bug;
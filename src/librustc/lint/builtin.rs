@@ -304,6 +304,12 @@ declare_lint! {
     "failures in resolving intra-doc link targets"
 }
 
+declare_lint! {
+    pub BROKEN_DOC_LINKS,
+    Warn,
+    "detects links in documentation to pages or anchors that rustdoc does not generate"
+}
+
 declare_lint! {
     pub MISSING_DOC_CODE_EXAMPLES,
     Allow,
@@ -447,6 +453,7 @@ declare_lint_pass! {
         IRREFUTABLE_LET_PATTERNS,
         DUPLICATE_MACRO_EXPORTS,
         INTRA_DOC_LINK_RESOLUTION_FAILURE,
+        BROKEN_DOC_LINKS,
         MISSING_DOC_CODE_EXAMPLES,
         PRIVATE_DOC_TESTS,
         WHERE_CLAUSES_OBJECT_SAFETY,
@@ -544,6 +544,8 @@ pub enum ResourceExhaustionInfo {
     StackFrameLimitReached,
     /// The program ran into an infinite loop.
     InfiniteLoop,
+    /// The program took more steps than the interpreter was allowed to take.
+    StepLimitReached,
 }
 
 impl fmt::Debug for ResourceExhaustionInfo {
@@ -555,6 +557,8 @@ impl fmt::Debug for ResourceExhaustionInfo {
             InfiniteLoop =>
                 write!(f, "duplicate interpreter state observed here, const evaluation will never \
                     terminate"),
+            StepLimitReached =>
+                write!(f, "reached the maximum number of interpreter steps"),
         }
     }
 }
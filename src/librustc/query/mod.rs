@@ -174,8 +174,10 @@ rustc_queries! {
             -> &'tcx ty::GenericPredicates<'tcx> {}
 
         /// Returns the predicates written explicitly by the user.
-        query explicit_predicates_of(_: DefId)
-            -> &'tcx ty::GenericPredicates<'tcx> {}
+        query explicit_predicates_of(key: DefId)
+            -> &'tcx ty::GenericPredicates<'tcx> {
+            cache_on_disk_if { key.is_local() }
+        }
 
         /// Returns the inferred outlives predicates (e.g., for `struct
         /// Foo<'a, T> { x: &'a T }`, this would return `T: 'a`).
@@ -189,6 +191,7 @@ rustc_queries! {
         /// additional acyclicity requirements).
         query super_predicates_of(key: DefId) -> &'tcx ty::GenericPredicates<'tcx> {
             desc { |tcx| "computing the supertraits of `{}`", tcx.def_path_str(key) }
+            cache_on_disk_if { key.is_local() }
         }
 
         /// To avoid cycles within the predicates of a single item we compute
@@ -272,7 +275,9 @@ rustc_queries! {
         /// Maps from a trait item to the trait item "descriptor".
         query associated_item(_: DefId) -> ty::AssocItem {}
 
-        query impl_trait_ref(_: DefId) -> Option<ty::TraitRef<'tcx>> {}
+        query impl_trait_ref(key: DefId) -> Option<ty::TraitRef<'tcx>> {
+            cache_on_disk_if { key.is_local() }
+        }
         query impl_polarity(_: DefId) -> hir::ImplPolarity {}
 
         query issue33140_self_ty(_: DefId) -> Option<ty::Ty<'tcx>> {}
@@ -297,7 +302,9 @@ rustc_queries! {
         query unsafe_derive_on_repr_packed(_: DefId) -> () {}
 
         /// The signature of functions and closures.
-        query fn_sig(_: DefId) -> ty::PolyFnSig<'tcx> {}
+        query fn_sig(key: DefId) -> ty::PolyFnSig<'tcx> {
+            cache_on_disk_if { key.is_local() }
+        }
     }
 
     Other {
@@ -970,6 +977,9 @@ rustc_queries! {
         ) -> Result<traits::EvaluationResult, traits::OverflowError> {
             no_force
             desc { "evaluating trait selection obligation `{}`", goal.value.value }
+            // Since the query can't be forced, only the results used by a
+            // session are saved for the next one.
+            cache_on_disk_if { true }
         }
 
         query evaluate_goal(
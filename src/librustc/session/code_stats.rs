@@ -1,6 +1,7 @@
 use rustc_target::abi::{Align, Size};
-use rustc_data_structures::fx::{FxHashSet};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use std::cmp::{self, Ordering};
+use syntax_pos::Span;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -25,12 +26,35 @@ pub struct FieldInfo {
     pub align: u64,
 }
 
+/// A local saved in a generator, with the suspension points it is stored at.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SavedLocalInfo {
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+    pub align: u64,
+    pub span: Span,
+    pub live_at: Vec<String>,
+}
+
+/// The breakdown of the layout of a generator into its upvars and saved locals.
+#[derive(Clone, Debug)]
+pub struct GeneratorSizeInfo {
+    pub discr_size: Size,
+    /// The variants, one per state of the generator. The upvars are fields of all of them.
+    pub variants: Vec<VariantInfo>,
+    /// The upvars, along with the first span at which they are used.
+    pub upvars: Vec<(FieldInfo, Span)>,
+    pub saved_locals: Vec<SavedLocalInfo>,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum DataTypeKind {
     Struct,
     Union,
     Enum,
     Closure,
+    Generator,
 }
 
 #[derive(PartialEq, Eq, Hash, Debug)]
@@ -42,11 +66,32 @@ pub struct TypeSizeInfo {
     pub packed: bool,
     pub opt_discr_size: Option<u64>,
     pub variants: Vec<VariantInfo>,
+    pub saved_locals: Vec<SavedLocalInfo>,
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct MonoItemInfo {
+    pub description: String,
+    pub symbol_name: String,
+    /// The generic item this item is an instantiation of, if any.
+    pub generic: Option<String>,
+    /// The crate of the item whose code made this item necessary.
+    pub requested_by: String,
+    /// The items through which this item was reached, starting from a
+    /// non-generic item of the local crate.
+    pub chain: Vec<String>,
 }
 
 #[derive(PartialEq, Eq, Debug, Default)]
 pub struct CodeStats {
     type_sizes: FxHashSet<TypeSizeInfo>,
+    mono_items: Vec<MonoItemInfo>,
+    /// The number of LLVM IR instructions generated for each symbol, summed
+    /// over the codegen units it is instantiated in.
+    ir_instructions: FxHashMap<String, u64>,
+    /// The size in bytes of each symbol of the object files, summed over the
+    /// object files defining it.
+    symbol_sizes: FxHashMap<String, u64>,
 }
 
 impl CodeStats {
@@ -57,7 +102,8 @@ impl CodeStats {
                                          overall_size: Size,
                                          packed: bool,
                                          opt_discr_size: Option<Size>,
-                                         mut variants: Vec<VariantInfo>) {
+                                         mut variants: Vec<VariantInfo>,
+                                         saved_locals: Vec<SavedLocalInfo>) {
         // Sort variants so the largest ones are shown first. A stable sort is
         // used here so that source code order is preserved for all variants
         // that have the same size.
@@ -72,6 +118,7 @@ impl CodeStats {
             packed: packed,
             opt_discr_size: opt_discr_size.map(|s| s.bytes()),
             variants,
+            saved_locals,
         };
         self.type_sizes.insert(info);
     }
@@ -110,7 +157,7 @@ impl CodeStats {
 
             let struct_like = match info.kind {
                 DataTypeKind::Struct | DataTypeKind::Closure => true,
-                DataTypeKind::Enum | DataTypeKind::Union => false,
+                DataTypeKind::Enum | DataTypeKind::Union | DataTypeKind::Generator => false,
             };
             for (i, variant_info) in info.variants.iter().enumerate() {
                 let VariantInfo { ref name, kind: _, align: _, size, ref fields } = *variant_info;
@@ -170,6 +217,105 @@ impl CodeStats {
                 println!("print-type-size {}end padding: {} bytes",
                          indent, info.overall_size - max_variant_size);
             }
+
+            // Generators also list their saved locals, largest first, along with the
+            // suspension points they are stored at.
+            let mut saved_locals: Vec<_> = info.saved_locals.iter().collect();
+            saved_locals.sort_by(|local1, local2| local2.size.cmp(&local1.size));
+            for local in saved_locals {
+                println!("print-type-size {}local `.{}`: {} bytes, offset: {} bytes, \
+                          alignment: {} bytes, live at: `{}`",
+                         indent, local.name, local.size, local.offset, local.align,
+                         local.live_at.join("`, `"));
+            }
+        }
+    }
+
+    pub fn record_mono_item(&mut self, info: MonoItemInfo) {
+        self.mono_items.push(info);
+    }
+
+    pub fn record_ir_instructions(&mut self, symbol_name: &str, count: u64) {
+        *self.ir_instructions.entry(symbol_name.to_string()).or_insert(0) += count;
+    }
+
+    pub fn record_symbol_size(&mut self, symbol_name: &str, size: u64) {
+        *self.symbol_sizes.entry(symbol_name.to_string()).or_insert(0) += size;
+    }
+
+    fn symbol_size(&self, symbol_name: &str) -> Option<u64> {
+        // Some object formats prefix the symbols with an underscore.
+        self.symbol_sizes.get(symbol_name)
+            .or_else(|| self.symbol_sizes.get(&format!("_{}", symbol_name)))
+            .cloned()
+    }
+
+    pub fn print_mono_item_sizes(&self) {
+        fn show(n: Option<u64>) -> String {
+            n.map_or_else(|| "?".to_string(), |n| n.to_string())
+        }
+
+        #[derive(Default)]
+        struct Totals {
+            size: Option<u64>,
+            ir_instructions: Option<u64>,
+            items: usize,
+        }
+
+        impl Totals {
+            fn add(&mut self, size: Option<u64>, ir_instructions: Option<u64>) {
+                if let Some(size) = size {
+                    *self.size.get_or_insert(0) += size;
+                }
+                if let Some(ir_instructions) = ir_instructions {
+                    *self.ir_instructions.get_or_insert(0) += ir_instructions;
+                }
+                self.items += 1;
+            }
+        }
+
+        let mut items: Vec<_> = self.mono_items.iter().map(|info| {
+            let size = self.symbol_size(&info.symbol_name);
+            let ir_instructions = self.ir_instructions.get(&info.symbol_name).cloned();
+            (info, size, ir_instructions)
+        }).collect();
+
+        // Primary sort: large-to-small, by object code size and then by IR size.
+        // Secondary sort: description (dictionary order)
+        items.sort_by(|&(info1, size1, ir1), &(info2, size2, ir2)| {
+            (size2, ir2).cmp(&(size1, ir1))
+                .then_with(|| info1.description.cmp(&info2.description))
+        });
+
+        let mut generics: FxHashMap<&str, Totals> = Default::default();
+        let mut crates: FxHashMap<&str, Totals> = Default::default();
+        for &(info, size, ir_instructions) in &items {
+            println!("print-mono-item-size item: `{}`: {} bytes, {} IR instructions",
+                     info.description, show(size), show(ir_instructions));
+            println!("print-mono-item-size     requested by crate `{}`", info.requested_by);
+            if !info.chain.is_empty() {
+                println!("print-mono-item-size     through `{}`", info.chain.join("` -> `"));
+            }
+
+            if let Some(ref generic) = info.generic {
+                generics.entry(&generic[..]).or_default().add(size, ir_instructions);
+            }
+            crates.entry(&info.requested_by[..]).or_default().add(size, ir_instructions);
+        }
+
+        for (kind, totals, unit) in vec![("generic", generics, "instantiations"),
+                                         ("crate", crates, "items")] {
+            let mut totals: Vec<_> = totals.into_iter().collect();
+            totals.sort_by(|(name1, totals1), (name2, totals2)| {
+                (totals2.size, totals2.ir_instructions)
+                    .cmp(&(totals1.size, totals1.ir_instructions))
+                    .then_with(|| name1.cmp(name2))
+            });
+            for (name, totals) in totals {
+                println!("print-mono-item-size {}: `{}`: {} bytes, {} IR instructions, {} {}",
+                         kind, name, show(totals.size), show(totals.ir_instructions),
+                         totals.items, unit);
+            }
         }
     }
 }
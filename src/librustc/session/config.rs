@@ -812,6 +812,8 @@ macro_rules! options {
         pub const parse_list: Option<&str> = Some("a space-separated list of strings");
         pub const parse_opt_list: Option<&str> = Some("a space-separated list of strings");
         pub const parse_opt_comma_list: Option<&str> = Some("a comma-separated list of strings");
+        pub const parse_list_with_polarity: Option<&str> =
+            Some("a comma-separated list of strings, each beginning with `+` or `-`");
         pub const parse_uint: Option<&str> = Some("a number");
         pub const parse_passes: Option<&str> =
             Some("a space-separated list of passes, or `all`");
@@ -955,6 +957,26 @@ macro_rules! options {
             }
         }
 
+        fn parse_list_with_polarity(slot: &mut Vec<(String, bool)>, v: Option<&str>) -> bool {
+            match v {
+                Some(s) => {
+                    for s in s.split(',') {
+                        let enabled = match s.chars().next() {
+                            Some('+') => true,
+                            Some('-') => false,
+                            _ => return false,
+                        };
+                        if s.len() == 1 {
+                            return false;
+                        }
+                        slot.push((s[1..].to_string(), enabled));
+                    }
+                    true
+                },
+                None => false,
+            }
+        }
+
         fn parse_uint(slot: &mut usize, v: Option<&str>) -> bool {
             match v.and_then(|s| s.parse().ok()) {
                 Some(i) => { *slot = i; true },
@@ -1258,8 +1280,11 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "prints the LLVM optimization passes being run"),
     ast_json: bool = (false, parse_bool, [UNTRACKED],
         "print the AST as JSON and halt"),
+    memory_limit: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "abort compilation with an error when arenas or the resident set size of the process \
+        grow beyond this many bytes"),
     threads: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
-        "use a thread pool with N threads"),
+        "use a thread pool with N threads to check and build the MIR of item bodies in parallel"),
     ast_json_noexpand: bool = (false, parse_bool, [UNTRACKED],
         "print the pre-expansion AST as JSON and halt"),
     ls: bool = (false, parse_bool, [UNTRACKED],
@@ -1302,6 +1327,28 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "enable incremental compilation support for queries (experimental)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof)"),
+    incremental_explain: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "explain why the queries with the given name, or computed for the item with the given \
+        path, are re-executed instead of being reused from the incremental cache"),
+    incremental_cache_max_size: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the least recently used incremental compilation sessions of all crates sharing \
+        the incremental directory, to keep its size under the given number of megabytes"),
+    incremental_cache_max_age: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the incremental compilation sessions which weren't used for the given number of \
+        days"),
+    incremental_gc: bool = (false, parse_bool, [UNTRACKED],
+        "garbage collect the incremental compilation directory given with `-C incremental`, \
+        without compiling anything"),
+    incremental_shared: bool = (false, parse_bool, [TRACKED],
+        "make the incremental compilation cache reusable by other checkouts of the sources, \
+        by identifying source files by their name after `--remap-path-prefix` and storing work \
+        products by content"),
+    incremental_seed: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "a read-only incremental compilation directory, like one produced by CI, to start from \
+        when the incremental directory has no previous session for the crate"),
+    incremental_cache_stats: bool = (false, parse_bool, [UNTRACKED],
+        "print, for each query, the time spent loading its results from the incremental cache \
+        or recomputing them, and the size of the results saved for the next session"),
     incremental_dump_hash: bool = (false, parse_bool, [UNTRACKED],
         "dump hash information in textual format to stdout"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
@@ -1338,10 +1385,29 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "show spans for compiler debugging (expr|pat|ty)"),
     print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
         "print layout information for each type encountered"),
+    future_size_limit: usize = (16384, parse_uint, [TRACKED],
+        "the size in bytes above which a future is reported by the `large_futures` lint \
+        (default: 16384)"),
     print_mono_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "print the result of the monomorphization collection pass"),
+    print_mono_item_sizes: bool = (false, parse_bool, [UNTRACKED],
+        "print the size of the code generated for each monomorphized item, and why it was \
+        instantiated"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
         "set the MIR optimization level (0-3, default: 1)"),
+    inline_mir_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "the cost up to which the MIR inliner inlines a callee (default: depends on the \
+        optimization level)"),
+    inline_mir_hint_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "the cost up to which the MIR inliner inlines a callee with `#[inline]` (default: \
+        twice the threshold of the other callees)"),
+    mir_enable_passes: Vec<(String, bool)> = (Vec::new(), parse_list_with_polarity, [TRACKED],
+        "enable (`+Pass`) or disable (`-Pass`) the MIR passes with these names, the last \
+        occurrence of a name taking precedence"),
+    mir_opt_bisect_limit: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "only apply the first N changes made by the MIR optimizations, and log them"),
+    validate_mir: bool = (false, parse_bool, [UNTRACKED],
+        "check the invariants of the MIR after each pass, reporting the pass that breaks them"),
     mutable_noalias: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "emit noalias metadata for mutable references (default: yes on LLVM >= 6)"),
     dump_mir: Option<String> = (None, parse_opt_string, [UNTRACKED],
@@ -1453,6 +1519,12 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "run the self profiler and output the raw event data"),
     self_profile_events: Option<Vec<String>> = (None, parse_opt_comma_list, [UNTRACKED],
         "specifies which kinds of events get recorded by the self profiler"),
+    item_time_report: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "print the N items on which typeck, borrowck, MIR optimization, monomorphization and \
+        codegen spent the most time"),
+    self_profile_report: Option<Vec<String>> = (None, parse_opt_comma_list, [UNTRACKED],
+        "write reports of the self profiler's data at the end of the session \
+        (comma separated list of `summary`, `flamegraph` and `chrome`)"),
     emit_stack_sizes: bool = (false, parse_bool, [UNTRACKED],
         "emits a section containing stack size metadata"),
     plt: Option<bool> = (None, parse_opt_bool, [TRACKED],
@@ -2161,6 +2233,14 @@ pub fn build_session_options_and_crate_config(
         );
     }
 
+    if debugging_opts.threads.unwrap_or(1) > 1 && !cfg!(parallel_compiler) {
+        early_warn(
+            error_format,
+            "this compiler was built without support for the parallel front-end, \
+             `-Z threads` is ignored",
+        );
+    }
+
     if debugging_opts.threads.unwrap_or(1) > 1 && debugging_opts.fuel.is_some() {
         early_error(
             error_format,
@@ -2168,6 +2248,13 @@ pub fn build_session_options_and_crate_config(
         );
     }
 
+    if debugging_opts.threads.unwrap_or(1) > 1 && debugging_opts.mir_opt_bisect_limit.is_some() {
+        early_error(
+            error_format,
+            "`-Z mir-opt-bisect-limit` is incompatible with multiple threads",
+        );
+    }
+
     if codegen_units == Some(0) {
         early_error(
             error_format,
@@ -2675,6 +2762,7 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(Option<usize>);
     impl_dep_tracking_hash_via_hash!(Option<String>);
     impl_dep_tracking_hash_via_hash!(Option<(String, u64)>);
+    impl_dep_tracking_hash_via_hash!(Vec<(String, bool)>);
     impl_dep_tracking_hash_via_hash!(Option<Vec<String>>);
     impl_dep_tracking_hash_via_hash!(Option<MergeFunctions>);
     impl_dep_tracking_hash_via_hash!(Option<PanicStrategy>);
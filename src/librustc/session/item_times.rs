@@ -0,0 +1,137 @@
+use crate::hir::def_id::DefId;
+use crate::ty::Instance;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::sync::Lock;
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+/// The columns of the `-Z item-time-report` table, along with the activities they are made of.
+/// Activities are either query names or the names used by the callers of `time_instance`.
+const CATEGORIES: &[(&str, &[&str])] = &[
+    ("typeck", &["typeck_tables_of", "check_item_well_formed", "check_trait_item_well_formed",
+                 "check_impl_item_well_formed"]),
+    ("borrowck", &["borrowck", "mir_borrowck"]),
+    ("MIR opt", &["mir_const", "mir_validated", "optimized_mir"]),
+    ("mono", &["monomorphization"]),
+    // Only the generation of the LLVM IR is timed per item: LLVM optimizes and emits whole
+    // codegen units.
+    ("LLVM IR", &["codegen"]),
+];
+
+const MONO: usize = 3;
+const LLVM_IR: usize = 4;
+
+thread_local! {
+    /// The time spent in the nested timed activities of each activity being timed on this thread.
+    static CHILD_TIMES: RefCell<Vec<Duration>> = RefCell::new(Vec::new());
+}
+
+/// Time spent on each item of the crate, gathered with `-Z item-time-report`. Only the self time
+/// of an activity is attributed to its item: e.g., the time spent type-checking a function doesn't
+/// include the time spent computing the types of the other items it uses.
+#[derive(Default)]
+pub struct ItemTimes {
+    times: Lock<FxHashMap<(DefId, &'static str), Duration>>,
+    /// The instantiations of each generic item that were monomorphized or translated to LLVM IR.
+    instances: Lock<FxHashMap<DefId, FxHashSet<String>>>,
+}
+
+impl ItemTimes {
+    /// Runs `f`, attributing the time it takes, minus the time taken by the activities it starts,
+    /// to `item`.
+    pub fn time<R>(&self, activity: &'static str, item: DefId, f: impl FnOnce() -> R) -> R {
+        CHILD_TIMES.with(|stack| stack.borrow_mut().push(Duration::default()));
+        let start = Instant::now();
+        let result = f();
+        let total = start.elapsed();
+        let child_time = CHILD_TIMES.with(|stack| {
+            let mut stack = stack.borrow_mut();
+            let child_time = stack.pop().unwrap_or_default();
+            if let Some(parent) = stack.last_mut() {
+                *parent += total;
+            }
+            child_time
+        });
+
+        let self_time = total.checked_sub(child_time).unwrap_or_default();
+        *self.times.lock().entry((item, activity)).or_default() += self_time;
+        result
+    }
+
+    /// Like `time`, for an activity working on `instance`. The instantiations of generic items are
+    /// recorded, so that the report can list the generic items which are the most costly to
+    /// instantiate.
+    pub fn time_instance<R>(
+        &self,
+        activity: &'static str,
+        instance: Instance<'_>,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let item = instance.def_id();
+        if instance.substs.non_erasable_generics().next().is_some() {
+            self.instances.lock().entry(item).or_default().insert(instance.to_string());
+        }
+        self.time(activity, item, f)
+    }
+
+    /// Prints the `top` items which took the most time, naming them with `item_name`.
+    pub fn print(&self, top: usize, item_name: impl Fn(DefId) -> String) {
+        let mut items: FxHashMap<DefId, [Duration; 5]> = Default::default();
+        for (&(item, activity), &time) in self.times.lock().iter() {
+            if let Some(i) = CATEGORIES.iter().position(|(_, acts)| acts.contains(&activity)) {
+                items.entry(item).or_default()[i] += time;
+            }
+        }
+
+        let total = |times: &[Duration; 5]| times.iter().sum::<Duration>();
+        let mut sorted: Vec<_> = items.iter().map(|(&item, times)| (item, times)).collect();
+        sorted.sort_by_key(|&(item, times)| (Reverse(total(times)), item));
+
+        println!("item-time-report: top {} of {} items, times in milliseconds",
+                 top.min(sorted.len()), sorted.len());
+        print!("item-time-report: {:>10}", "total");
+        for (name, _) in CATEGORIES {
+            print!(" {:>10}", name);
+        }
+        println!("  item");
+        for &(item, times) in sorted.iter().take(top) {
+            print!("item-time-report: {:>10.3}", millis(total(times)));
+            for &time in times {
+                print!(" {:>10.3}", millis(time));
+            }
+            println!("  {}", item_name(item));
+        }
+
+        let instances = self.instances.lock();
+        let mut generics: Vec<_> = instances.iter().map(|(&item, instances)| {
+            let times = items.get(&item).cloned().unwrap_or_default();
+            (item, instances.len(), times[MONO], times[LLVM_IR])
+        }).collect();
+        if generics.is_empty() {
+            return;
+        }
+        generics.sort_by_key(|&(item, _, _, llvm_ir)| (Reverse(llvm_ir), item));
+
+        let total_llvm_ir: Duration = items.values().map(|times| times[LLVM_IR]).sum();
+        println!("item-time-report:");
+        println!("item-time-report: top {} of {} generic items by LLVM IR generation time \
+                  ({:.3} ms of LLVM IR generation in total)",
+                 top.min(generics.len()), generics.len(), millis(total_llvm_ir));
+        println!("item-time-report: {:>10} {:>10} {:>10} {:>10}  item",
+                 "LLVM IR", "% LLVM IR", "mono", "instances");
+        for &(item, count, mono, llvm_ir) in generics.iter().take(top) {
+            let share = if total_llvm_ir == Duration::default() {
+                0.0
+            } else {
+                llvm_ir.as_nanos() as f64 * 100.0 / total_llvm_ir.as_nanos() as f64
+            };
+            println!("item-time-report: {:>10.3} {:>10.1} {:>10.3} {:>10}  {}",
+                     millis(llvm_ir), share, millis(mono), count, item_name(item));
+        }
+    }
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_nanos() as f64 / 1_000_000.0
+}
@@ -1,5 +1,7 @@
-pub use self::code_stats::{DataTypeKind, SizeKind, FieldInfo, VariantInfo};
+pub use self::code_stats::{DataTypeKind, SizeKind, FieldInfo, VariantInfo, MonoItemInfo};
+pub use self::code_stats::{GeneratorSizeInfo, SavedLocalInfo};
 use self::code_stats::CodeStats;
+use self::item_times::ItemTimes;
 
 use crate::dep_graph::cgu_reuse_tracker::CguReuseTracker;
 use crate::hir::def_id::CrateNum;
@@ -50,6 +52,7 @@ use std::time::Duration;
 use std::sync::{Arc, mpsc};
 
 mod code_stats;
+mod item_times;
 pub mod config;
 pub mod filesearch;
 pub mod search_paths;
@@ -139,6 +142,9 @@ pub struct Session {
     /// Data about code being compiled, gathered during compilation.
     pub code_stats: Lock<CodeStats>,
 
+    /// Time spent on each item, if `-Z item-time-report` is specified.
+    pub item_times: Option<ItemTimes>,
+
     next_node_id: OneThread<Cell<ast::NodeId>>,
 
     /// If `-zfuel=crate=n` is specified, `Some(crate)`.
@@ -153,6 +159,10 @@ pub struct Session {
     /// Always set to zero and incremented so that we can print fuel expended by a crate.
     pub print_fuel: AtomicU64,
 
+    /// The number of MIR optimizations considered so far, if `-Z mir-opt-bisect-limit`
+    /// is specified.
+    mir_opt_bisect_count: AtomicU64,
+
     /// Loaded up early on in the initialization of this `Session` to avoid
     /// false positives about a job server in our environment.
     pub jobserver: Client,
@@ -858,6 +868,15 @@ impl Session {
         }
     }
 
+    /// Writes the reports requested with `-Z self-profile-report`.
+    pub fn write_self_profile_reports(&self) {
+        if let Some(ref profiler) = self.self_profiling {
+            if let Err(e) = profiler.write_reports() {
+                self.warn(&format!("failed to write self-profile reports: {}", e));
+            }
+        }
+    }
+
     pub fn print_perf_stats(&self) {
         println!(
             "Total time spent computing symbol hashes:      {}",
@@ -901,10 +920,31 @@ impl Session {
         ret
     }
 
+    /// Returns whether the next change made by a MIR optimization, described by
+    /// `msg`, should be applied. With `-Z mir-opt-bisect-limit=N`, only the
+    /// first N ones are, and each of them is logged.
+    pub fn consider_mir_optimization<T: Fn() -> String>(&self, msg: T) -> bool {
+        let limit = match self.opts.debugging_opts.mir_opt_bisect_limit {
+            Some(limit) => limit as u64,
+            None => return true,
+        };
+        assert_eq!(self.threads(), 1);
+        let count = self.mir_opt_bisect_count.fetch_add(1, SeqCst) + 1;
+        let apply = count <= limit;
+        eprintln!(
+            "mir-opt-bisect: {} optimization ({}) {}",
+            if apply { "applying" } else { "NOT applying" },
+            count,
+            msg(),
+        );
+        apply
+    }
+
     /// Returns the number of query threads that should be used for this
-    /// compilation
+    /// compilation. The front-end only runs in parallel when asked to with
+    /// `-Z threads`.
     pub fn threads_from_count(query_threads: Option<usize>) -> usize {
-        query_threads.unwrap_or(::num_cpus::get())
+        query_threads.unwrap_or(1)
     }
 
     /// Returns the number of query threads that should be used for this
@@ -1149,8 +1189,15 @@ fn build_session_(
     source_map: Lrc<source_map::SourceMap>,
     driver_lint_caps: FxHashMap<lint::LintId, lint::Level>,
 ) -> Session {
+    let self_profile = match sopts.debugging_opts.self_profile {
+        // Asking for a report is enough to run the profiler.
+        SwitchWithOptPath::Disabled if sopts.debugging_opts.self_profile_report.is_some() => {
+            SwitchWithOptPath::Enabled(None)
+        }
+        ref self_profile => self_profile.clone(),
+    };
     let self_profiler =
-        if let SwitchWithOptPath::Enabled(ref d) = sopts.debugging_opts.self_profile {
+        if let SwitchWithOptPath::Enabled(ref d) = self_profile {
             let directory = if let Some(ref directory) = d {
                 directory
             } else {
@@ -1160,7 +1207,8 @@ fn build_session_(
             let profiler = SelfProfiler::new(
                 directory,
                 sopts.crate_name.as_ref().map(|s| &s[..]),
-                &sopts.debugging_opts.self_profile_events
+                &sopts.debugging_opts.self_profile_events,
+                &sopts.debugging_opts.self_profile_report,
             );
             match profiler {
                 Ok(profiler) => {
@@ -1210,6 +1258,7 @@ fn build_session_(
     });
     let print_fuel_crate = sopts.debugging_opts.print_fuel.clone();
     let print_fuel = AtomicU64::new(0);
+    let mir_opt_bisect_count = AtomicU64::new(0);
 
     let working_dir = env::current_dir().unwrap_or_else(|e|
         p_s.span_diagnostic
@@ -1224,6 +1273,12 @@ fn build_session_(
         CguReuseTracker::new_disabled()
     };
 
+    let item_times = if sopts.debugging_opts.item_time_report.is_some() {
+        Some(ItemTimes::default())
+    } else {
+        None
+    };
+
     let sess = Session {
         target: target_cfg,
         host,
@@ -1262,10 +1317,12 @@ fn build_session_(
             normalize_projection_ty: AtomicUsize::new(0),
         },
         code_stats: Default::default(),
+        item_times,
         optimization_fuel_crate,
         optimization_fuel,
         print_fuel_crate,
         print_fuel,
+        mir_opt_bisect_count,
         jobserver: jobserver::client(),
         has_global_allocator: Once::new(),
         has_panic_handler: Once::new(),
@@ -374,7 +374,7 @@ enum BuiltinImplConditions<'tcx> {
     Ambiguous,
 }
 
-#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, RustcEncodable, RustcDecodable)]
 /// The result of trait evaluation. The order is important
 /// here as the evaluation of a list is the maximum of the
 /// evaluations.
@@ -494,7 +494,7 @@ impl_stable_hash_for!(enum self::EvaluationResult {
     EvaluatedToErr
 });
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable)]
 /// Indicates that trait evaluation caused overflow.
 pub struct OverflowError;
 
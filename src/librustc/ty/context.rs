@@ -1423,6 +1423,19 @@ impl<'tcx> TyCtxt<'tcx> {
         self.queries.on_disk_cache.serialize(self.global_tcx(), encoder)
     }
 
+    pub fn print_query_result_cache_stats(self) {
+        self.queries.on_disk_cache.print_stats()
+    }
+
+    /// Returns the number of bytes allocated by each of the arenas of this
+    /// context, by name. With a parallel compiler, only the arenas of the
+    /// current worker thread are included.
+    pub fn arena_allocated_bytes(self) -> Vec<(&'static str, usize)> {
+        let mut arenas = self.arena.allocated_bytes();
+        arenas.push(("interners", self.interners.arena.allocated_bytes()));
+        arenas
+    }
+
     /// If true, we should use the AST-based borrowck (we may *also* use
     /// the MIR-based borrowck).
     pub fn use_ast_borrowck(self) -> bool {
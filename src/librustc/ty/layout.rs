@@ -17,7 +17,6 @@ use crate::ich::StableHashingContext;
 use crate::mir::{GeneratorLayout, GeneratorSavedLocal};
 use crate::ty::GeneratorSubsts;
 use crate::ty::subst::Subst;
-use rustc_data_structures::bit_set::BitSet;
 use rustc_data_structures::indexed_vec::{IndexVec, Idx};
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher,
                                            StableHasherResult};
@@ -1273,133 +1272,68 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
     }
 }
 
-/// Overlap eligibility and variant assignment for each GeneratorSavedLocal.
-#[derive(Clone, Debug, PartialEq)]
-enum SavedLocalEligibility {
-    Unassigned,
-    Assigned(VariantIdx),
-    // FIXME: Use newtype_index so we aren't wasting bytes
-    Ineligible(Option<u32>),
-}
-
-// When laying out generators, we divide our saved local fields into two
-// categories: overlap-eligible and overlap-ineligible.
-//
-// Those fields which are ineligible for overlap go in a "prefix" at the
-// beginning of the layout, and always have space reserved for them.
-//
-// Overlap-eligible fields are only assigned to one variant, so we lay
-// those fields out for each variant and put them right after the
-// prefix.
+// When laying out generators, the upvars and the discriminant go in a "prefix", which is
+// shared by all the variants. The saved locals are then the fields of the variants of the
+// suspension points they are live at.
 //
-// Finally, in the layout details, we point to the fields from the
-// variants they are assigned to. It is possible for some fields to be
-// included in multiple variants. No field ever "moves around" in the
-// layout; its offset is always the same.
-//
-// Also included in the layout are the upvars and the discriminant.
-// These are included as fields on the "outer" layout; they are not part
-// of any variant.
+// Each saved local has a single offset, the same in all the variants it is a field of: no
+// field ever "moves around" in the layout. Two saved locals only get overlapping offsets if
+// their storage is never live at the same time, which rules out two locals of the same
+// variant. This way, locals live at different suspension points share their bytes even if
+// they are live at several of them, and the size of a generator is not the sum of the sizes
+// of all the locals that are ever live across a suspension point.
 impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
-    /// Compute the eligibility and assignment of each local.
-    fn generator_saved_local_eligibility(&self, info: &GeneratorLayout<'tcx>)
-    -> (BitSet<GeneratorSavedLocal>, IndexVec<GeneratorSavedLocal, SavedLocalEligibility>) {
-        use SavedLocalEligibility::*;
-
-        let mut assignments: IndexVec<GeneratorSavedLocal, SavedLocalEligibility> =
-            IndexVec::from_elem_n(Unassigned, info.field_tys.len());
-
-        // The saved locals not eligible for overlap. These will get
-        // "promoted" to the prefix of our generator.
-        let mut ineligible_locals = BitSet::new_empty(info.field_tys.len());
-
-        // Figure out which of our saved locals are fields in only
-        // one variant. The rest are deemed ineligible for overlap.
-        for (variant_index, fields) in info.variant_fields.iter_enumerated() {
-            for local in fields {
-                match assignments[*local] {
-                    Unassigned => {
-                        assignments[*local] = Assigned(variant_index);
-                    }
-                    Assigned(idx) => {
-                        // We've already seen this local at another suspension
-                        // point, so it is no longer a candidate.
-                        trace!("removing local {:?} in >1 variant ({:?}, {:?})",
-                               local, variant_index, idx);
-                        ineligible_locals.insert(*local);
-                        assignments[*local] = Ineligible(None);
-                    }
-                    Ineligible(_) => {},
+    /// Computes the offset of each saved local, given the ranges taken by the prefix fields,
+    /// which no local can overlap.
+    fn generator_saved_local_offsets(
+        &self,
+        info: &GeneratorLayout<'tcx>,
+        local_layouts: &IndexVec<GeneratorSavedLocal, TyLayout<'tcx>>,
+        prefix_ranges: &[(Size, Size)],
+    ) -> IndexVec<GeneratorSavedLocal, Size> {
+        // The locals of a variant are all live at its suspension point, so they should
+        // already conflict. Make sure of it, as overlapping them would be a miscompilation.
+        let mut conflicts = info.storage_conflicts.clone();
+        for fields in &info.variant_fields {
+            for &local_a in fields {
+                for &local_b in fields {
+                    conflicts.insert(local_a, local_b);
                 }
             }
         }
 
-        // Next, check every pair of eligible locals to see if they
-        // conflict.
-        for local_a in info.storage_conflicts.rows() {
-            let conflicts_a = info.storage_conflicts.count(local_a);
-            if ineligible_locals.contains(local_a) {
-                continue;
-            }
-
-            for local_b in info.storage_conflicts.iter(local_a) {
-                // local_a and local_b are storage live at the same time, therefore they
-                // cannot overlap in the generator layout. The only way to guarantee
-                // this is if they are in the same variant, or one is ineligible
-                // (which means it is stored in every variant).
-                if ineligible_locals.contains(local_b) ||
-                    assignments[local_a] == assignments[local_b]
-                {
-                    continue;
-                }
-
-                // If they conflict, we will choose one to make ineligible.
-                // This is not always optimal; it's just a greedy heuristic that
-                // seems to produce good results most of the time.
-                let conflicts_b = info.storage_conflicts.count(local_b);
-                let (remove, other) = if conflicts_a > conflicts_b {
-                    (local_a, local_b)
-                } else {
-                    (local_b, local_a)
-                };
-                ineligible_locals.insert(remove);
-                assignments[remove] = Ineligible(None);
-                trace!("removing local {:?} due to conflict with {:?}", remove, other);
-            }
-        }
+        // Place the locals with the largest alignment first, and the largest ones first
+        // among them, so that locals which all conflict with each other are packed without
+        // padding, as in a struct. Every local then goes at the lowest offset at which it
+        // doesn't overlap with a local it conflicts with.
+        let mut order: Vec<_> = local_layouts.indices().collect();
+        order.sort_by_key(|&local| {
+            let layout = local_layouts[local];
+            (cmp::Reverse(layout.align.abi), cmp::Reverse(layout.size))
+        });
 
-        // Count the number of variants in use. If only one of them, then it is
-        // impossible to overlap any locals in our layout. In this case it's
-        // always better to make the remaining locals ineligible, so we can
-        // lay them out with the other locals in the prefix and eliminate
-        // unnecessary padding bytes.
-        {
-            let mut used_variants = BitSet::new_empty(info.variant_fields.len());
-            for assignment in &assignments {
-                match assignment {
-                    Assigned(idx) => { used_variants.insert(*idx); }
-                    _ => {}
-                }
-            }
-            if used_variants.count() < 2 {
-                for assignment in assignments.iter_mut() {
-                    *assignment = Ineligible(None);
+        let mut offsets: IndexVec<GeneratorSavedLocal, Option<Size>> =
+            IndexVec::from_elem_n(None, local_layouts.len());
+        for local in order {
+            let layout = local_layouts[local];
+            let mut taken = prefix_ranges.to_vec();
+            taken.extend(conflicts.iter(local).filter_map(|other| {
+                offsets[other].map(|offset| (offset, offset + local_layouts[other].size))
+            }));
+            taken.sort();
+
+            // As the ranges are sorted by their start, the ones skipped before moving
+            // `offset` forward end before it.
+            let mut offset = Size::ZERO;
+            for &(start, end) in &taken {
+                if start < end && start < offset + layout.size && offset < end {
+                    offset = end.align_to(layout.align.abi);
                 }
-                ineligible_locals.insert_all();
-            }
-        }
-
-        // Write down the order of our locals that will be promoted to the prefix.
-        {
-            let mut idx = 0u32;
-            for local in ineligible_locals.iter() {
-                assignments[local] = Ineligible(Some(idx));
-                idx += 1;
             }
+            trace!("saved local {:?} at offset {:?}", local, offset);
+            offsets[local] = Some(offset);
         }
-        debug!("generator saved local assignments: {:?}", assignments);
-
-        (ineligible_locals, assignments)
+        offsets.into_iter().map(|offset| offset.unwrap()).collect()
     }
 
     /// Compute the full generator layout.
@@ -1409,17 +1343,13 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
         def_id: hir::def_id::DefId,
         substs: &GeneratorSubsts<'tcx>,
     ) -> Result<&'tcx LayoutDetails, LayoutError<'tcx>> {
-        use SavedLocalEligibility::*;
         let tcx = self.tcx;
 
         let subst_field = |ty: Ty<'tcx>| { ty.subst(tcx, substs.substs) };
 
         let info = tcx.generator_layout(def_id);
-        let (ineligible_locals, assignments) = self.generator_saved_local_eligibility(&info);
 
-        // Build a prefix layout, including "promoting" all ineligible
-        // locals as part of the prefix. We compute the layout of all of
-        // these fields at once to get optimal packing.
+        // Build the prefix layout, with the upvars and the discriminant.
         let discr_index = substs.prefix_tys(def_id, tcx).count();
         // FIXME(eddyb) set the correct vaidity range for the discriminant.
         let discr_layout = self.layout_of(substs.discr_ty(tcx))?;
@@ -1427,14 +1357,9 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
             Abi::Scalar(s) => s.clone(),
             _ => bug!(),
         };
-        let promoted_layouts = ineligible_locals.iter()
-            .map(|local| subst_field(info.field_tys[local]))
-            .map(|ty| tcx.mk_maybe_uninit(ty))
-            .map(|ty| self.layout_of(ty));
         let prefix_layouts = substs.prefix_tys(def_id, tcx)
             .map(|ty| self.layout_of(ty))
             .chain(iter::once(Ok(discr_layout)))
-            .chain(promoted_layouts)
             .collect::<Result<Vec<_>, _>>()?;
         let prefix = self.univariant_uninterned(
             ty,
@@ -1442,121 +1367,54 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
             &ReprOptions::default(),
             StructKind::AlwaysSized,
         )?;
-
-        let (prefix_size, prefix_align) = (prefix.size, prefix.align);
-
-        // Split the prefix layout into the "outer" fields (upvars and
-        // discriminant) and the "promoted" fields. Promoted fields will
-        // get included in each variant that requested them in
-        // GeneratorLayout.
         debug!("prefix = {:#?}", prefix);
-        let (outer_fields, promoted_offsets, promoted_memory_index) = match prefix.fields {
-            FieldPlacement::Arbitrary { mut offsets, memory_index } => {
-                let mut inverse_memory_index = invert_mapping(&memory_index);
-
-                // "a" (`0..b_start`) and "b" (`b_start..`) correspond to
-                // "outer" and "promoted" fields respectively.
-                let b_start = (discr_index + 1) as u32;
-                let offsets_b = offsets.split_off(b_start as usize);
-                let offsets_a = offsets;
-
-                // Disentangle the "a" and "b" components of `inverse_memory_index`
-                // by preserving the order but keeping only one disjoint "half" each.
-                // FIXME(eddyb) build a better abstraction for permutations, if possible.
-                let inverse_memory_index_b: Vec<_> =
-                    inverse_memory_index.iter().filter_map(|&i| i.checked_sub(b_start)).collect();
-                inverse_memory_index.retain(|&i| i < b_start);
-                let inverse_memory_index_a = inverse_memory_index;
-
-                // Since `inverse_memory_index_{a,b}` each only refer to their
-                // respective fields, they can be safely inverted
-                let memory_index_a = invert_mapping(&inverse_memory_index_a);
-                let memory_index_b = invert_mapping(&inverse_memory_index_b);
-
-                let outer_fields = FieldPlacement::Arbitrary {
-                    offsets: offsets_a,
-                    memory_index: memory_index_a,
-                };
-                (outer_fields, offsets_b, memory_index_b)
-            }
-            _ => bug!(),
-        };
+        let prefix_ranges: Vec<_> = prefix_layouts.iter().enumerate().map(|(i, layout)| {
+            let offset = prefix.fields.offset(i);
+            (offset, offset + layout.size)
+        }).collect();
+
+        let local_layouts = info.field_tys.iter()
+            .map(|&ty| self.layout_of(subst_field(ty)))
+            .collect::<Result<IndexVec<GeneratorSavedLocal, _>, _>>()?;
+        let local_offsets = self.generator_saved_local_offsets(
+            &info,
+            &local_layouts,
+            &prefix_ranges,
+        );
+        debug!("generator saved local offsets: {:?}", local_offsets);
 
         let mut size = prefix.size;
         let mut align = prefix.align;
         let variants = info.variant_fields.iter_enumerated().map(|(index, variant_fields)| {
-            // Only include overlap-eligible fields when we compute our variant layout.
-            let variant_only_tys = variant_fields
-                .iter()
-                .filter(|local| {
-                    match assignments[**local] {
-                        Unassigned => bug!(),
-                        Assigned(v) if v == index => true,
-                        Assigned(_) => bug!("assignment does not match variant"),
-                        Ineligible(_) => false,
-                    }
-                })
-                .map(|local| subst_field(info.field_tys[*local]));
-
-            let mut variant = self.univariant_uninterned(
-                ty,
-                &variant_only_tys
-                    .map(|ty| self.layout_of(ty))
-                    .collect::<Result<Vec<_>, _>>()?,
-                &ReprOptions::default(),
-                StructKind::Prefixed(prefix_size, prefix_align.abi))?;
-            variant.variants = Variants::Single { index };
-
-            let (offsets, memory_index) = match variant.fields {
-                FieldPlacement::Arbitrary { offsets, memory_index } => {
-                    (offsets, memory_index)
-                }
-                _ => bug!(),
-            };
-
-            // Now, stitch the promoted and variant-only fields back together in
-            // the order they are mentioned by our GeneratorLayout.
-            // Because we only use some subset (that can differ between variants)
-            // of the promoted fields, we can't just pick those elements of the
-            // `promoted_memory_index` (as we'd end up with gaps).
-            // So instead, we build an "inverse memory_index", as if all of the
-            // promoted fields were being used, but leave the elements not in the
-            // subset as `INVALID_FIELD_IDX`, which we can filter out later to
-            // obtain a valid (bijective) mapping.
-            const INVALID_FIELD_IDX: u32 = !0;
-            let mut combined_inverse_memory_index =
-                vec![INVALID_FIELD_IDX; promoted_memory_index.len() + memory_index.len()];
-            let mut offsets_and_memory_index = offsets.into_iter().zip(memory_index);
-            let combined_offsets = variant_fields.iter().enumerate().map(|(i, local)| {
-                let (offset, memory_index) = match assignments[*local] {
-                    Unassigned => bug!(),
-                    Assigned(_) => {
-                        let (offset, memory_index) = offsets_and_memory_index.next().unwrap();
-                        (offset, promoted_memory_index.len() as u32 + memory_index)
-                    }
-                    Ineligible(field_idx) => {
-                        let field_idx = field_idx.unwrap() as usize;
-                        (promoted_offsets[field_idx], promoted_memory_index[field_idx])
-                    }
-                };
-                combined_inverse_memory_index[memory_index as usize] = i as u32;
+            let mut variant_size = prefix.size;
+            let mut variant_align = prefix.align;
+            let mut uninhabited = false;
+            let offsets: Vec<_> = variant_fields.iter().map(|&local| {
+                let layout = local_layouts[local];
+                let offset = local_offsets[local];
+                variant_size = variant_size.max(offset + layout.size);
+                variant_align = variant_align.max(layout.align);
+                uninhabited |= layout.abi.is_uninhabited();
                 offset
             }).collect();
 
-            // Remove the unused slots and invert the mapping to obtain the
-            // combined `memory_index` (also see previous comment).
-            combined_inverse_memory_index.retain(|&i| i != INVALID_FIELD_IDX);
-            let combined_memory_index = invert_mapping(&combined_inverse_memory_index);
-
-            variant.fields = FieldPlacement::Arbitrary {
-                offsets: combined_offsets,
-                memory_index: combined_memory_index,
-            };
+            // The fields of a variant never overlap, so ordering them by offset gives their
+            // order in memory.
+            let mut inverse_memory_index: Vec<u32> = (0..offsets.len() as u32).collect();
+            inverse_memory_index.sort_by_key(|&i| offsets[i as usize]);
+            let memory_index = invert_mapping(&inverse_memory_index);
 
-            size = size.max(variant.size);
-            align = align.max(variant.align);
-            Ok(variant)
-        }).collect::<Result<IndexVec<VariantIdx, _>, _>>()?;
+            size = size.max(variant_size);
+            align = align.max(variant_align);
+            LayoutDetails {
+                variants: Variants::Single { index },
+                fields: FieldPlacement::Arbitrary { offsets, memory_index },
+                abi: if uninhabited { Abi::Uninhabited } else { Abi::Aggregate { sized: true } },
+                largest_niche: None,
+                size: variant_size.align_to(variant_align.abi),
+                align: variant_align,
+            }
+        }).collect::<IndexVec<VariantIdx, _>>();
 
         size = size.align_to(align.abi);
 
@@ -1574,7 +1432,7 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
                 discr_index,
                 variants,
             },
-            fields: outer_fields,
+            fields: prefix.fields,
             abi,
             largest_niche: prefix.largest_niche,
             size,
@@ -1615,7 +1473,8 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
                                                                    layout.size,
                                                                    packed,
                                                                    opt_discr_size,
-                                                                   variants);
+                                                                   variants,
+                                                                   vec![]);
         };
 
         let adt_def = match layout.ty.sty {
@@ -1630,6 +1489,12 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
                 return;
             }
 
+            ty::Generator(def_id, substs, _) => {
+                debug!("print-type-size t: `{:?}` record generator", layout.ty);
+                self.record_generator_layout_for_printing(layout, def_id, substs);
+                return;
+            }
+
             _ => {
                 debug!("print-type-size t: `{:?}` skip non-nominal", layout.ty);
                 return;
@@ -1721,6 +1586,111 @@ impl<'tcx> LayoutCx<'tcx, TyCtxt<'tcx>> {
             }
         }
     }
+
+    /// Records the layout of a generator, with a variant for each suspension point, and the
+    /// offset, size and suspension points of each of its saved locals.
+    fn record_generator_layout_for_printing(
+        &self,
+        layout: TyLayout<'tcx>,
+        def_id: hir::def_id::DefId,
+        substs: GeneratorSubsts<'tcx>,
+    ) {
+        let info = self.generator_size_info(layout, def_id, substs);
+        let type_desc = format!("{:?}", layout.ty);
+        self.tcx.sess.code_stats.borrow_mut().record_type_size(
+            DataTypeKind::Generator,
+            type_desc,
+            layout.align.abi,
+            layout.size,
+            false,
+            Some(info.discr_size),
+            info.variants,
+            info.saved_locals,
+        );
+    }
+
+    /// Breaks the layout of a generator down into its upvars and saved locals, as shown by
+    /// `-Z print-type-sizes`.
+    pub fn generator_size_info(
+        &self,
+        layout: TyLayout<'tcx>,
+        def_id: hir::def_id::DefId,
+        substs: GeneratorSubsts<'tcx>,
+    ) -> session::GeneratorSizeInfo {
+        let tcx = self.tcx;
+        let info = tcx.generator_layout(def_id);
+        let (discr_size, discr_index) = match layout.variants {
+            Variants::Multiple { ref discr, discr_index, .. } => {
+                (discr.value.size(self), discr_index)
+            }
+            Variants::Single { .. } => bug!("generator without a discriminant: {:#?}", layout),
+        };
+        let discr_end = layout.fields.offset(discr_index) + discr_size;
+
+        let field_info = |layout: TyLayout<'tcx>, i: usize, name: String| {
+            let field_layout = layout.field(self, i).unwrap_or_else(|err| {
+                bug!("no layout found for field {}: `{:?}`", name, err)
+            });
+            session::FieldInfo {
+                name,
+                offset: layout.fields.offset(i).bytes(),
+                size: field_layout.size.bytes(),
+                align: field_layout.align.abi.bytes(),
+            }
+        };
+
+        // The upvars are in the prefix, so they are part of every variant.
+        let upvars: Vec<_> = tcx.upvars(def_id).iter()
+            .flat_map(|upvars| upvars.iter())
+            .enumerate()
+            .map(|(i, (&var_id, upvar))| {
+                (field_info(layout, i, tcx.hir().name(var_id).to_string()), upvar.span)
+            })
+            .collect();
+
+        let mut saved_locals: IndexVec<GeneratorSavedLocal, Option<session::SavedLocalInfo>> =
+            IndexVec::from_elem_n(None, info.field_tys.len());
+        let variants = info.variant_fields.iter_enumerated().map(|(index, fields)| {
+            let variant_name = substs.variant_name(index).into_owned();
+            let variant_layout = layout.for_variant(self, index);
+            let mut field_infos: Vec<_> = upvars.iter().map(|(field, _)| field.clone()).collect();
+            for (i, &local) in fields.iter().enumerate() {
+                let decl = &info.__local_debuginfo_codegen_only_do_not_use[local];
+                let name = match decl.name {
+                    Some(name) => name.to_string(),
+                    None => format!("__{}", local.as_usize()),
+                };
+                let field = field_info(variant_layout, i, name);
+                saved_locals[local].get_or_insert_with(|| session::SavedLocalInfo {
+                    name: field.name.clone(),
+                    offset: field.offset,
+                    size: field.size,
+                    align: field.align,
+                    span: decl.source_info.span,
+                    live_at: vec![],
+                }).live_at.push(variant_name.clone());
+                field_infos.push(field);
+            }
+
+            let size = field_infos.iter()
+                .map(|field| field.offset + field.size)
+                .fold(discr_end.bytes(), cmp::max);
+            session::VariantInfo {
+                name: Some(variant_name),
+                kind: session::SizeKind::Exact,
+                align: variant_layout.align.abi.bytes(),
+                size,
+                fields: field_infos,
+            }
+        }).collect();
+
+        session::GeneratorSizeInfo {
+            discr_size,
+            variants,
+            upvars,
+            saved_locals: saved_locals.into_iter().filter_map(|local| local).collect(),
+        }
+    }
 }
 
 /// Type size "skeleton", i.e., the only information determining a type's size.
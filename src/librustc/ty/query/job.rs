@@ -11,6 +11,7 @@ use std::ptr;
 
 #[cfg(parallel_compiler)]
 use {
+    errors::{DiagnosticBuilder, Level},
     parking_lot::{Mutex, Condvar},
     rustc_data_structures::{jobserver, OnDrop},
     rustc_data_structures::fx::FxHashSet,
@@ -478,6 +479,30 @@ pub unsafe fn handle_deadlock() {
     });
 }
 
+/// Reports a deadlock which isn't caused by a query cycle, which is a compiler
+/// bug, along with the queries that were being computed. The threads of the
+/// pool are stuck, so the process exits without unwinding them.
+#[cfg(parallel_compiler)]
+fn report_deadlock(tcx: TyCtxt<'_>, jobs: &[Lrc<QueryJob<'_>>]) -> ! {
+    let mut err = DiagnosticBuilder::new(
+        tcx.sess.diagnostic(),
+        Level::Bug,
+        "the compiler deadlocked while running queries in parallel",
+    );
+    for job in jobs {
+        let note = format!("waiting while {}", job.info.query.describe(tcx));
+        if job.info.span == DUMMY_SP {
+            err.note(&note);
+        } else {
+            err.span_note(job.info.span, &note);
+        }
+    }
+    err.note("this is a bug in the parallel front-end, compiling with `-Z threads=1` \
+              avoids it");
+    err.emit();
+    process::exit(101)
+}
+
 /// Detects query cycles by using depth first search over all active query jobs.
 /// If a query cycle is found it will break the cycle by finding an edge which
 /// uses a query latch and then resuming that waiter.
@@ -492,6 +517,7 @@ fn deadlock(tcx: TyCtxt<'_>, registry: &rayon_core::Registry) {
 
     let mut wakelist = Vec::new();
     let mut jobs: Vec<_> = tcx.queries.collect_active_jobs();
+    let active_jobs = jobs.clone();
 
     let mut found_cycle = false;
 
@@ -508,7 +534,9 @@ fn deadlock(tcx: TyCtxt<'_>, registry: &rayon_core::Registry) {
     // which in turn will wait on X causing a deadlock. We have a false dependency from
     // X to Y due to Rayon waiting and a true dependency from Y to X. The algorithm here
     // only considers the true dependency and won't detect a cycle.
-    assert!(found_cycle);
+    if !found_cycle {
+        report_deadlock(tcx, &active_jobs);
+    }
 
     // FIXME: Ensure this won't cause a deadlock before we return
     for waiter in wakelist.into_iter() {
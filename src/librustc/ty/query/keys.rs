@@ -23,6 +23,12 @@ pub(super) trait Key: Clone + Hash + Eq + Debug {
     /// In the event that a cycle occurs, if no explicit span has been
     /// given for a query with key `self`, what span should we use?
     fn default_span(&self, tcx: TyCtxt<'_>) -> Span;
+
+    /// Which item should `-Z item-time-report` charge the time spent
+    /// computing the query for `self` to?
+    fn attributed_item(&self) -> Option<DefId> {
+        None
+    }
 }
 
 impl<'tcx> Key for ty::InstanceDef<'tcx> {
@@ -80,6 +86,9 @@ impl Key for DefId {
     fn default_span(&self, tcx: TyCtxt<'_>) -> Span {
         tcx.def_span(*self)
     }
+    fn attributed_item(&self) -> Option<DefId> {
+        Some(*self)
+    }
 }
 
 impl Key for (DefId, DefId) {
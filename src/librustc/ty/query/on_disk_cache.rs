@@ -19,7 +19,9 @@ use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::thin_vec::ThinVec;
 use rustc_data_structures::sync::{Lrc, Lock, HashMapExt, Once};
 use rustc_data_structures::indexed_vec::{IndexVec, Idx};
+use std::cmp::Reverse;
 use std::mem;
+use std::time::Duration;
 use syntax::ast::NodeId;
 use syntax::source_map::{SourceMap, StableSourceFileId};
 use syntax_pos::{BytePos, Span, DUMMY_SP, SourceFile};
@@ -69,6 +71,24 @@ pub struct OnDiskCache<'sess> {
     prev_diagnostics_index: FxHashMap<SerializedDepNodeIndex, AbsoluteBytePos>,
 
     alloc_decoding_state: AllocDecodingState,
+
+    // Statistics gathered with `-Z incremental-cache-stats`, by query name.
+    stats: Lock<FxHashMap<&'static str, QueryCacheStats>>,
+}
+
+/// How the results of a query were obtained when the query was green, and
+/// how much space they take in the cache of the next session.
+#[derive(Default)]
+struct QueryCacheStats {
+    /// Results loaded from the on-disk cache, and the time spent decoding them.
+    loaded: usize,
+    load_time: Duration,
+    /// Results which had to be recomputed because they weren't in the cache.
+    recomputed: usize,
+    recompute_time: Duration,
+    /// Results written to the cache for the next session, and their size.
+    saved: usize,
+    saved_bytes: usize,
 }
 
 // This type is used only for (de-)serialization.
@@ -137,6 +157,7 @@ impl<'sess> OnDiskCache<'sess> {
             prev_diagnostics_index: footer.diagnostics_index.into_iter().collect(),
             synthetic_syntax_contexts: Default::default(),
             alloc_decoding_state: AllocDecodingState::new(footer.interpret_alloc_index),
+            stats: Default::default(),
         }
     }
 
@@ -153,6 +174,49 @@ impl<'sess> OnDiskCache<'sess> {
             prev_diagnostics_index: Default::default(),
             synthetic_syntax_contexts: Default::default(),
             alloc_decoding_state: AllocDecodingState::new(Vec::new()),
+            stats: Default::default(),
+        }
+    }
+
+    /// Records that a green result of `query` was loaded from the cache.
+    pub(super) fn record_load(&self, query: &'static str, time: Duration) {
+        let mut stats = self.stats.lock();
+        let stats = stats.entry(query).or_default();
+        stats.loaded += 1;
+        stats.load_time += time;
+    }
+
+    /// Records that a green result of `query` wasn't cached and was recomputed.
+    pub(super) fn record_recompute(&self, query: &'static str, time: Duration) {
+        let mut stats = self.stats.lock();
+        let stats = stats.entry(query).or_default();
+        stats.recomputed += 1;
+        stats.recompute_time += time;
+    }
+
+    /// Prints, for each query, the time spent loading green results from the
+    /// cache and recomputing the ones that weren't cached, along with the size
+    /// of the results saved for the next session. Must be called after
+    /// `serialize`.
+    pub fn print_stats(&self) {
+        let stats = self.stats.lock();
+        let mut queries: Vec<_> = stats.iter().collect();
+        queries.sort_by_key(|&(name, stats)| {
+            (Reverse(stats.load_time + stats.recompute_time), *name)
+        });
+
+        let millis = |time: Duration| time.as_nanos() as f64 / 1_000_000.0;
+        println!("incremental-cache-stats: {:>8} {:>10} {:>10} {:>12} {:>8} {:>10}  query",
+                 "loaded", "load ms", "recomputed", "recompute ms", "saved", "saved KiB");
+        for (name, stats) in queries {
+            println!("incremental-cache-stats: {:>8} {:>10.3} {:>10} {:>12.3} {:>8} {:>10.1}  {}",
+                     stats.loaded,
+                     millis(stats.load_time),
+                     stats.recomputed,
+                     millis(stats.recompute_time),
+                     stats.saved,
+                     stats.saved_bytes as f64 / 1024.0,
+                     name);
         }
     }
 
@@ -170,11 +234,20 @@ impl<'sess> OnDiskCache<'sess> {
                 let mut file_index_to_stable_id = FxHashMap::with_capacity_and_hasher(
                     files.len(), Default::default());
 
+                // With `-Z incremental-shared`, the cache can be loaded from
+                // another checkout of the sources.
+                let relocatable = tcx.sess.opts.debugging_opts.incremental_shared;
+
                 for (index, file) in files.iter().enumerate() {
                     let index = SourceFileIndex(index as u32);
                     let file_ptr: *const SourceFile = &**file as *const _;
                     file_to_file_index.insert(file_ptr, index);
-                    file_index_to_stable_id.insert(index, StableSourceFileId::new(&file));
+                    let stable_id = if relocatable {
+                        StableSourceFileId::new_relocatable(&file)
+                    } else {
+                        StableSourceFileId::new(&file)
+                    };
+                    file_index_to_stable_id.insert(index, stable_id);
                 }
 
                 (file_to_file_index, file_index_to_stable_id)
@@ -449,6 +522,7 @@ struct CacheDecoder<'a, 'tcx> {
 impl<'a, 'tcx> CacheDecoder<'a, 'tcx> {
     fn file_index_to_file(&self, index: SourceFileIndex) -> Lrc<SourceFile> {
         let CacheDecoder {
+            tcx,
             ref file_index_to_file,
             ref file_index_to_stable_id,
             ref source_map,
@@ -457,8 +531,12 @@ impl<'a, 'tcx> CacheDecoder<'a, 'tcx> {
 
         file_index_to_file.borrow_mut().entry(index).or_insert_with(|| {
             let stable_id = file_index_to_stable_id[&index];
-            source_map.source_file_by_stable_id(stable_id)
-                .expect("Failed to lookup SourceFile in new context.")
+            let source_file = if tcx.sess.opts.debugging_opts.incremental_shared {
+                source_map.source_file_by_relocatable_id(stable_id)
+            } else {
+                source_map.source_file_by_stable_id(stable_id)
+            };
+            source_file.expect("Failed to lookup SourceFile in new context.")
         }).clone()
     }
 }
@@ -1060,6 +1138,8 @@ where
     time_ext(tcx.sess.time_extended(), Some(tcx.sess), desc, || {
         let shards = Q::query_cache(tcx).lock_shards();
         assert!(shards.iter().all(|shard| shard.active.is_empty()));
+        let start_pos = encoder.position();
+        let mut saved = 0;
         for (key, entry) in shards.iter().flat_map(|shard| shard.results.iter()) {
             if Q::cache_on_disk(tcx, key.clone(), Some(&entry.value)) {
                 let dep_node = SerializedDepNodeIndex::new(entry.index.index());
@@ -1070,9 +1150,17 @@ where
                 // Encode the type check tables with the SerializedDepNodeIndex
                 // as tag.
                 encoder.encode_tagged(dep_node, &entry.value)?;
+                saved += 1;
             }
         }
 
+        if tcx.sess.opts.debugging_opts.incremental_cache_stats && saved > 0 {
+            let mut stats = tcx.queries.on_disk_cache.stats.lock();
+            let stats = stats.entry(Q::NAME.as_str()).or_default();
+            stats.saved += saved;
+            stats.saved_bytes += encoder.position() - start_pos;
+        }
+
         Ok(())
     })
 }
@@ -10,6 +10,7 @@ use crate::ty::query::config::{QueryConfig, QueryDescription};
 use crate::ty::query::job::{QueryJob, QueryResult, QueryInfo};
 
 use crate::util::common::{profq_msg, ProfileQueriesMsg, QueryMsg};
+use crate::util::memory_limit;
 
 use errors::DiagnosticBuilder;
 use errors::Level;
@@ -24,6 +25,7 @@ use rustc_data_structures::cold_path;
 use std::mem;
 use std::ptr;
 use std::collections::hash_map::Entry;
+use std::time::Instant;
 use syntax_pos::Span;
 use syntax::source_map::DUMMY_SP;
 
@@ -274,7 +276,9 @@ impl<'tcx> TyCtxt<'tcx> {
 
             // Use the ImplicitCtxt while we execute the query
             tls::enter_context(&new_icx, |_| {
-                compute(self.global_tcx())
+                let result = compute(self.global_tcx());
+                memory_limit::check(self.sess);
+                result
             })
         })
     }
@@ -445,6 +449,12 @@ impl<'tcx> TyCtxt<'tcx> {
 
         debug_assert!(self.dep_graph.is_green(dep_node));
 
+        let start = if unlikely!(self.sess.opts.debugging_opts.incremental_cache_stats) {
+            Some(Instant::now())
+        } else {
+            None
+        };
+
         // First we try to load the result from the on-disk cache
         let result = if Q::cache_on_disk(self.global_tcx(), key.clone(), None) &&
                         self.sess.opts.debugging_opts.incremental_queries {
@@ -452,6 +462,10 @@ impl<'tcx> TyCtxt<'tcx> {
             let result = Q::try_load_from_disk(self.global_tcx(), prev_dep_node_index);
             self.sess.profiler(|p| p.incremental_load_result_end(Q::NAME));
 
+            if let (Some(start), Some(_)) = (start, &result) {
+                self.queries.on_disk_cache.record_load(Q::NAME.as_str(), start.elapsed());
+            }
+
             // We always expect to find a cached result for things that
             // can be forced from DepNode.
             debug_assert!(!dep_node.kind.can_reconstruct_query_key() ||
@@ -482,6 +496,10 @@ impl<'tcx> TyCtxt<'tcx> {
             });
 
             self.sess.profiler(|p| p.end_query(Q::NAME));
+
+            if let Some(start) = start {
+                self.queries.on_disk_cache.record_recompute(Q::NAME.as_str(), start.elapsed());
+            }
             result
         };
 
@@ -525,6 +543,31 @@ impl<'tcx> TyCtxt<'tcx> {
             for {:?}", dep_node);
     }
 
+    /// Prints why the query for `dep_node` has to be re-executed, if it matches
+    /// the filter given to `-Z incremental-explain`.
+    #[inline(never)]
+    #[cold]
+    fn explain_reexecution<Q: QueryDescription<'tcx>>(self, dep_node: &DepNode) {
+        if !self.dep_graph.is_fully_enabled() {
+            return;
+        }
+
+        // The explanation mentions the items of the dep-nodes, which must not
+        // be recorded as dependencies of the query being executed.
+        self.dep_graph.with_ignore(|| {
+            let filter = self.sess.opts.debugging_opts.incremental_explain.as_ref().unwrap();
+            let item = dep_node.extract_def_id(self).map(|def_id| self.def_path_str(def_id));
+            if Q::NAME.as_str() != filter && item.as_ref() != Some(filter) {
+                return;
+            }
+
+            println!("[incremental] re-executing `{:?}`:", dep_node);
+            for line in self.dep_graph.explain_red(self, dep_node) {
+                println!("[incremental]     {}", line);
+            }
+        })
+    }
+
     #[inline(always)]
     fn force_query_with_job<Q: QueryDescription<'tcx>>(
         self,
@@ -543,6 +586,10 @@ impl<'tcx> TyCtxt<'tcx> {
                  - dep-node: {:?}",
                 key, dep_node);
 
+        if unlikely!(self.sess.opts.debugging_opts.incremental_explain.is_some()) {
+            self.explain_reexecution::<Q>(&dep_node);
+        }
+
         profq_msg!(self, ProfileQueriesMsg::ProviderBegin);
         self.sess.profiler(|p| p.start_query(Q::NAME));
 
@@ -996,6 +1043,13 @@ macro_rules! define_queries_inner {
                         // would be missing appropriate entries in `providers`.
                         .unwrap_or(&tcx.queries.fallback_extern_providers)
                         .$name;
+                    if let (Some(item_times), Some(item)) =
+                        (&tcx.sess.item_times, key.attributed_item())
+                    {
+                        return item_times.time(Self::NAME.as_str(), item, || {
+                            provider(tcx.global_tcx(), key)
+                        });
+                    }
                     provider(tcx.global_tcx(), key)
                 })
             }
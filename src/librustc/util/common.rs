@@ -17,6 +17,7 @@ use crate::ty::TyCtxt;
 use crate::dep_graph::{DepNode};
 use lazy_static;
 use crate::session::Session;
+use crate::util::memory_limit;
 
 #[cfg(test)]
 mod tests;
@@ -145,7 +146,7 @@ pub fn set_time_depth(depth: usize) {
 pub fn time<T, F>(sess: &Session, what: &str, f: F) -> T where
     F: FnOnce() -> T,
 {
-    time_ext(sess.time_passes(), Some(sess), what, f)
+    memory_limit::in_pass(sess, what, || time_ext(sess.time_passes(), Some(sess), what, f))
 }
 
 pub fn time_ext<T, F>(do_it: bool, sess: Option<&Session>, what: &str, f: F) -> T where
@@ -244,7 +245,7 @@ pub fn record_time<T, F>(accu: &Lock<Duration>, f: F) -> T where
 
 // Memory reporting
 #[cfg(unix)]
-fn get_resident() -> Option<usize> {
+pub(crate) fn get_resident() -> Option<usize> {
     use std::fs;
 
     let field = 1;
@@ -256,7 +257,7 @@ fn get_resident() -> Option<usize> {
 }
 
 #[cfg(windows)]
-fn get_resident() -> Option<usize> {
+pub(crate) fn get_resident() -> Option<usize> {
     type BOOL = i32;
     type DWORD = u32;
     type HANDLE = *mut u8;
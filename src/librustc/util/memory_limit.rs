@@ -0,0 +1,153 @@
+//! Support for `-Z memory-limit`.
+//!
+//! Instead of letting the process be killed by the operating system once it
+//! runs out of memory, the memory used by the compiler is compared with the
+//! limit at the start and end of every pass and after every query. Once the
+//! limit is exceeded, compilation is aborted with a fatal error naming the
+//! pass and query in progress and the largest arenas.
+//!
+//! The memory used is the larger of the bytes allocated by all arenas and the
+//! resident set size of the process. The latter is comparatively expensive to
+//! read, so after queries it is only read every `RESIDENT_CHECK_INTERVAL`
+//! checks.
+
+use crate::session::Session;
+use crate::ty::tls;
+use crate::util::common::get_resident;
+
+use errors::FatalError;
+use lazy_static;
+use rustc_data_structures::OnDrop;
+
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const RESIDENT_CHECK_INTERVAL: usize = 64;
+
+/// The number of arenas listed in the error.
+const ARENAS_REPORTED: usize = 5;
+
+thread_local! {
+    static CHECKS: Cell<usize> = Cell::new(0);
+
+    /// Set while this thread is reporting the error, as describing the query
+    /// in progress may itself run queries.
+    static REPORTING: Cell<bool> = Cell::new(false);
+}
+
+lazy_static! {
+    /// The stack of passes in progress. Passes are run from the main thread,
+    /// but queries run from worker threads should still be able to name them.
+    static ref PASSES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+/// Only the first thread to exceed the limit reports it.
+static REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Runs the pass `what`, checking the memory limit before and after it.
+pub fn in_pass<T, F>(sess: &Session, what: &str, f: F) -> T where
+    F: FnOnce() -> T,
+{
+    let limit = match sess.opts.debugging_opts.memory_limit {
+        Some(limit) => limit,
+        None => return f(),
+    };
+
+    PASSES.lock().unwrap().push(what.to_string());
+    let _pop = OnDrop(|| {
+        PASSES.lock().unwrap().pop();
+    });
+
+    check_limit(sess, limit, true);
+    let rv = f();
+    check_limit(sess, limit, true);
+    rv
+}
+
+/// Checks the memory limit, if any. This is called after every query.
+#[inline]
+pub fn check(sess: &Session) {
+    if let Some(limit) = sess.opts.debugging_opts.memory_limit {
+        let read_resident = CHECKS.with(|checks| {
+            let n = checks.get().wrapping_add(1);
+            checks.set(n);
+            n % RESIDENT_CHECK_INTERVAL == 0
+        });
+        check_limit(sess, limit, read_resident);
+    }
+}
+
+fn check_limit(sess: &Session, limit: usize, read_resident: bool) {
+    if REPORTING.with(|reporting| reporting.get()) {
+        return;
+    }
+
+    let arena_bytes = arena::total_allocated_bytes();
+    let resident = if read_resident || arena_bytes > limit {
+        get_resident()
+    } else {
+        None
+    };
+
+    if arena_bytes > limit || resident.map_or(false, |resident| resident > limit) {
+        report(sess, limit, arena_bytes, resident);
+    }
+}
+
+fn to_mb(bytes: usize) -> usize {
+    (bytes as f64 / 1_000_000.0).round() as usize
+}
+
+#[cold]
+#[inline(never)]
+fn report(sess: &Session, limit: usize, arena_bytes: usize, resident: Option<usize>) -> ! {
+    if REPORTED.swap(true, Ordering::SeqCst) {
+        FatalError.raise();
+    }
+    REPORTING.with(|reporting| reporting.set(true));
+
+    let mut err = sess.struct_fatal(&format!(
+        "memory usage exceeded the limit of {} bytes set with `-Z memory-limit`",
+        limit,
+    ));
+    let usage = match resident {
+        Some(resident) => format!(
+            "{}MB allocated in arenas, resident set size: {}MB",
+            to_mb(arena_bytes),
+            to_mb(resident),
+        ),
+        None => format!("{}MB allocated in arenas", to_mb(arena_bytes)),
+    };
+    err.note(&usage);
+
+    if let Some(pass) = PASSES.lock().unwrap().last() {
+        err.note(&format!("while running the pass `{}`", pass));
+    }
+
+    tls::with_context_opt(|icx| {
+        let icx = match icx {
+            Some(icx) => icx,
+            None => return,
+        };
+
+        if let Some(job) = &icx.query {
+            err.note(&format!("while {}", job.info.query.describe(icx.tcx)));
+        }
+
+        let mut arenas = icx.tcx.arena_allocated_bytes();
+        arenas.retain(|&(_, bytes)| bytes > 0);
+        arenas.sort_by(|a, b| b.1.cmp(&a.1));
+        if !arenas.is_empty() {
+            let arenas: Vec<_> = arenas
+                .iter()
+                .take(ARENAS_REPORTED)
+                .map(|&(name, bytes)| format!("`{}` ({}MB)", name, to_mb(bytes)))
+                .collect();
+            err.note(&format!("largest arenas: {}", arenas.join(", ")));
+        }
+    });
+
+    err.emit();
+    FatalError.raise()
+}
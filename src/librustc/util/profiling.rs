@@ -2,15 +2,21 @@ use std::borrow::Cow;
 use std::error::Error;
 use std::fs;
 use std::mem::{self, Discriminant};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process;
+use std::sync::Mutex;
 use std::thread::ThreadId;
+use std::time::Instant;
 use std::u32;
 
 use crate::ty::query::QueryName;
 
 use measureme::{StringId, TimestampKind};
 
+use self::report::{EventKind, Phase, RawEvent, ReportKinds, REPORT_KINDS_BY_NAME};
+
+mod report;
+
 /// MmapSerializatioSink is faster on macOS and Linux
 /// but FileSerializationSink is faster on Windows
 #[cfg(not(windows))]
@@ -62,8 +68,18 @@ fn thread_id_to_u64(tid: ThreadId) -> u64 {
     unsafe { mem::transmute::<ThreadId, u64>(tid) }
 }
 
+/// The events kept in memory to write the reports requested with `-Z self-profile-report`.
+struct ReportData {
+    kinds: ReportKinds,
+    /// The path of the raw event data, without its extension.
+    stem: PathBuf,
+    start: Instant,
+    events: Mutex<Vec<RawEvent>>,
+}
+
 pub struct SelfProfiler {
     profiler: Profiler,
+    report: Option<ReportData>,
     event_filter_mask: EventFilter,
     query_event_kind: StringId,
     generic_activity_event_kind: StringId,
@@ -76,14 +92,14 @@ impl SelfProfiler {
     pub fn new(
         output_directory: &Path,
         crate_name: Option<&str>,
-        event_filters: &Option<Vec<String>>
+        event_filters: &Option<Vec<String>>,
+        report_kinds: &Option<Vec<String>>,
     ) -> Result<SelfProfiler, Box<dyn Error>> {
         fs::create_dir_all(output_directory)?;
 
         let crate_name = crate_name.unwrap_or("unknown-crate");
-        let filename = format!("{}-{}.rustc_profile", crate_name, process::id());
-        let path = output_directory.join(&filename);
-        let profiler = Profiler::new(&path)?;
+        let stem = output_directory.join(format!("{}-{}", crate_name, process::id()));
+        let profiler = Profiler::new(&stem.with_extension("rustc_profile"))?;
 
         let query_event_kind = profiler.alloc_string("Query");
         let generic_activity_event_kind = profiler.alloc_string("GenericActivity");
@@ -120,8 +136,33 @@ impl SelfProfiler {
             event_filter_mask = EventFilter::DEFAULT;
         }
 
+        let mut kinds = ReportKinds::NONE;
+        for item in report_kinds.iter().flatten() {
+            if let Some(&(_, kind)) = REPORT_KINDS_BY_NAME.iter().find(|&(name, _)| name == item) {
+                kinds |= kind;
+            } else {
+                warn!("Unknown self-profiler report `{}`. Available options are: {}.",
+                    item,
+                    REPORT_KINDS_BY_NAME.iter()
+                                        .map(|&(name, _)| name.to_string())
+                                        .collect::<Vec<_>>()
+                                        .join(", "));
+            }
+        }
+        let report = if kinds.is_empty() {
+            None
+        } else {
+            Some(ReportData {
+                kinds,
+                stem,
+                start: Instant::now(),
+                events: Default::default(),
+            })
+        };
+
         Ok(SelfProfiler {
             profiler,
+            report,
             event_filter_mask,
             query_event_kind,
             generic_activity_event_kind,
@@ -150,7 +191,7 @@ impl SelfProfiler {
         label: impl Into<Cow<'static, str>>,
     ) {
         if self.event_filter_mask.contains(EventFilter::GENERIC_ACTIVITIES) {
-            self.record(&label.into(), self.generic_activity_event_kind, TimestampKind::Start);
+            self.record(label.into(), self.generic_activity_event_kind, TimestampKind::Start);
         }
     }
 
@@ -160,7 +201,7 @@ impl SelfProfiler {
         label: impl Into<Cow<'static, str>>,
     ) {
         if self.event_filter_mask.contains(EventFilter::GENERIC_ACTIVITIES) {
-            self.record(&label.into(), self.generic_activity_event_kind, TimestampKind::End);
+            self.record(label.into(), self.generic_activity_event_kind, TimestampKind::End);
         }
     }
 
@@ -222,11 +263,20 @@ impl SelfProfiler {
     }
 
     #[inline]
-    fn record(&self, event_id: &str, event_kind: StringId, timestamp_kind: TimestampKind) {
+    fn record(
+        &self,
+        event_id: Cow<'static, str>,
+        event_kind: StringId,
+        timestamp_kind: TimestampKind,
+    ) {
         let thread_id = thread_id_to_u64(std::thread::current().id());
 
-        let event_id = self.profiler.alloc_string(event_id);
-        self.profiler.record_event(event_kind, event_id, thread_id, timestamp_kind);
+        let event_id_string = self.profiler.alloc_string(&event_id[..]);
+        self.profiler.record_event(event_kind, event_id_string, thread_id, timestamp_kind);
+
+        if self.report.is_some() {
+            self.record_for_report(event_id, event_kind, thread_id, timestamp_kind);
+        }
     }
 
     #[inline]
@@ -241,5 +291,56 @@ impl SelfProfiler {
         let thread_id = thread_id_to_u64(std::thread::current().id());
 
         self.profiler.record_event(event_kind, dep_node_name, thread_id, timestamp_kind);
+
+        if self.report.is_some() {
+            self.record_for_report(query_name.as_str().into(), event_kind, thread_id,
+                                   timestamp_kind);
+        }
+    }
+
+    #[cold]
+    fn record_for_report(
+        &self,
+        label: Cow<'static, str>,
+        event_kind: StringId,
+        thread_id: u64,
+        timestamp_kind: TimestampKind,
+    ) {
+        let report = self.report.as_ref().unwrap();
+        let kind = if event_kind == self.query_event_kind {
+            EventKind::Query
+        } else if event_kind == self.generic_activity_event_kind {
+            EventKind::GenericActivity
+        } else if event_kind == self.incremental_load_result_event_kind {
+            EventKind::IncrementalLoadResult
+        } else if event_kind == self.query_blocked_event_kind {
+            EventKind::QueryBlocked
+        } else {
+            EventKind::QueryCacheHit
+        };
+        let phase = match timestamp_kind {
+            TimestampKind::Start => Phase::Start,
+            TimestampKind::End => Phase::End,
+            TimestampKind::Instant => Phase::Instant,
+        };
+
+        // The timestamp is taken while holding the lock, so that the events are ordered.
+        let mut events = report.events.lock().unwrap();
+        events.push(RawEvent {
+            label,
+            kind,
+            phase,
+            thread_id,
+            timestamp: report.start.elapsed(),
+        });
+    }
+
+    /// Writes the reports requested with `-Z self-profile-report`, next to the raw event data.
+    pub fn write_reports(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(ref report) = self.report {
+            let events = report.events.lock().unwrap();
+            report::write_reports(report.kinds, &report.stem, &events)?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,271 @@
+//! Reports built from the events recorded by the self-profiler, written at the end of the session
+//! when `-Z self-profile-report` is given.
+//!
+//! - `summary`: a table of the self time, total time, invocations and cache hits of each query and
+//!   generic activity.
+//! - `flamegraph`: the self time of each stack of queries and activities, in the "folded stacks"
+//!   format read by `flamegraph.pl` and `inferno`.
+//! - `chrome`: the events in the Trace Event format read by `chrome://tracing`.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use rustc_data_structures::fx::FxHashMap;
+
+use crate::util::common::duration_to_secs_str;
+
+#[cfg(test)]
+mod tests;
+
+bitflags! {
+    pub struct ReportKinds: u32 {
+        const SUMMARY    = 1 << 0;
+        const FLAMEGRAPH = 1 << 1;
+        const CHROME     = 1 << 2;
+
+        const NONE = 0;
+    }
+}
+
+pub const REPORT_KINDS_BY_NAME: &[(&str, ReportKinds)] = &[
+    ("summary", ReportKinds::SUMMARY),
+    ("flamegraph", ReportKinds::FLAMEGRAPH),
+    ("chrome", ReportKinds::CHROME),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    Query,
+    GenericActivity,
+    IncrementalLoadResult,
+    QueryBlocked,
+    QueryCacheHit,
+}
+
+impl EventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Query => "Query",
+            EventKind::GenericActivity => "GenericActivity",
+            EventKind::IncrementalLoadResult => "IncrementalLoadResult",
+            EventKind::QueryBlocked => "QueryBlocked",
+            EventKind::QueryCacheHit => "QueryCacheHit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Start,
+    End,
+    Instant,
+}
+
+#[derive(Clone, Debug)]
+pub struct RawEvent {
+    pub label: Cow<'static, str>,
+    pub kind: EventKind,
+    pub phase: Phase,
+    pub thread_id: u64,
+    /// The time elapsed since the profiler was created.
+    pub timestamp: Duration,
+}
+
+impl RawEvent {
+    /// The name under which the event is reported. Loading a query result from the incremental
+    /// cache and waiting on another thread are reported apart from the query itself.
+    fn item_name(&self) -> Cow<'static, str> {
+        match self.kind {
+            EventKind::IncrementalLoadResult => {
+                format!("{} (incremental load)", self.label).into()
+            }
+            EventKind::QueryBlocked => format!("{} (blocked)", self.label).into(),
+            _ => self.label.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemStats {
+    /// Time spent in the item itself, excluding the other items it started.
+    pub self_time: Duration,
+    /// Time spent in the item, including the other items it started. Recursive invocations are
+    /// only counted once.
+    pub total_time: Duration,
+    pub invocations: u64,
+    pub cache_hits: u64,
+}
+
+#[derive(Default)]
+pub struct Analysis {
+    pub items: FxHashMap<Cow<'static, str>, ItemStats>,
+    /// The self time of each stack of items, keyed by the names of the items from the outermost
+    /// one, separated by `;`.
+    pub stacks: FxHashMap<String, Duration>,
+}
+
+struct Frame {
+    name: Cow<'static, str>,
+    start: Duration,
+    child_time: Duration,
+}
+
+/// Pairs the start and end events of each thread, which must be ordered by timestamp.
+pub fn analyze(events: &[RawEvent]) -> Analysis {
+    let mut analysis = Analysis::default();
+    let mut threads: FxHashMap<u64, Vec<Frame>> = Default::default();
+
+    for event in events {
+        let stack = threads.entry(event.thread_id).or_default();
+        match event.phase {
+            Phase::Start => stack.push(Frame {
+                name: event.item_name(),
+                start: event.timestamp,
+                child_time: Duration::default(),
+            }),
+            Phase::End => {
+                let name = event.item_name();
+                // Frames which were never ended (e.g., because of a fatal error unwinding past
+                // them) are dropped when one of their parents ends.
+                let pos = match stack.iter().rposition(|frame| frame.name == name) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                stack.truncate(pos + 1);
+                let frame = stack.pop().unwrap();
+
+                let total_time = event.timestamp.checked_sub(frame.start).unwrap_or_default();
+                let self_time = total_time.checked_sub(frame.child_time).unwrap_or_default();
+                if let Some(parent) = stack.last_mut() {
+                    parent.child_time += total_time;
+                }
+
+                let mut path = String::new();
+                for parent in stack.iter() {
+                    path.push_str(&parent.name);
+                    path.push(';');
+                }
+                path.push_str(&frame.name);
+                *analysis.stacks.entry(path).or_default() += self_time;
+
+                let is_recursive = stack.iter().any(|parent| parent.name == frame.name);
+                let stats = analysis.items.entry(frame.name).or_default();
+                stats.self_time += self_time;
+                if !is_recursive {
+                    stats.total_time += total_time;
+                }
+                stats.invocations += 1;
+            }
+            Phase::Instant => {
+                if event.kind == EventKind::QueryCacheHit {
+                    analysis.items.entry(event.label.clone()).or_default().cache_hits += 1;
+                }
+            }
+        }
+    }
+
+    analysis
+}
+
+/// Writes the reports in `kinds` next to the raw event data, whose path without extension is
+/// `stem`.
+pub fn write_reports(kinds: ReportKinds, stem: &Path, events: &[RawEvent]) -> io::Result<()> {
+    if kinds.intersects(ReportKinds::SUMMARY | ReportKinds::FLAMEGRAPH) {
+        let analysis = analyze(events);
+        if kinds.contains(ReportKinds::SUMMARY) {
+            let mut out = BufWriter::new(File::create(stem.with_extension("summary.txt"))?);
+            write_summary(&mut out, &analysis)?;
+        }
+        if kinds.contains(ReportKinds::FLAMEGRAPH) {
+            let mut out = BufWriter::new(File::create(stem.with_extension("folded"))?);
+            write_folded_stacks(&mut out, &analysis)?;
+        }
+    }
+    if kinds.contains(ReportKinds::CHROME) {
+        let mut out = BufWriter::new(File::create(stem.with_extension("chrome.json"))?);
+        write_chrome_trace(&mut out, events)?;
+    }
+    Ok(())
+}
+
+fn percent(part: Duration, total: Duration) -> f64 {
+    if total == Duration::default() {
+        0.0
+    } else {
+        part.as_nanos() as f64 * 100.0 / total.as_nanos() as f64
+    }
+}
+
+pub fn write_summary(out: &mut dyn Write, analysis: &Analysis) -> io::Result<()> {
+    let mut items: Vec<_> = analysis.items.iter().collect();
+    items.sort_by(|a, b| b.1.self_time.cmp(&a.1.self_time).then_with(|| a.0.cmp(b.0)));
+
+    let total: Duration = items.iter().map(|(_, stats)| stats.self_time).sum();
+    let width = items.iter().map(|(name, _)| name.len()).max().unwrap_or(0).max(4);
+
+    writeln!(out, "| {:<width$} | {:>10} | {:>10} | {:>10} | {:>11} | {:>10} |",
+             "Item", "Self time", "% of total", "Time", "Invocations", "Cache hits",
+             width = width)?;
+    writeln!(out, "|{:-<width$}|{:->12}|{:->12}|{:->12}|{:->13}|{:->12}|",
+             "", "", "", "", "", "", width = width + 2)?;
+    for (name, stats) in items {
+        writeln!(out, "| {:<width$} | {:>10} | {:>10.2} | {:>10} | {:>11} | {:>10} |",
+                 name,
+                 duration_to_secs_str(stats.self_time),
+                 percent(stats.self_time, total),
+                 duration_to_secs_str(stats.total_time),
+                 stats.invocations,
+                 stats.cache_hits,
+                 width = width)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "Total time: {}s", duration_to_secs_str(total))
+}
+
+/// Writes one line per stack, with its self time in microseconds.
+pub fn write_folded_stacks(out: &mut dyn Write, analysis: &Analysis) -> io::Result<()> {
+    let mut stacks: Vec<_> = analysis.stacks.iter().collect();
+    stacks.sort();
+    for (stack, self_time) in stacks {
+        let micros = self_time.as_micros();
+        if micros > 0 {
+            writeln!(out, "{} {}", stack, micros)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_chrome_trace(out: &mut dyn Write, events: &[RawEvent]) -> io::Result<()> {
+    let pid = process::id();
+    write!(out, "[")?;
+    for (i, event) in events.iter().enumerate() {
+        let phase = match event.phase {
+            Phase::Start => "B",
+            Phase::End => "E",
+            Phase::Instant => "i",
+        };
+        write!(out, "{}\n{{\"name\":", if i == 0 { "" } else { "," })?;
+        write_json_str(out, &event.label)?;
+        write!(out, ",\"cat\":\"{}\",\"ph\":\"{}\",\"ts\":{:.3},\"pid\":{},\"tid\":{}}}",
+               event.kind.as_str(), phase, event.timestamp.as_nanos() as f64 / 1000.0,
+               pid, event.thread_id)?;
+    }
+    writeln!(out, "\n]")
+}
+
+fn write_json_str(out: &mut dyn Write, s: &str) -> io::Result<()> {
+    write!(out, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(out, "\\\"")?,
+            '\\' => write!(out, "\\\\")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    write!(out, "\"")
+}
@@ -0,0 +1,106 @@
+use super::*;
+
+fn event(label: &'static str, kind: EventKind, phase: Phase, thread_id: u64, ms: u64) -> RawEvent {
+    RawEvent {
+        label: label.into(),
+        kind,
+        phase,
+        thread_id,
+        timestamp: Duration::from_millis(ms),
+    }
+}
+
+fn start(label: &'static str, ms: u64) -> RawEvent {
+    event(label, EventKind::Query, Phase::Start, 0, ms)
+}
+
+fn end(label: &'static str, ms: u64) -> RawEvent {
+    event(label, EventKind::Query, Phase::End, 0, ms)
+}
+
+fn stats(self_ms: u64, total_ms: u64, invocations: u64, cache_hits: u64) -> ItemStats {
+    ItemStats {
+        self_time: Duration::from_millis(self_ms),
+        total_time: Duration::from_millis(total_ms),
+        invocations,
+        cache_hits,
+    }
+}
+
+#[test]
+fn test_self_and_total_time() {
+    let analysis = analyze(&[
+        start("typeck", 0),
+        start("type_of", 2),
+        end("type_of", 5),
+        event("type_of", EventKind::QueryCacheHit, Phase::Instant, 0, 6),
+        start("type_of", 6),
+        end("type_of", 7),
+        end("typeck", 10),
+    ]);
+
+    assert_eq!(analysis.items["typeck"], stats(6, 10, 1, 0));
+    assert_eq!(analysis.items["type_of"], stats(4, 4, 2, 1));
+    assert_eq!(analysis.stacks["typeck"], Duration::from_millis(6));
+    assert_eq!(analysis.stacks["typeck;type_of"], Duration::from_millis(4));
+}
+
+#[test]
+fn test_recursive_total_time() {
+    let analysis = analyze(&[
+        start("layout_raw", 0),
+        start("layout_raw", 1),
+        end("layout_raw", 3),
+        end("layout_raw", 4),
+    ]);
+
+    assert_eq!(analysis.items["layout_raw"], stats(4, 4, 2, 0));
+    assert_eq!(analysis.stacks["layout_raw;layout_raw"], Duration::from_millis(2));
+}
+
+#[test]
+fn test_threads_and_kinds() {
+    let analysis = analyze(&[
+        event("codegen", EventKind::GenericActivity, Phase::Start, 0, 0),
+        event("codegen", EventKind::GenericActivity, Phase::Start, 1, 1),
+        event("mir_built", EventKind::IncrementalLoadResult, Phase::Start, 0, 2),
+        event("codegen", EventKind::GenericActivity, Phase::End, 1, 4),
+        event("mir_built", EventKind::IncrementalLoadResult, Phase::End, 0, 5),
+        event("codegen", EventKind::GenericActivity, Phase::End, 0, 6),
+    ]);
+
+    assert_eq!(analysis.items["codegen"], stats(6, 9, 2, 0));
+    assert_eq!(analysis.items["mir_built (incremental load)"], stats(3, 3, 1, 0));
+}
+
+#[test]
+fn test_unmatched_events() {
+    let analysis = analyze(&[
+        end("stray", 0),
+        start("outer", 1),
+        start("unfinished", 2),
+        end("outer", 5),
+    ]);
+
+    assert!(!analysis.items.contains_key("stray"));
+    assert!(!analysis.items.contains_key("unfinished"));
+    assert_eq!(analysis.items["outer"], stats(4, 4, 1, 0));
+}
+
+#[test]
+fn test_chrome_trace() {
+    let mut out = Vec::new();
+    write_chrome_trace(&mut out, &[
+        event("say \"hi\"", EventKind::GenericActivity, Phase::Start, 3, 1),
+        event("say \"hi\"", EventKind::GenericActivity, Phase::End, 3, 2),
+    ]).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let pid = process::id();
+
+    assert_eq!(out, format!("[\n\
+        {{\"name\":\"say \\\"hi\\\"\",\"cat\":\"GenericActivity\",\"ph\":\"B\",\"ts\":1000.000,\
+          \"pid\":{pid},\"tid\":3}},\n\
+        {{\"name\":\"say \\\"hi\\\"\",\"cat\":\"GenericActivity\",\"ph\":\"E\",\"ts\":2000.000,\
+          \"pid\":{pid},\"tid\":3}}\n\
+        ]\n", pid = pid));
+}
@@ -14,27 +14,29 @@
 //!   int)` and `rec(x=int, y=int, z=int)` will have the same `llvm::Type`.
 
 use super::{LlvmCodegenBackend, ModuleLlvm};
-use rustc_codegen_ssa::{ModuleCodegen, ModuleKind};
+use rustc_codegen_ssa::{ModuleCodegen, ModuleKind, CompiledModule};
 use rustc_codegen_ssa::base::maybe_create_entry_wrapper;
 
-use crate::llvm;
+use crate::llvm::{self, False, ObjectFile, mk_symbol_iter};
 use crate::metadata;
 use crate::builder::Builder;
 use crate::common;
 use crate::context::CodegenCx;
 use rustc::dep_graph;
-use rustc::mir::mono::{Linkage, Visibility};
+use rustc::mir::mono::{Linkage, Visibility, MonoItem};
 use rustc::middle::cstore::{EncodedMetadata};
 use rustc::ty::TyCtxt;
 use rustc::middle::exported_symbols;
+use rustc::session::Session;
 use rustc::session::config::DebugInfo;
 use rustc_codegen_ssa::mono_item::MonoItemExt;
 use rustc_data_structures::small_c_str::SmallCStr;
+use rustc_fs_util::path_to_c_string;
 
 use rustc_codegen_ssa::traits::*;
 use rustc_codegen_ssa::back::write::submit_codegened_module_to_llvm;
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::time::Instant;
 use syntax_pos::symbol::InternedString;
 use rustc::hir::CodegenFnAttrs;
@@ -143,6 +145,10 @@ pub fn compile_codegen_unit(tcx: TyCtxt<'tcx>, cgu_name: InternedString) {
                 mono_item.define::<Builder<'_, '_, '_>>(&cx);
             }
 
+            if cx.sess().opts.debugging_opts.print_mono_item_sizes {
+                record_ir_instructions(&cx, &mono_items);
+            }
+
             // If this codegen unit contains the main function, also create the
             // wrapper here
             maybe_create_entry_wrapper::<Builder<'_, '_, '_>>(&cx);
@@ -210,3 +216,49 @@ pub fn visibility_to_llvm(linkage: Visibility) -> llvm::Visibility {
         Visibility::Protected => llvm::Visibility::Protected,
     }
 }
+
+/// Records the number of LLVM IR instructions generated for each function of
+/// a codegen unit, for `-Z print-mono-item-sizes`.
+fn record_ir_instructions(
+    cx: &CodegenCx<'_, 'tcx>,
+    mono_items: &[(MonoItem<'tcx>, (Linkage, Visibility))],
+) {
+    let mut code_stats = cx.sess().code_stats.borrow_mut();
+    for &(mono_item, _) in mono_items {
+        if let MonoItem::Fn(..) = mono_item {
+            let symbol_name = mono_item.symbol_name(cx.tcx).name.as_str();
+            if let Some(llfn) = cx.get_defined_value(&symbol_name) {
+                code_stats.record_ir_instructions(&symbol_name, llvm::count_instructions(llfn));
+            }
+        }
+    }
+}
+
+/// Records the size of the symbols defined by the object files of the crate,
+/// for `-Z print-mono-item-sizes`.
+pub fn record_symbol_sizes(sess: &Session, modules: &[CompiledModule]) {
+    let mut code_stats = sess.code_stats.borrow_mut();
+    for path in modules.iter().filter_map(|module| module.object.as_ref()) {
+        unsafe {
+            let buf = path_to_c_string(path);
+            let of = match llvm::LLVMRustCreateMemoryBufferWithContentsOfFile(buf.as_ptr())
+                .and_then(ObjectFile::new)
+            {
+                Some(of) => of,
+                None => {
+                    sess.warn(&format!("couldn't read the symbols of `{}`", path.display()));
+                    continue;
+                }
+            };
+            let si = mk_symbol_iter(of.llof);
+            while llvm::LLVMIsSymbolIteratorAtEnd(of.llof, si.llsi) == False {
+                let name = llvm::LLVMGetSymbolName(si.llsi);
+                if !name.is_null() {
+                    code_stats.record_symbol_size(&CStr::from_ptr(name).to_string_lossy(),
+                                                  llvm::LLVMGetSymbolSize(si.llsi));
+                }
+                llvm::LLVMMoveToNextSymbol(si.llsi);
+            }
+        }
+    }
+}
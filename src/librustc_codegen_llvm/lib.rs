@@ -303,6 +303,9 @@ impl CodegenBackend for LlvmCodegenBackend {
                 <rustc_codegen_ssa::back::write::OngoingCodegen<LlvmCodegenBackend>>()
                 .expect("Expected LlvmCodegenBackend's OngoingCodegen, found Box<Any>")
                 .join(sess);
+        if sess.opts.debugging_opts.print_mono_item_sizes {
+            base::record_symbol_sizes(sess, &codegen_results.modules);
+        }
         if sess.opts.debugging_opts.incremental_info {
             rustc_codegen_ssa::back::write::dump_incremental_data(&codegen_results);
         }
@@ -208,6 +208,41 @@ pub fn mk_section_iter(llof: &'a ffi::ObjectFile) -> SectionIter<'a> {
     unsafe { SectionIter { llsi: LLVMGetSections(llof) } }
 }
 
+// Memory-managed interface to symbol iterators.
+
+pub struct SymbolIter<'a> {
+    pub llsi: &'a mut SymbolIterator<'a>,
+}
+
+impl Drop for SymbolIter<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            LLVMDisposeSymbolIterator(&mut *(self.llsi as *mut _));
+        }
+    }
+}
+
+pub fn mk_symbol_iter(llof: &'a ffi::ObjectFile) -> SymbolIter<'a> {
+    unsafe { SymbolIter { llsi: LLVMGetSymbols(llof) } }
+}
+
+/// Counts the instructions of `llfn`, which must be a function definition.
+pub fn count_instructions(llfn: &Value) -> u64 {
+    let mut count = 0;
+    unsafe {
+        let mut llbb = Some(LLVMGetFirstBasicBlock(llfn));
+        while let Some(bb) = llbb {
+            let mut llinst = LLVMGetFirstInstruction(bb);
+            while let Some(inst) = llinst {
+                count += 1;
+                llinst = LLVMGetNextInstruction(inst);
+            }
+            llbb = LLVMGetNextBasicBlock(bb);
+        }
+    }
+    count
+}
+
 /// Safe wrapper around `LLVMGetParam`, because segfaults are no fun.
 pub fn get_param(llfn: &'a Value, index: c_uint) -> &'a Value {
     unsafe {
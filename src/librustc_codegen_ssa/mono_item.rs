@@ -37,7 +37,12 @@ impl<'a, 'tcx: 'a> MonoItemExt<'a, 'tcx> for MonoItem<'tcx> {
                 }
             }
             MonoItem::Fn(instance) => {
-                base::codegen_instance::<Bx>(&cx, instance);
+                match cx.tcx().sess.item_times {
+                    Some(ref item_times) => item_times.time_instance("codegen", instance, || {
+                        base::codegen_instance::<Bx>(&cx, instance)
+                    }),
+                    None => base::codegen_instance::<Bx>(&cx, instance),
+                }
             }
         }
 
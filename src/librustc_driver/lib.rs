@@ -206,6 +206,10 @@ pub fn run_compiler(
                             );
                             return;
                         }
+                        if sopts.debugging_opts.incremental_gc {
+                            garbage_collect_incr_comp_dir(compiler.session());
+                            return;
+                        }
                         let should_stop = RustcDefaultCalls::print_crate_info(
                             &***compiler.codegen_backend(),
                             compiler.session(),
@@ -231,6 +235,11 @@ pub fn run_compiler(
         }
     };
 
+    if sopts.debugging_opts.incremental_gc {
+        early_error(sopts.error_format, "`-Z incremental-gc` doesn't compile anything, \
+                                         so it can't be given an input file");
+    }
+
     if let Some(err) = input_err {
         // Immediately stop compilation if there was an issue reading
         // the input (for example if the input stream is not UTF-8).
@@ -394,6 +403,10 @@ pub fn run_compiler(
 
         compiler.link()?;
 
+        if sess.opts.debugging_opts.print_mono_item_sizes {
+            sess.code_stats.borrow().print_mono_item_sizes();
+        }
+
         if sess.opts.debugging_opts.perf_stats {
             sess.print_perf_stats();
         }
@@ -558,6 +571,23 @@ fn handle_explain(code: &str,
     }
 }
 
+/// Runs the garbage collection of the incremental compilation directory for `-Z incremental-gc`.
+fn garbage_collect_incr_comp_dir(sess: &Session) {
+    let incr_dir = match sess.opts.incremental {
+        Some(ref incr_dir) => incr_dir,
+        None => early_error(sess.opts.error_format,
+                            "`-Z incremental-gc` requires an incremental compilation directory, \
+                             given with `-C incremental`"),
+    };
+    if let Err(err) = rustc_incremental::garbage_collect_incr_comp_dir(sess, None) {
+        early_error(sess.opts.error_format, &format!(
+            "failed to garbage collect incremental compilation directory `{}`: {}",
+            incr_dir.display(),
+            err,
+        ));
+    }
+}
+
 fn show_content_with_pager(content: &String) {
     let pager_name = env::var_os("PAGER").unwrap_or_else(|| if cfg!(windows) {
         OsString::from("more.com")
@@ -50,6 +50,17 @@
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
 //!
+//! None of this limits the number of crates whose session directories are kept
+//! in the incremental compilation directory though, and a directory shared by
+//! many projects or by a CI cache can thus grow without bound. The size of the
+//! whole directory and the age of its session directories can be limited with
+//! `-Z incremental-cache-max-size` and `-Z incremental-cache-max-age`, in which
+//! case the least recently used finalized session directories of all crates
+//! are deleted after each session. Since a new session directory is created
+//! whenever a crate is compiled, the timestamp in the name of a session
+//! directory is the time at which it was last used. The same collection can be
+//! run without compiling anything with `-Z incremental-gc`.
+//!
 //! ## Synchronization
 //!
 //! There is some synchronization needed in order for the compiler to be able to
@@ -125,6 +136,18 @@ const DEP_GRAPH_FILENAME: &str = "dep-graph.bin";
 const WORK_PRODUCTS_FILENAME: &str = "work-products.bin";
 const QUERY_CACHE_FILENAME: &str = "query-cache.bin";
 
+// The directory holding the content-addressed work products of
+// `-Z incremental-shared`. It can't be mistaken for a crate directory, since
+// crate names don't contain dashes.
+const WORK_PRODUCT_STORE_DIR: &str = "shared-work-products";
+
+// The length of the hashes naming the files of the work product store: 128-bit
+// hashes encoded in base 36, padded with zeros.
+pub const WORK_PRODUCT_HASH_LEN: usize = 25;
+
+// The extensions of the files of each `WorkProductFileKind`.
+const WORK_PRODUCT_EXTENSIONS: &[&str] = &["o", "bc", "bc.z"];
+
 // We encode integers using the following base, so they are shorter than decimal
 // or hexadecimal numbers (we want short file and directory names). Since these
 // numbers will be used in file names, we choose an encoding that is not
@@ -146,6 +169,33 @@ pub fn query_cache_path(sess: &Session) -> PathBuf {
     in_incr_comp_dir_sess(sess, QUERY_CACHE_FILENAME)
 }
 
+pub fn work_product_store_path(sess: &Session) -> PathBuf {
+    sess.opts.incremental.as_ref().unwrap().join(WORK_PRODUCT_STORE_DIR)
+}
+
+/// Returns the name of the file of the work product store that a file of a
+/// session directory is a link to, if it is one: with `-Z incremental-shared`,
+/// work product files are saved as `{cgu-name}-{hash}.{extension}`, where
+/// `{hash}.{extension}` is the name of the file in the store and the hash is
+/// `WORK_PRODUCT_HASH_LEN` characters long.
+pub fn work_product_store_file_name(file_name: &str) -> Option<&str> {
+    WORK_PRODUCT_EXTENSIONS.iter().filter_map(|extension| {
+        let store_file_len = WORK_PRODUCT_HASH_LEN + 1 + extension.len();
+        // The CGU name and the dash before the hash come first.
+        if file_name.len() < store_file_len + 2 ||
+           !file_name.is_char_boundary(file_name.len() - store_file_len) {
+            return None
+        }
+        let (cgu_name, store_file_name) = file_name.split_at(file_name.len() - store_file_len);
+        let (hash, dot_extension) = store_file_name.split_at(WORK_PRODUCT_HASH_LEN);
+        let is_store_file = cgu_name.ends_with('-') &&
+            hash.bytes().all(|b| b.is_ascii_digit() || b.is_ascii_lowercase()) &&
+            dot_extension.starts_with('.') &&
+            dot_extension[1..] == **extension;
+        if is_store_file { Some(store_file_name) } else { None }
+    }).next()
+}
+
 pub fn lock_file_path(session_dir: &Path) -> PathBuf {
     let crate_dir = session_dir.parent().unwrap();
 
@@ -234,19 +284,24 @@ pub fn prepare_session_directory(sess: &Session,
         }
 
         // Find a suitable source directory to copy from. Ignore those that we
-        // have already tried before.
+        // have already tried before. If the crate has never been compiled
+        // into this incremental directory, start from the seed directory.
         let source_directory = find_source_directory(&crate_dir,
                                                      &source_directories_already_tried);
-
-        let source_directory = if let Some(dir) = source_directory {
-            dir
-        } else {
-            // There's nowhere to copy from, we're done
-            debug!("no source directory found. Continuing with empty session \
-                    directory.");
-
-            sess.init_incr_comp_session(session_dir, directory_lock, false);
-            return
+        let (source_directory, from_seed) = match source_directory {
+            Some(dir) => (dir, false),
+            None => match find_seed_directory(sess, &crate_dir,
+                                              &source_directories_already_tried) {
+                Some(dir) => (dir, true),
+                None => {
+                    // There's nowhere to copy from, we're done
+                    debug!("no source directory found. Continuing with empty session \
+                            directory.");
+
+                    sess.init_incr_comp_session(session_dir, directory_lock, false);
+                    return
+                }
+            }
         };
 
         debug!("attempting to copy data from source: {}",
@@ -257,7 +312,8 @@ pub fn prepare_session_directory(sess: &Session,
         // Try copying over all files from the source directory
         if let Ok(allows_links) = copy_files(sess,
                                              &session_dir,
-                                             &source_directory) {
+                                             &source_directory,
+                                             from_seed) {
             debug!("successfully copied data from: {}",
                    source_directory.display());
 
@@ -376,6 +432,20 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     }
 
     let _ = garbage_collect_session_directories(sess);
+
+    // Even without limits, the work products which aren't used by any session
+    // anymore have to be deleted from the store of `-Z incremental-shared`.
+    let opts = &sess.opts.debugging_opts;
+    if opts.incremental_cache_max_size.is_some() || opts.incremental_cache_max_age.is_some() ||
+       opts.incremental_shared {
+        let session_dir = sess.incr_comp_session_dir().clone();
+        if let Err(err) = garbage_collect_incr_comp_dir(sess, Some(&session_dir)) {
+            sess.warn(&format!("Error garbage collecting incremental compilation \
+                                directory `{}`: {}",
+                               sess.opts.incremental.as_ref().unwrap().display(),
+                               err));
+        }
+    }
 }
 
 pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
@@ -387,18 +457,25 @@ pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
     Ok(())
 }
 
+/// Copies the files of `source_dir` into `target_dir`, hard linking them if
+/// possible. A seed directory is read-only: it isn't locked, since no compiler
+/// ever deletes it, and its files are always copied, so that they can't be
+/// modified through the session directory.
 fn copy_files(sess: &Session,
               target_dir: &Path,
-              source_dir: &Path)
+              source_dir: &Path,
+              from_seed: bool)
               -> Result<bool, ()> {
     // We acquire a shared lock on the lock file of the directory, so that
     // nobody deletes it out from under us while we are reading from it.
     let lock_file_path = lock_file_path(source_dir);
-    let _lock = if let Ok(lock) = flock::Lock::new(&lock_file_path,
-                                                   false,   // don't wait,
-                                                   false,   // don't create
-                                                   false) { // not exclusive
-        lock
+    let _lock = if from_seed {
+        None
+    } else if let Ok(lock) = flock::Lock::new(&lock_file_path,
+                                              false,   // don't wait,
+                                              false,   // don't create
+                                              false) { // not exclusive
+        Some(lock)
     } else {
         // Could not acquire the lock, don't try to copy from here
         return Err(())
@@ -421,7 +498,12 @@ fn copy_files(sess: &Session,
                 let source_path = entry.path();
 
                 debug!("copying into session dir: {}", source_path.display());
-                match link_or_copy(source_path, target_file_path) {
+                let result = if from_seed {
+                    copy_from_seed(&source_path, &target_file_path).map(|_| LinkOrCopy::Copy)
+                } else {
+                    link_or_copy(source_path, target_file_path)
+                };
+                match result {
                     Ok(LinkOrCopy::Link) => {
                         files_linked += 1
                     }
@@ -438,13 +520,17 @@ fn copy_files(sess: &Session,
     }
 
     if sess.opts.debugging_opts.incremental_info {
+        if from_seed {
+            println!("[incremental] session directory: starting from seed `{}`",
+                     source_dir.display());
+        }
         println!("[incremental] session directory: \
                   {} files hard-linked", files_linked);
         println!("[incremental] session directory: \
                  {} files copied", files_copied);
     }
 
-    Ok(files_linked > 0 || files_copied == 0)
+    Ok(from_seed || files_linked > 0 || files_copied == 0)
 }
 
 /// Generates unique directory path of the form:
@@ -558,6 +644,31 @@ fn find_source_directory_in_iter<I>(iter: I,
     best_candidate.1
 }
 
+/// Copies a file of a seed directory, making sure that the copy is writable
+/// even though the seed probably isn't.
+fn copy_from_seed(source: &Path, target: &Path) -> io::Result<()> {
+    std_fs::copy(source, target)?;
+    let mut permissions = std_fs::metadata(target)?.permissions();
+    permissions.set_readonly(false);
+    std_fs::set_permissions(target, permissions)
+}
+
+/// Finds the most recent published session directory of the crate in the
+/// seed directory given with `-Z incremental-seed`, which has the same layout
+/// as an incremental compilation directory.
+fn find_seed_directory(sess: &Session,
+                       crate_dir: &Path,
+                       source_directories_already_tried: &FxHashSet<PathBuf>)
+                       -> Option<PathBuf> {
+    let seed_dir = sess.opts.debugging_opts.incremental_seed.as_ref()?;
+    let iter = seed_dir.join(crate_dir.file_name()?)
+                       .read_dir()
+                       .ok()?
+                       .filter_map(|e| e.ok().map(|e| e.path()));
+
+    find_source_directory_in_iter(iter, source_directories_already_tried)
+}
+
 fn is_finalized(directory_name: &str) -> bool {
     !directory_name.ends_with("-working")
 }
@@ -643,6 +754,13 @@ pub fn garbage_collect_session_directories(sess: &Session) -> io::Result<()> {
         session_directory.display());
 
     let crate_directory = session_directory.parent().unwrap();
+    garbage_collect_crate_directory(sess, crate_directory)
+}
+
+/// Deletes the session directories of a single crate which aren't needed
+/// anymore: the ones left behind by aborted sessions, and all finalized ones
+/// except the most recent.
+fn garbage_collect_crate_directory(sess: &Session, crate_directory: &Path) -> io::Result<()> {
     debug!("garbage_collect_session_directories() - crate directory: {}",
         crate_directory.display());
 
@@ -871,6 +989,286 @@ fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<
     }
 }
 
+/// Deletes the least recently used finalized session directories, of all the
+/// crates in the incremental compilation directory, until the limits set with
+/// `-Z incremental-cache-max-size` and `-Z incremental-cache-max-age` are
+/// respected. `current_session_dir` is the session directory of the running
+/// compiler, if any, which is never deleted but counts towards the size of the
+/// cache. When there is no such session, as with `-Z incremental-gc`, the
+/// per-crate garbage collection is done first for each crate.
+///
+/// Session directories which are locked by another compiler instance are left
+/// alone, so the cache may stay above its size limit until the next collection.
+pub fn garbage_collect_incr_comp_dir(sess: &Session,
+                                     current_session_dir: Option<&Path>)
+                                     -> io::Result<()> {
+    debug!("garbage_collect_incr_comp_dir() - begin");
+
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    let max_size = sess.opts.debugging_opts.incremental_cache_max_size
+                                           .map(|mb| mb as u64 * 1024 * 1024);
+    let max_age = sess.opts.debugging_opts.incremental_cache_max_age
+                                          .map(|days| Duration::from_secs(days as u64 * 86400));
+
+    // The work products of `-Z incremental-shared` are counted once, in the
+    // store, rather than in each of the session directories linking to them.
+    let store = read_work_product_store(&incr_dir.join(WORK_PRODUCT_STORE_DIR));
+    let mut total_size = store.values().map(|&(size, _)| size).sum();
+    let mut candidates = vec![];
+
+    for crate_dir in incr_dir.read_dir()? {
+        let crate_dir = match crate_dir {
+            Ok(crate_dir) => crate_dir.path(),
+            _ => continue,
+        };
+        if !crate_dir.is_dir() || crate_dir.ends_with(WORK_PRODUCT_STORE_DIR) {
+            continue
+        }
+
+        if current_session_dir.is_none() {
+            if let Err(err) = garbage_collect_crate_directory(sess, &crate_dir) {
+                debug!("garbage_collect_incr_comp_dir() - error collecting `{}`: {}",
+                       crate_dir.display(), err);
+            }
+        }
+
+        let entries = match crate_dir.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                _ => continue,
+            };
+            let path = entry.path();
+            let size = disk_usage(&path, &store);
+            total_size += size;
+
+            let file_name = entry.file_name();
+            let is_current = current_session_dir.map_or(false, |dir| {
+                dir.file_name() == Some(file_name.as_os_str())
+            });
+            let entry_name = file_name.to_string_lossy();
+            if is_current || !is_session_directory(&entry_name) || !is_finalized(&entry_name) {
+                continue
+            }
+
+            match extract_timestamp_from_session_dir(&entry_name) {
+                Ok(timestamp) => candidates.push((timestamp, size, path)),
+                Err(()) => {
+                    debug!("garbage_collect_incr_comp_dir() - malformed timestamp: {}",
+                           path.display());
+                }
+            }
+        }
+    }
+
+    let initial_size = total_size;
+    let mut deleted = 0;
+
+    let to_delete = select_least_recently_used(candidates.iter().map(|&(timestamp, size, _)| {
+        (timestamp, size)
+    }).collect(), total_size, max_size, max_age, SystemTime::now());
+
+    for index in to_delete {
+        let (_, size, ref path) = candidates[index];
+        let lock_file_path = lock_file_path(path);
+
+        // Make sure that nobody is reading from the directory, or has just
+        // picked it as the source of a new session.
+        let lock = match flock::Lock::new(&lock_file_path,
+                                          false,  // don't wait
+                                          false,  // don't create the lock-file
+                                          true) { // get an exclusive lock
+            Ok(lock) => lock,
+            Err(_) => {
+                debug!("garbage_collect_incr_comp_dir() - `{}` is in use, skipping",
+                       path.display());
+                continue
+            }
+        };
+
+        debug!("garbage_collect_incr_comp_dir() - deleting `{}`", path.display());
+        if let Err(err) = safe_remove_dir_all(path) {
+            sess.warn(&format!("Failed to garbage collect incremental \
+                                compilation session directory `{}`: {}",
+                               path.display(),
+                               err));
+        } else {
+            delete_session_dir_lock_file(sess, &lock_file_path);
+            total_size -= size;
+            deleted += 1;
+        }
+
+        mem::drop(lock);
+    }
+
+    // Deleting a session directory doesn't free the work products it shares
+    // with other sessions through the store, so the cache may end up a bit
+    // smaller than needed.
+    if !store.is_empty() {
+        total_size -= prune_work_product_store(sess, incr_dir, current_session_dir, &store);
+    }
+
+    if sess.opts.debugging_opts.incremental_info {
+        println!("[incremental] garbage collection deleted {} session \
+                  directories, cache size: {} KiB -> {} KiB",
+                 deleted,
+                 initial_size / 1024,
+                 total_size / 1024);
+    }
+
+    Ok(())
+}
+
+/// Given the timestamp and size of the session directories which may be
+/// deleted, returns the indices of the ones to delete, least recently used
+/// first: the ones older than `max_age`, and then as many as needed to bring
+/// `total_size` down to `max_size`.
+fn select_least_recently_used(candidates: Vec<(SystemTime, u64)>,
+                              mut total_size: u64,
+                              max_size: Option<u64>,
+                              max_age: Option<Duration>,
+                              now: SystemTime)
+                              -> Vec<usize> {
+    let mut indices: Vec<usize> = (0 .. candidates.len()).collect();
+    indices.sort_by_key(|&index| candidates[index].0);
+
+    let oldest_allowed = max_age.and_then(|max_age| now.checked_sub(max_age));
+
+    indices.into_iter().filter(|&index| {
+        let (timestamp, size) = candidates[index];
+        let too_old = oldest_allowed.map_or(false, |oldest| timestamp < oldest);
+        let too_big = max_size.map_or(false, |max_size| total_size > max_size);
+        if too_old || too_big {
+            total_size = total_size.saturating_sub(size);
+            true
+        } else {
+            false
+        }
+    }).collect()
+}
+
+/// The total size of the files in `path`, which may be a directory, not
+/// counting the links to the files of the work product store.
+fn disk_usage(path: &Path, store: &FxHashMap<String, (u64, SystemTime)>) -> u64 {
+    let metadata = match std_fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        return match work_product_store_file_name(&file_name) {
+            Some(store_file) if store.contains_key(store_file) => 0,
+            _ => metadata.len(),
+        }
+    }
+    match path.read_dir() {
+        Ok(entries) => entries.filter_map(|entry| entry.ok())
+                              .map(|entry| disk_usage(&entry.path(), store))
+                              .sum(),
+        Err(_) => 0,
+    }
+}
+
+/// Lists the files of the work product store, with their size and the time
+/// they were last modified at.
+fn read_work_product_store(store_dir: &Path) -> FxHashMap<String, (u64, SystemTime)> {
+    let entries = match store_dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return FxHashMap::default(),
+    };
+    entries.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let metadata = entry.metadata().ok()?;
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        Some((entry.file_name().to_string_lossy().into_owned(), (metadata.len(), modified)))
+    }).collect()
+}
+
+/// Deletes the files of the work product store which aren't linked to by any
+/// session directory anymore, returning the number of bytes freed. Recent
+/// files are kept, since the session which added them may not have linked
+/// them into its directory yet.
+///
+/// Nothing is deleted while another session is running, since it may link
+/// any file of the store into its directory. The session directories are
+/// locked until the files are deleted, so that no session starts in between.
+fn prune_work_product_store(sess: &Session,
+                            incr_dir: &Path,
+                            current_session_dir: Option<&Path>,
+                            store: &FxHashMap<String, (u64, SystemTime)>)
+                            -> u64 {
+    let mut referenced = FxHashSet::default();
+    let mut locks = vec![];
+    let session_dirs = incr_dir.read_dir().into_iter()
+        .flat_map(|crate_dirs| crate_dirs.filter_map(|entry| entry.ok()))
+        .filter(|crate_dir| crate_dir.file_name() != WORK_PRODUCT_STORE_DIR)
+        .filter_map(|crate_dir| crate_dir.path().read_dir().ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()));
+    for session_dir in session_dirs {
+        if !is_session_directory(&session_dir.file_name().to_string_lossy()) {
+            continue
+        }
+
+        // The current session already holds the lock of its directory.
+        let session_dir_path = session_dir.path();
+        if current_session_dir != Some(session_dir_path.as_path()) {
+            let lock_file_path = lock_file_path(&session_dir_path);
+            if lock_file_path.exists() {
+                match flock::Lock::new(&lock_file_path,
+                                       false,   // don't wait
+                                       false,   // don't create the lock-file
+                                       false) { // not exclusive
+                    Ok(lock) => locks.push(lock),
+                    Err(_) => {
+                        debug!("prune_work_product_store() - `{}` is in use, skipping",
+                               session_dir_path.display());
+                        return 0
+                    }
+                }
+            }
+        }
+
+        let files = match session_dir_path.read_dir() {
+            Ok(files) => files,
+            Err(_) => {
+                // We can't tell which files this session needs
+                return 0
+            }
+        };
+        for file in files.filter_map(|file| file.ok()) {
+            let file_name = file.file_name().to_string_lossy().into_owned();
+            if let Some(store_file) = work_product_store_file_name(&file_name) {
+                referenced.insert(store_file.to_string());
+            }
+        }
+    }
+
+    let store_dir = incr_dir.join(WORK_PRODUCT_STORE_DIR);
+    let mut freed = 0;
+    for (file_name, &(size, modified)) in store {
+        if referenced.contains(file_name) || !is_old_enough_to_be_collected(modified) {
+            continue
+        }
+        let path = store_dir.join(file_name);
+        debug!("prune_work_product_store() - deleting `{}`", path.display());
+        match safe_remove_file(&path) {
+            Ok(()) => freed += size,
+            Err(err) => {
+                sess.warn(&format!("Failed to delete unused incremental compilation \
+                                    work product `{}`: {}",
+                                   path.display(),
+                                   err));
+            }
+        }
+    }
+
+    mem::drop(locks);
+    freed
+}
+
 /// Since paths of artifacts within session directories can get quite long, we
 /// need to support deleting files with very long paths. The regular
 /// WinApi functions only support paths up to 260 characters, however. In order
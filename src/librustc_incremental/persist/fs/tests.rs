@@ -63,3 +63,65 @@ fn test_find_source_directory_in_iter() {
              PathBuf::from("crate-dir/s-1234-0000-working")].into_iter(), &already_visited),
         None);
 }
+
+#[test]
+fn test_select_least_recently_used() {
+    let now = UNIX_EPOCH + Duration::new(100, 0);
+    let candidates = vec![
+        (UNIX_EPOCH + Duration::new(30, 0), 10),
+        (UNIX_EPOCH + Duration::new(10, 0), 20),
+        (UNIX_EPOCH + Duration::new(90, 0), 40),
+        (UNIX_EPOCH + Duration::new(20, 0), 30),
+    ];
+
+    // No limits
+    assert_eq!(select_least_recently_used(candidates.clone(), 100, None, None, now),
+               Vec::<usize>::new());
+
+    // Under the size limit
+    assert_eq!(select_least_recently_used(candidates.clone(), 100, Some(100), None, now),
+               Vec::<usize>::new());
+
+    // Oldest first, until the cache fits
+    assert_eq!(select_least_recently_used(candidates.clone(), 100, Some(60), None, now),
+               vec![1, 3]);
+
+    // Sizes outside of the candidates count towards the total
+    assert_eq!(select_least_recently_used(candidates.clone(), 150, Some(60), None, now),
+               vec![1, 3, 0, 2]);
+
+    // Everything older than the maximum age
+    assert_eq!(select_least_recently_used(candidates.clone(), 100, None,
+                                          Some(Duration::new(75, 0)), now),
+               vec![1, 3]);
+
+    // Both limits
+    assert_eq!(select_least_recently_used(candidates.clone(), 100, Some(70),
+                                          Some(Duration::new(85, 0)), now),
+               vec![1, 3]);
+    assert_eq!(select_least_recently_used(candidates, 100, Some(45),
+                                          Some(Duration::new(85, 0)), now),
+               vec![1, 3, 0]);
+}
+
+#[test]
+fn test_work_product_store_file_name() {
+    assert_eq!(work_product_store_file_name("foo.7rcbfp3g-cgu.0-00000000000000000001a2b3c.o"),
+               Some("00000000000000000001a2b3c.o"));
+    assert_eq!(work_product_store_file_name("foo.7rcbfp3g-cgu.0-zy000000000000000001a2b3c.bc.z"),
+               Some("zy000000000000000001a2b3c.bc.z"));
+}
+
+#[test]
+fn test_work_product_store_file_name_other_files() {
+    assert_eq!(work_product_store_file_name("dep-graph.bin"), None);
+    assert_eq!(work_product_store_file_name("work-products.bin"), None);
+    assert_eq!(work_product_store_file_name("foo.7rcbfp3g-cgu.0.o"), None);
+    // The hash is too short.
+    assert_eq!(work_product_store_file_name("foo.7rcbfp3g-cgu.0-1a2b3c.o"), None);
+    // The extension isn't the one of a work product.
+    assert_eq!(work_product_store_file_name("foo.7rcbfp3g-cgu.0-00000000000000000001a2b3c.bin"),
+               None);
+    // There is no CGU name.
+    assert_eq!(work_product_store_file_name("-00000000000000000001a2b3c.o"), None);
+}
@@ -113,6 +113,7 @@ pub fn load_dep_graph(sess: &Session) -> DepGraphFuture {
     // Fortunately, we just checked that this isn't the case.
     let path = dep_graph_path_from(&sess.incr_comp_session_dir());
     let report_incremental_info = sess.opts.debugging_opts.incremental_info;
+    let explain = sess.opts.debugging_opts.incremental_explain.is_some();
     let expected_hash = sess.opts.dep_tracking_hash();
 
     let mut prev_work_products = FxHashMap::default();
@@ -171,7 +172,7 @@ pub fn load_dep_graph(sess: &Session) -> DepGraphFuture {
                         .expect("Error reading commandline arg hash from cached dep-graph");
 
                     if prev_commandline_args_hash != expected_hash {
-                        if report_incremental_info {
+                        if report_incremental_info || explain {
                             println!("[incremental] completely ignoring cache because of \
                                     differing commandline arguments");
                         }
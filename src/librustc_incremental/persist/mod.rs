@@ -11,6 +11,7 @@ mod work_product;
 mod file_format;
 
 pub use fs::finalize_session_directory;
+pub use fs::garbage_collect_incr_comp_dir;
 pub use fs::garbage_collect_session_directories;
 pub use fs::in_incr_comp_dir;
 pub use fs::in_incr_comp_dir_sess;
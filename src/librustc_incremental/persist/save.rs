@@ -236,5 +236,9 @@ fn encode_work_product_index(work_products: &FxHashMap<WorkProductId, WorkProduc
 fn encode_query_cache(tcx: TyCtxt<'_>, encoder: &mut Encoder) {
     time(tcx.sess, "serialize query result cache", || {
         tcx.serialize_query_result_cache(encoder).unwrap();
-    })
+    });
+
+    if tcx.sess.opts.debugging_opts.incremental_cache_stats {
+        tcx.print_query_result_cache_stats();
+    }
 }
@@ -3,8 +3,13 @@
 use crate::persist::fs::*;
 use rustc::dep_graph::{WorkProduct, WorkProductId, WorkProductFileKind};
 use rustc::session::Session;
+use rustc_data_structures::base_n;
+use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_fs_util::link_or_copy;
-use std::path::PathBuf;
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::fs as std_fs;
 
 pub fn copy_cgu_workproducts_to_incr_comp_cache_dir(
@@ -27,6 +32,18 @@ pub fn copy_cgu_workproducts_to_incr_comp_cache_dir(
                      WorkProductFileKind::Bytecode => "bc",
                      WorkProductFileKind::BytecodeCompressed => "bc.z",
                  };
+                 if sess.opts.debugging_opts.incremental_shared {
+                     return match save_in_work_product_store(sess, cgu_name, extension, path) {
+                         Ok(file_name) => Some((kind, file_name)),
+                         Err(err) => {
+                             sess.warn(&format!("error saving object file `{}` \
+                                                 in incremental work product store: {}",
+                                                path.display(),
+                                                err));
+                             None
+                         }
+                     }
+                 }
                  let file_name = format!("{}.{}", cgu_name, extension);
                  let path_in_incr_dir = in_incr_comp_dir_sess(sess, &file_name);
                  match link_or_copy(path, &path_in_incr_dir) {
@@ -52,6 +69,53 @@ pub fn copy_cgu_workproducts_to_incr_comp_cache_dir(
     Some((work_product_id, work_product))
 }
 
+/// Saves the file at `path` in the work product store of
+/// `-Z incremental-shared`, under a name made of the hash of its content, and
+/// links it into the session directory. Returns the name of the file in the
+/// session directory, which refers to the file of the store.
+fn save_in_work_product_store(sess: &Session,
+                              cgu_name: &str,
+                              extension: &str,
+                              path: &Path)
+                              -> io::Result<String> {
+    let mut hasher: StableHasher<u128> = StableHasher::new();
+    let mut file = std_fs::File::open(path)?;
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            n => hasher.write(&buffer[..n]),
+        }
+    }
+    let hash = base_n::encode(hasher.finish(), base_n::CASE_INSENSITIVE);
+    let store_file_name = format!("{:0>2$}.{}", hash, extension, WORK_PRODUCT_HASH_LEN);
+
+    let store_dir = work_product_store_path(sess);
+    std_fs::create_dir_all(&store_dir)?;
+    let path_in_store = store_dir.join(&store_file_name);
+    let file_name = format!("{}-{}", cgu_name, store_file_name);
+    debug_assert_eq!(work_product_store_file_name(&file_name), Some(&store_file_name[..]));
+    let path_in_session_dir = in_incr_comp_dir_sess(sess, &file_name);
+
+    // The file may be pruned from the store by a garbage collection which
+    // started before this session, between the check and the link.
+    let mut saved = path_in_store.exists();
+    loop {
+        if !saved {
+            // Go through a temporary file, so that other sessions never see a
+            // partially written work product.
+            let tmp_path = store_dir.join(format!("{}.{}.tmp", store_file_name, process::id()));
+            link_or_copy(path, &tmp_path)?;
+            std_fs::rename(&tmp_path, &path_in_store)?;
+        }
+        match link_or_copy(&path_in_store, &path_in_session_dir) {
+            Ok(_) => return Ok(file_name),
+            Err(ref err) if saved && err.kind() == io::ErrorKind::NotFound => saved = false,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 pub fn delete_workproduct_files(sess: &Session, work_product: &WorkProduct) {
     for &(_, ref file_name) in &work_product.saved_files {
         let path = in_incr_comp_dir_sess(sess, file_name);
@@ -125,6 +125,8 @@ where
         profile::dump(&compiler.sess, "profile_queries".to_string())
     }
 
+    compiler.sess.write_self_profile_reports();
+
     r
 }
 
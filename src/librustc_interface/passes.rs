@@ -885,6 +885,12 @@ pub fn create_global_ctxt(
         if sess.opts.debugging_opts.query_stats {
             gcx.queries.print_stats();
         }
+
+        if let Some(top) = sess.opts.debugging_opts.item_time_report {
+            ty::tls::enter_global(gcx, |tcx| {
+                sess.item_times.as_ref().unwrap().print(top, |def_id| tcx.def_path_str(def_id));
+            });
+        }
     });
 
     result
@@ -898,6 +904,8 @@ fn analysis(tcx: TyCtxt<'_>, cnum: CrateNum) -> Result<()> {
     let sess = tcx.sess;
     let mut entry_point = None;
 
+    mir::transform::check_enabled_passes(sess);
+
     time(sess, "misc checking 1", || {
         parallel!({
             entry_point = time(sess, "looking for entry point", || {
@@ -962,9 +970,7 @@ fn analysis(tcx: TyCtxt<'_>, cnum: CrateNum) -> Result<()> {
     });
 
     time(sess, "MIR effect checking", || {
-        for def_id in tcx.body_owners() {
-            mir::transform::check_unsafety::check_unsafety(tcx, def_id)
-        }
+        tcx.par_body_owners(|def_id| mir::transform::check_unsafety::check_unsafety(tcx, def_id));
     });
 
     time(sess, "layout testing", || layout_test::test_layout(tcx));
@@ -1087,6 +1093,9 @@ pub fn start_codegen<'tcx>(
         middle::dependency_format::calculate(tcx)
     });
 
+    // The `.rmeta` file is written (and announced with an artifact notification)
+    // before codegen starts, so that with `--emit=metadata,link` a build system
+    // can start compiling dependent crates while we are still generating code.
     let (metadata, need_metadata_module) = time(tcx.sess, "metadata encoding and writing", || {
         encode_and_write_metadata(tcx, outputs)
     });
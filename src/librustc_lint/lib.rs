@@ -38,6 +38,7 @@ use rustc::lint::builtin::{
     ELIDED_LIFETIMES_IN_PATHS,
     EXPLICIT_OUTLIVES_REQUIREMENTS,
     INTRA_DOC_LINK_RESOLUTION_FAILURE,
+    BROKEN_DOC_LINKS,
     MISSING_DOC_CODE_EXAMPLES,
     PRIVATE_DOC_TESTS,
     parser::ILL_FORMED_ATTRIBUTE_INPUT,
@@ -147,6 +148,7 @@ macro_rules! late_lint_mod_passes {
             HardwiredLints: HardwiredLints,
             ImproperCTypes: ImproperCTypes,
             VariantSizeDifferences: VariantSizeDifferences,
+            LargeFutures: LargeFutures,
             BoxPointers: BoxPointers,
             PathStatements: PathStatements,
 
@@ -288,6 +290,7 @@ pub fn register_builtins(store: &mut lint::LintStore, sess: Option<&Session>) {
     add_lint_group!(sess,
                     "rustdoc",
                     INTRA_DOC_LINK_RESOLUTION_FAILURE,
+                    BROKEN_DOC_LINKS,
                     MISSING_DOC_CODE_EXAMPLES,
                     PRIVATE_DOC_TESTS);
 
@@ -5,7 +5,7 @@ use crate::hir::def_id::DefId;
 use rustc::hir::lowering::is_range_literal;
 use rustc::ty::subst::SubstsRef;
 use rustc::ty::{self, AdtKind, ParamEnv, Ty, TyCtxt};
-use rustc::ty::layout::{self, IntegerExt, LayoutOf, VariantIdx, SizeSkeleton};
+use rustc::ty::layout::{self, IntegerExt, LayoutCx, LayoutOf, VariantIdx, SizeSkeleton};
 use rustc::{lint, util};
 use rustc_data_structures::indexed_vec::Idx;
 use util::nodemap::FxHashSet;
@@ -45,6 +45,12 @@ declare_lint! {
     "detects enums with widely varying variant sizes"
 }
 
+declare_lint! {
+    LARGE_FUTURES,
+    Allow,
+    "detects `async` functions and blocks whose futures are larger than `-Z future-size-limit`"
+}
+
 #[derive(Copy, Clone)]
 pub struct TypeLimits {
     /// Id of the last visited negated expression
@@ -1001,3 +1007,65 @@ impl<'a, 'tcx> LateLintPass<'a, 'tcx> for VariantSizeDifferences {
         }
     }
 }
+
+declare_lint_pass!(LargeFutures => [LARGE_FUTURES]);
+
+impl<'a, 'tcx> LateLintPass<'a, 'tcx> for LargeFutures {
+    fn check_expr(&mut self, cx: &LateContext<'a, 'tcx>, expr: &hir::Expr) {
+        let body_id = match expr.node {
+            hir::ExprKind::Closure(_, _, body_id, _, Some(_)) => body_id,
+            _ => return,
+        };
+        if cx.tcx.hir().body(body_id).generator_kind != Some(hir::GeneratorKind::Async) {
+            return;
+        }
+        // Computing the layout of a generator requires its optimized MIR, which is only
+        // worth it if the lint is enabled.
+        if cx.tcx.lint_level_at_node(LARGE_FUTURES, expr.hir_id).0 == lint::Allow {
+            return;
+        }
+
+        let ty = cx.tcx.erase_regions(&cx.tables.expr_ty(expr));
+        let (def_id, substs) = match ty.sty {
+            ty::Generator(def_id, substs, _) => (def_id, substs),
+            _ => bug!("`async` body of type `{}`", ty),
+        };
+        let layout = match cx.layout_of(ty) {
+            Ok(layout) => layout,
+            // The size of futures depending on type parameters isn't known, and futures that
+            // are too large for the target are reported when they are compiled.
+            Err(_) => return,
+        };
+        let limit = cx.sess().opts.debugging_opts.future_size_limit as u64;
+        if layout.size.bytes() <= limit {
+            return;
+        }
+
+        let layout_cx = LayoutCx { tcx: cx.tcx, param_env: cx.param_env };
+        let info = layout_cx.generator_size_info(layout, def_id, substs);
+        let mut err = cx.struct_span_lint(
+            LARGE_FUTURES,
+            expr.span,
+            &format!("this future is {} bytes large, more than the limit of {} bytes",
+                     layout.size.bytes(), limit),
+        );
+
+        // Point at the largest values stored in the future.
+        let mut values: Vec<_> = info.upvars.iter().map(|(field, span)| {
+            (field.size, *span, format!("`{}` is captured", field.name))
+        }).chain(info.saved_locals.iter().map(|local| {
+            let awaits = match local.live_at.len() {
+                1 => String::from("an `await`"),
+                n => format!("{} `await`s", n),
+            };
+            (local.size, local.span, format!("this value is held across {}", awaits))
+        })).collect();
+        values.sort_by(|(size1, ..), (size2, ..)| size2.cmp(size1));
+        for (size, span, what) in values.into_iter().filter(|&(size, ..)| size > 0).take(3) {
+            err.span_note(span, &format!("{}, taking {} bytes", what, size));
+        }
+        err.help("box the largest values, or the future itself with `Box::pin`, to store them \
+                  on the heap instead of the stack");
+        err.emit();
+    }
+}
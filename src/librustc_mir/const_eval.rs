@@ -32,6 +32,9 @@ const STEPS_UNTIL_DETECTOR_ENABLED: isize = 1_000_000;
 /// The number of steps between loop detector snapshots.
 /// Should be a power of two for performance reasons.
 const DETECTOR_SNAPSHOT_PERIOD: isize = 256;
+/// The number of terminators const propagation may evaluate in a call to a `const fn`, so that
+/// expensive or diverging calls are left for runtime instead of stalling the compilation.
+const CONST_FN_CALL_STEP_LIMIT: usize = 1_000;
 
 /// The `InterpCx` is only meant to be used to do field and index projections into constants for
 /// `simd_shuffle` and const patterns in match arms.
@@ -60,6 +63,43 @@ pub(crate) fn eval_promoted<'mir, 'tcx>(
     eval_body_using_ecx(&mut ecx, cid, body, param_env)
 }
 
+/// Evaluates a call to the `const fn` `instance` with the arguments `args`. This is used by
+/// const propagation, whose arguments and result can only be immediates.
+pub(crate) fn eval_const_fn_call<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    span: Span,
+    param_env: ty::ParamEnv<'tcx>,
+    instance: ty::Instance<'tcx>,
+    args: &[ImmTy<'tcx>],
+) -> InterpResult<'tcx, Immediate> {
+    let mut ecx = mk_eval_cx(tcx, span, param_env);
+    // Recursive calls such as `fib(60)` could take arbitrarily long, so give up early.
+    ecx.machine.steps_remaining = Some(CONST_FN_CALL_STEP_LIMIT);
+    let body = ecx.load_mir(instance.def)?;
+    if body.arg_count != args.len() || body.spread_arg.is_some() {
+        throw_unsup!(FunctionArgCountMismatch)
+    }
+
+    let layout = ecx.layout_of(body.return_ty().subst(tcx, instance.substs))?;
+    let ret = ecx.allocate(layout, MemoryKind::Stack);
+    ecx.push_stack_frame(
+        instance,
+        span,
+        body,
+        Some(ret.into()),
+        StackPopCleanup::None { cleanup: false },
+    )?;
+    for (local, arg) in body.args_iter().zip(args) {
+        let dest = ecx.eval_place(&mir::Place::from(local))?;
+        ecx.write_immediate(**arg, dest)?;
+    }
+
+    // The main interpreter loop.
+    ecx.run()?;
+
+    Ok(*ecx.read_immediate(ret.into())?)
+}
+
 fn op_to_const<'tcx>(
     ecx: &CompileTimeEvalContext<'_, 'tcx>,
     op: OpTy<'tcx>,
@@ -229,6 +269,9 @@ pub struct CompileTimeInterpreter<'mir, 'tcx> {
 
     /// Extra state to detect loops.
     pub(super) loop_detector: snapshot::InfiniteLoopDetector<'mir, 'tcx>,
+
+    /// The number of terminators which may still be evaluated, if the evaluation is limited.
+    pub(super) steps_remaining: Option<usize>,
 }
 
 impl<'mir, 'tcx> CompileTimeInterpreter<'mir, 'tcx> {
@@ -236,6 +279,7 @@ impl<'mir, 'tcx> CompileTimeInterpreter<'mir, 'tcx> {
         CompileTimeInterpreter {
             loop_detector: Default::default(),
             steps_since_detector_enabled: -STEPS_UNTIL_DETECTOR_ENABLED,
+            steps_remaining: None,
         }
     }
 }
@@ -456,6 +500,13 @@ impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for CompileTimeInterpreter<'mir,
     }
 
     fn before_terminator(ecx: &mut InterpCx<'mir, 'tcx, Self>) -> InterpResult<'tcx> {
+        if let Some(steps) = &mut ecx.machine.steps_remaining {
+            if *steps == 0 {
+                throw_exhaust!(StepLimitReached)
+            }
+            *steps -= 1;
+        }
+
         {
             let steps = &mut ecx.machine.steps_since_detector_enabled;
 
@@ -33,9 +33,11 @@ impl<'a, 'tcx> BitDenotation<'tcx> for MaybeStorageLive<'a, 'tcx> {
     }
 
     fn start_block_effect(&self, _on_entry: &mut BitSet<Local>) {
-        // Nothing is live on function entry (generators only have a self
-        // argument, and we don't care about that)
-        assert_eq!(1, self.body.arg_count);
+        // Nothing is live on function entry. This used to assert that the body
+        // has a single argument, since only the generator transform used this
+        // analysis, but the MIR validator runs it on every body. Neither of them
+        // cares about the arguments and the return place: they have no storage
+        // statements, so their storage is always live.
     }
 
     fn statement_effect(&self,
@@ -197,6 +197,7 @@ use rustc::util::common::time;
 use rustc_data_structures::bit_set::GrowableBitSet;
 use rustc_data_structures::sync::{MTRef, MTLock, ParallelIterator, par_iter};
 
+use std::collections::VecDeque;
 use std::iter;
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -218,15 +219,19 @@ pub struct InliningMap<'tcx> {
     // Contains one bit per mono item in the `targets` field. That bit
     // is true if that mono item needs to be inlined into every CGU.
     inlines: GrowableBitSet<usize>,
+
+    // The items the collection started from.
+    roots: Vec<MonoItem<'tcx>>,
 }
 
 impl<'tcx> InliningMap<'tcx> {
 
-    fn new() -> InliningMap<'tcx> {
+    fn new(roots: Vec<MonoItem<'tcx>>) -> InliningMap<'tcx> {
         InliningMap {
             index: FxHashMap::default(),
             targets: Vec::new(),
             inlines: GrowableBitSet::with_capacity(1024),
+            roots,
         }
     }
 
@@ -279,6 +284,28 @@ impl<'tcx> InliningMap<'tcx> {
             f(accessor, &self.targets[start_index .. end_index])
         }
     }
+
+    // Maps every item, except for the roots, to the item which first
+    // accessed it in a breadth-first walk from the roots. Following these
+    // links leads to a root through a shortest chain of accesses.
+    pub fn first_accessors(&self) -> FxHashMap<MonoItem<'tcx>, MonoItem<'tcx>> {
+        let mut accessors = FxHashMap::default();
+        let mut seen: FxHashSet<_> = self.roots.iter().cloned().collect();
+        let mut queue: VecDeque<_> = self.roots.iter().cloned().collect();
+
+        while let Some(source) = queue.pop_front() {
+            if let Some(&(start_index, end_index)) = self.index.get(&source) {
+                for &target in &self.targets[start_index .. end_index] {
+                    if seen.insert(target) {
+                        accessors.insert(target, source);
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+
+        accessors
+    }
 }
 
 pub fn collect_crate_mono_items(
@@ -292,7 +319,7 @@ pub fn collect_crate_mono_items(
     debug!("building mono item graph, beginning at roots");
 
     let mut visited = MTLock::new(FxHashSet::default());
-    let mut inlining_map = MTLock::new(InliningMap::new());
+    let mut inlining_map = MTLock::new(InliningMap::new(roots.clone()));
 
     {
         let visited: MTRef<'_, _> = &mut visited;
@@ -393,7 +420,12 @@ fn collect_items_rec<'tcx>(
                                                                recursion_depths));
             check_type_length_limit(tcx, instance);
 
-            collect_neighbours(tcx, instance, &mut neighbors);
+            match tcx.sess.item_times {
+                Some(ref item_times) => item_times.time_instance("monomorphization", instance, || {
+                    collect_neighbours(tcx, instance, &mut neighbors)
+                }),
+                None => collect_neighbours(tcx, instance, &mut neighbors),
+            }
         }
         MonoItem::GlobalAsm(..) => {
             recursion_depth_reset = None;
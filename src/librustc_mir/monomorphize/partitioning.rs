@@ -102,6 +102,7 @@ use rustc::hir::def::DefKind;
 use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE, CRATE_DEF_INDEX};
 use rustc::mir::mono::{Linkage, Visibility, CodegenUnitNameBuilder, CodegenUnit};
 use rustc::middle::exported_symbols::SymbolExportLevel;
+use rustc::session::MonoItemInfo;
 use rustc::ty::{self, DefIdTree, TyCtxt, InstanceDef};
 use rustc::ty::print::characteristic_def_id_of_type;
 use rustc::ty::query::Providers;
@@ -959,9 +960,66 @@ fn collect_and_partition_mono_items(
         }
     }
 
+    if tcx.sess.opts.debugging_opts.print_mono_item_sizes {
+        record_mono_item_origins(tcx, &items, &inlining_map);
+    }
+
     (Arc::new(mono_items), Arc::new(codegen_units))
 }
 
+/// Records why each mono item had to be instantiated, for
+/// `-Z print-mono-item-sizes`. The size of the code generated for them is
+/// recorded by the backend.
+fn record_mono_item_origins<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    items: &FxHashSet<MonoItem<'tcx>>,
+    inlining_map: &InliningMap<'tcx>,
+) {
+    let accessors = inlining_map.first_accessors();
+    let krate = |item: MonoItem<'tcx>| match item {
+        MonoItem::Fn(instance) => instance.def_id().krate,
+        MonoItem::Static(def_id) => def_id.krate,
+        MonoItem::GlobalAsm(..) => LOCAL_CRATE,
+    };
+
+    let infos: Vec<_> = items.iter().filter_map(|&item| {
+        let generic = match item {
+            MonoItem::Fn(instance) => {
+                if instance.substs.non_erasable_generics().next().is_some() {
+                    Some(tcx.def_path_str(instance.def_id()))
+                } else {
+                    None
+                }
+            }
+            MonoItem::Static(_) => None,
+            MonoItem::GlobalAsm(..) => return None,
+        };
+
+        let mut chain = Vec::new();
+        let mut current = item;
+        while let Some(&accessor) = accessors.get(&current) {
+            chain.push(accessor.to_string(tcx, false));
+            current = accessor;
+        }
+        chain.reverse();
+
+        let requested_by = accessors.get(&item).map_or(LOCAL_CRATE, |&accessor| krate(accessor));
+
+        Some(MonoItemInfo {
+            description: item.to_string(tcx, false),
+            symbol_name: item.symbol_name(tcx).name.as_str().to_string(),
+            generic,
+            requested_by: tcx.crate_name(requested_by).to_string(),
+            chain,
+        })
+    }).collect();
+
+    let mut code_stats = tcx.sess.code_stats.borrow_mut();
+    for info in infos {
+        code_stats.record_mono_item(info);
+    }
+}
+
 pub fn provide(providers: &mut Providers<'_>) {
     providers.collect_and_partition_mono_items =
         collect_and_partition_mono_items;
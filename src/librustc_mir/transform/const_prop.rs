@@ -1,5 +1,6 @@
 //! Propagates constants for early reporting of statically known
-//! assertion failures
+//! assertion failures, and replaces the operands, assertion conditions
+//! and switch discriminants whose value is known by constants.
 
 use std::cell::Cell;
 
@@ -10,16 +11,17 @@ use rustc::mir::{
     TerminatorKind, Terminator,  ClearCrossCrate, SourceInfo, BinOp, ProjectionElem,
     SourceScope, SourceScopeLocalData, LocalDecl, Promoted,
 };
+use rustc::mir::traversal;
 use rustc::mir::visit::{
     Visitor, PlaceContext, MutatingUseContext, MutVisitor, NonMutatingUseContext,
 };
 use rustc::mir::interpret::{Scalar, GlobalId, InterpResult, PanicInfo};
-use rustc::ty::{self, Instance, ParamEnv, Ty, TyCtxt};
+use rustc::ty::{self, Instance, ParamEnv, Ty, TyCtxt, TypeFoldable};
 use syntax_pos::{Span, DUMMY_SP};
 use rustc::ty::subst::InternalSubsts;
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc::ty::layout::{
-    LayoutOf, TyLayout, LayoutError, HasTyCtxt, TargetDataLayout, HasDataLayout,
+    LayoutOf, TyLayout, LayoutError, HasTyCtxt, TargetDataLayout, HasDataLayout, Abi,
 };
 
 use crate::interpret::{
@@ -27,9 +29,9 @@ use crate::interpret::{
     ImmTy, MemoryKind, StackPopCleanup, LocalValue, LocalState,
 };
 use crate::const_eval::{
-    CompileTimeInterpreter, error_to_const_error, eval_promoted, mk_eval_cx,
+    CompileTimeInterpreter, error_to_const_error, eval_const_fn_call, eval_promoted, mk_eval_cx,
 };
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{self, MirPass, MirSource};
 
 pub struct ConstProp;
 
@@ -96,7 +98,12 @@ impl MirPass for ConstProp {
             tcx,
             source
         );
-        optimization_finder.visit_body(body);
+        // Visit the blocks in reverse postorder: the assignment of a temporary dominates its
+        // uses, so its value is known when they are visited, even in other blocks.
+        let blocks: Vec<_> = traversal::reverse_postorder(body).map(|(bb, _)| bb).collect();
+        for bb in blocks {
+            optimization_finder.visit_basic_block_data(bb, &mut body.basic_blocks_mut()[bb]);
+        }
 
         // put back the data we stole from `mir`
         let (source_scope_local_data, promoted) = optimization_finder.release_stolen_data();
@@ -125,6 +132,8 @@ struct ConstPropagator<'mir, 'tcx> {
     source_scope_local_data: ClearCrossCrate<IndexVec<SourceScope, SourceScopeLocalData>>,
     local_decls: IndexVec<Local, LocalDecl<'tcx>>,
     promoted: IndexVec<Promoted, Body<'tcx>>,
+    /// The source info of the statement or terminator being visited.
+    source_info: Option<SourceInfo>,
 }
 
 impl<'mir, 'tcx> LayoutOf for ConstPropagator<'mir, 'tcx> {
@@ -185,6 +194,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
             //FIXME(wesleywiser) we can't steal this because `Visitor::super_visit_body()` needs it
             local_decls: body.local_decls.clone(),
             promoted,
+            source_info: None,
         }
     }
 
@@ -541,6 +551,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
         rval: &mut Rvalue<'tcx>,
         value: Const<'tcx>,
         source_info: SourceInfo,
+        location: Location,
     ) {
         trace!("attepting to replace {:?} with {:?}", rval, value);
         if let Err(e) = self.ecx.validate_operand(
@@ -559,10 +570,10 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
         });
 
         if let Some(Ok(imm)) = imm {
-            match *imm {
+            let new_rval = match *imm {
                 interpret::Immediate::Scalar(ScalarMaybeUndef::Scalar(scalar)) => {
-                    *rval = Rvalue::Use(
-                        self.operand_from_scalar(scalar, value.layout.ty, source_info.span));
+                    Rvalue::Use(
+                        self.operand_from_scalar(scalar, value.layout.ty, source_info.span))
                 },
                 Immediate::ScalarPair(
                     ScalarMaybeUndef::Scalar(one),
@@ -570,7 +581,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
                 ) => {
                     let ty = &value.layout.ty.sty;
                     if let ty::Tuple(substs) = ty {
-                        *rval = Rvalue::Aggregate(
+                        Rvalue::Aggregate(
                             Box::new(AggregateKind::Tuple),
                             vec![
                                 self.operand_from_scalar(
@@ -580,16 +591,170 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
                                     two, substs[1].expect_ty(), source_info.span
                                 ),
                             ],
-                        );
+                        )
+                    } else {
+                        return;
                     }
                 },
-                _ => { }
+                _ => return,
+            };
+            if self.consider_optimizing(|| {
+                format!("replacing {:?} with {:?} at {:?}", rval, new_rval, location)
+            }) {
+                *rval = new_rval;
+            }
+        }
+    }
+
+    /// Replaces `operand` by a constant if it reads a primitive value that is known.
+    fn replace_operand(
+        &mut self,
+        operand: &mut Operand<'tcx>,
+        source_info: SourceInfo,
+        location: Location,
+    ) {
+        let value = match *operand {
+            Operand::Copy(ref place) | Operand::Move(ref place) => {
+                match self.eval_place(place, source_info) {
+                    Some(value) => value,
+                    None => return,
+                }
+            }
+            Operand::Constant(_) => return,
+        };
+        match value.layout.ty.sty {
+            ty::Bool | ty::Char | ty::Int(_) | ty::Uint(_) | ty::Float(_) => {}
+            _ => return,
+        }
+
+        if let Ok(imm) = self.ecx.try_read_immediate(value) {
+            // Pointers can point to the memory of `ecx`, which is not kept.
+            if let Ok(Immediate::Scalar(ScalarMaybeUndef::Scalar(scalar @ Scalar::Raw { .. }))) =
+                imm.map(|imm| *imm)
+            {
+                let apply = self.consider_optimizing(|| {
+                    format!("replacing {:?} with {:?} at {:?}", operand, scalar, location)
+                });
+                if apply {
+                    trace!("replacing {:?} with {:?}", operand, scalar);
+                    *operand = self.operand_from_scalar(scalar, value.layout.ty, source_info.span);
+                }
             }
         }
     }
 
+    /// Evaluates a call to a `const fn` whose arguments are known, and stores the result in
+    /// the destination like for assignments.
+    fn const_prop_call(
+        &mut self,
+        func: &Operand<'tcx>,
+        args: &[Operand<'tcx>],
+        dest: &Place<'tcx>,
+        source_info: SourceInfo,
+    ) -> Option<()> {
+        let local = match *dest {
+            Place {
+                base: PlaceBase::Local(local),
+                projection: None,
+            } if self.can_const_prop[local] => local,
+            _ => return None,
+        };
+        // Evaluating the call needs the optimized MIR of the callee and of the functions it
+        // calls, which could only need the MIR being optimized if it can be evaluated too.
+        let caller = self.source.def_id();
+        if self.tcx.is_const_fn_raw(caller) ||
+            self.tcx.def_kind(caller) == Some(DefKind::AssocConst)
+        {
+            return None;
+        }
+
+        let (def_id, substs) = match func.ty(&self.local_decls, self.tcx).sty {
+            ty::FnDef(def_id, substs) => (def_id, substs),
+            _ => return None,
+        };
+        if !self.tcx.is_const_fn(def_id) || substs.needs_subst() {
+            return None;
+        }
+
+        // Only results which are immediates can be propagated, so don't evaluate the calls
+        // returning other values, like `Vec::new()`.
+        let dest_ty = dest.ty(&self.local_decls, self.tcx).ty;
+        let layout = self.tcx.layout_of(self.param_env.and(dest_ty)).ok()?;
+        match layout.abi {
+            Abi::Scalar(_) | Abi::ScalarPair(..) => {}
+            _ => return None,
+        }
+        if self.get_const(local).is_some() {
+            // The local already has a value, which doesn't hold after this call.
+            self.remove_const(local);
+            return None;
+        }
+
+        let instance = Instance::resolve(self.tcx, self.param_env, def_id, substs)?;
+
+        let mut imm_args = Vec::with_capacity(args.len());
+        for arg in args {
+            let arg = self.eval_operand(arg, source_info)?;
+            let arg = self.ecx.read_immediate(arg).ok()?;
+            if !is_raw_immediate(&arg) {
+                return None;
+            }
+            imm_args.push(arg);
+        }
+
+        // Errors are not reported: the call is only evaluated to propagate its result, and
+        // it may panic only in code that is never executed.
+        let imm = eval_const_fn_call(
+            self.tcx,
+            source_info.span,
+            self.param_env,
+            instance,
+            &imm_args,
+        ).ok()?;
+        if !is_raw_immediate(&imm) {
+            return None;
+        }
+
+        trace!("storing the result {:?} of calling {:?} to {:?}", imm, instance, local);
+        self.set_const(local, ImmTy { imm, layout }.into());
+        Some(())
+    }
+
     fn should_const_prop(&self) -> bool {
-        self.tcx.sess.opts.debugging_opts.mir_opt_level >= 2
+        self.tcx.sess.opts.debugging_opts.mir_opt_level >= 1 ||
+            transform::is_pass_forced(self.tcx.sess, "ConstProp") == Some(true)
+    }
+
+    fn consider_optimizing<T: Fn() -> String>(&self, msg: T) -> bool {
+        transform::consider_optimizing(self.tcx, self.source, "ConstProp", msg)
+    }
+
+    /// Returns whether the operand of a terminator should be replaced by `scalar`. Operands
+    /// that are already constant, usually replaced while visiting them, are left alone.
+    fn consider_replacing(
+        &self,
+        operand: &Operand<'tcx>,
+        scalar: Scalar,
+        location: Location,
+    ) -> bool {
+        match *operand {
+            Operand::Constant(_) => false,
+            Operand::Copy(_) | Operand::Move(_) => self.consider_optimizing(|| {
+                format!("replacing {:?} with {:?} at {:?}", operand, scalar, location)
+            }),
+        }
+    }
+}
+
+/// Returns whether `imm` only holds raw bits, which don't depend on the memory of an `InterpCx`.
+fn is_raw_immediate(imm: &Immediate) -> bool {
+    match *imm {
+        Immediate::Scalar(ScalarMaybeUndef::Scalar(Scalar::Raw { .. })) => true,
+        Immediate::ScalarPair(
+            ScalarMaybeUndef::Scalar(Scalar::Raw { .. }),
+            ScalarMaybeUndef::Scalar(Scalar::Raw { .. }),
+        ) => true,
+        _ => false,
     }
 }
 
@@ -643,7 +808,8 @@ impl<'tcx> Visitor<'tcx> for CanConstProp {
             // Constants must have at most one write
             // FIXME(oli-obk): we could be more powerful here, if the multiple writes
             // only occur in independent execution paths
-            MutatingUse(MutatingUseContext::Store) => if self.found_assignment[local] {
+            MutatingUse(MutatingUseContext::Store) |
+            MutatingUse(MutatingUseContext::Call) => if self.found_assignment[local] {
                 trace!("local {:?} can't be propagated because of multiple assignments", local);
                 self.can_const_prop[local] = false;
             } else {
@@ -675,12 +841,26 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
         self.eval_constant(constant);
     }
 
+    fn visit_operand(
+        &mut self,
+        operand: &mut Operand<'tcx>,
+        location: Location,
+    ) {
+        self.super_operand(operand, location);
+        if let Some(source_info) = self.source_info {
+            if self.should_const_prop() {
+                self.replace_operand(operand, source_info, location);
+            }
+        }
+    }
+
     fn visit_statement(
         &mut self,
         statement: &mut Statement<'tcx>,
         location: Location,
     ) {
         trace!("visit_statement: {:?}", statement);
+        self.source_info = Some(statement.source_info);
         if let StatementKind::Assign(ref place, ref mut rval) = statement.kind {
             let place_ty: Ty<'tcx> = place
                 .ty(&self.local_decls, self.tcx)
@@ -692,9 +872,13 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                         projection: None,
                     } = *place {
                         trace!("checking whether {:?} can be stored to {:?}", value, local);
-                        if self.can_const_prop[local] {
+                        if self.can_const_prop[local] && self.get_const(local).is_some() {
+                            // The local already has a value, e.g. when it was written
+                            // through a projection: forget it, since it doesn't hold anymore.
+                            trace!("not storing {:?} to {:?}, it has a value", value, local);
+                            self.remove_const(local);
+                        } else if self.can_const_prop[local] {
                             trace!("storing {:?} to {:?}", value, local);
-                            assert!(self.get_const(local).is_none());
                             self.set_const(local, value);
 
                             if self.should_const_prop() {
@@ -702,6 +886,7 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                                     rval,
                                     value,
                                     statement.source_info,
+                                    location,
                                 );
                             }
                         }
@@ -710,6 +895,7 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
             }
         }
         self.super_statement(statement, location);
+        self.source_info = None;
     }
 
     fn visit_terminator(
@@ -717,7 +903,20 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
         terminator: &mut Terminator<'tcx>,
         location: Location,
     ) {
+        // The condition of an assertion is replaced while visiting the operands, but the
+        // local it reads must be poisoned if the assertion fails.
+        let assert_local = match terminator.kind {
+            TerminatorKind::Assert { cond: Operand::Move(ref place), .. } |
+            TerminatorKind::Assert { cond: Operand::Copy(ref place), .. } => match place.base {
+                PlaceBase::Local(local) => Some(local),
+                PlaceBase::Static(_) => None,
+            },
+            _ => None,
+        };
+
+        self.source_info = Some(terminator.source_info);
         self.super_terminator(terminator, location);
+        self.source_info = None;
         let source_info = terminator.source_info;
         match &mut terminator.kind {
             TerminatorKind::Assert { expected, ref msg, ref mut cond, .. } => {
@@ -728,13 +927,8 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                     if expected != value_const {
                         // poison all places this operand references so that further code
                         // doesn't use the invalid value
-                        match cond {
-                            Operand::Move(ref place) | Operand::Copy(ref place) => {
-                                if let PlaceBase::Local(local) = place.base {
-                                    self.remove_const(local);
-                                }
-                            },
-                            Operand::Constant(_) => {}
+                        if let Some(local) = assert_local {
+                            self.remove_const(local);
                         }
                         let span = terminator.source_info.span;
                         let hir_id = self
@@ -786,11 +980,13 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                     } else {
                         if self.should_const_prop() {
                             if let ScalarMaybeUndef::Scalar(scalar) = value_const {
-                                *cond = self.operand_from_scalar(
-                                    scalar,
-                                    self.tcx.types.bool,
-                                    source_info.span,
-                                );
+                                if self.consider_replacing(cond, scalar, location) {
+                                    *cond = self.operand_from_scalar(
+                                        scalar,
+                                        self.tcx.types.bool,
+                                        source_info.span,
+                                    );
+                                }
                             }
                         }
                     }
@@ -801,7 +997,10 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                     if let Some(value) = self.eval_operand(&discr, source_info) {
                         if let ScalarMaybeUndef::Scalar(scalar) =
                                 self.ecx.read_scalar(value).unwrap() {
-                            *discr = self.operand_from_scalar(scalar, switch_ty, source_info.span);
+                            if self.consider_replacing(discr, scalar, location) {
+                                *discr =
+                                    self.operand_from_scalar(scalar, switch_ty, source_info.span);
+                            }
                         }
                     }
                 }
@@ -818,8 +1017,10 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
             TerminatorKind::GeneratorDrop |
             TerminatorKind::FalseEdges { .. } |
             TerminatorKind::FalseUnwind { .. } => { }
-            //FIXME(wesleywiser) Call does have Operands that could be const-propagated
-            TerminatorKind::Call { .. } => { }
+            TerminatorKind::Call { ref func, ref args, destination: Some((ref dest, _)), .. } => {
+                self.const_prop_call(func, args, dest, source_info);
+            }
+            TerminatorKind::Call { destination: None, .. } => { }
         }
     }
 }
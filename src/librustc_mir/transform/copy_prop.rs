@@ -23,20 +23,21 @@ use rustc::mir::{
     Constant, Local, LocalKind, Location, Place, PlaceBase, Body, Operand, Rvalue, StatementKind
 };
 use rustc::mir::visit::MutVisitor;
+use rustc::session::Session;
 use rustc::ty::TyCtxt;
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{self, MirPass, MirSource};
 use crate::util::def_use::DefUseAnalysis;
 
 pub struct CopyPropagation;
 
 impl MirPass for CopyPropagation {
-    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, _source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_enabled(&self, sess: &Session) -> bool {
         // We only run when the MIR optimization level is > 1.
         // This avoids a slow pass, and messing up debug info.
-        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
-            return;
-        }
+        sess.opts.debugging_opts.mir_opt_level > 1
+    }
 
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
         let mut def_use_analysis = DefUseAnalysis::new(body);
         loop {
             def_use_analysis.analyze(body);
@@ -122,6 +123,12 @@ impl MirPass for CopyPropagation {
                     }
                 }
 
+                if !transform::consider_optimizing(tcx, source, "CopyPropagation", || {
+                    format!("propagating into {:?} defined at {:?}", dest_local, location)
+                }) {
+                    continue
+                }
+
                 changed = action.perform(body, &def_use_analysis, dest_local, location) || changed;
                 // FIXME(pcwalton): Update the use-def chains to delete the instructions instead of
                 // regenerating the chains.
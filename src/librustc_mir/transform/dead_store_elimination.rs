@@ -0,0 +1,89 @@
+//! Dead store elimination.
+//!
+//! This removes assignments to locals whose value is never read:
+//!
+//!     _2 = move _1;    // `_2` is not live after this statement
+//!
+//! When debuginfo is emitted, only temporaries are considered, so that the values of arguments
+//! and user variables remain visible in a debugger. Locals that are borrowed anywhere in the body
+//! are left alone too: liveness only considers them used where they are borrowed, and a store
+//! could be read through the reference afterwards.
+
+use rustc::mir::{Body, Local, LocalKind, Location, Place, PlaceBase, StatementKind};
+use rustc::session::Session;
+use rustc::session::config::DebugInfo;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::bit_set::BitSet;
+use crate::transform::{self, MirPass, MirSource};
+use crate::util::liveness;
+
+pub struct DeadStoreElimination;
+
+impl MirPass for DeadStoreElimination {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
+
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+        let remove_user_stores = tcx.sess.opts.debuginfo == DebugInfo::None;
+        let borrowed = liveness::borrowed_locals(body);
+        let mut candidates = BitSet::new_empty(body.local_decls.len());
+        for local in body.local_decls.indices() {
+            let removable = match body.local_kind(local) {
+                LocalKind::Temp => true,
+                LocalKind::Var | LocalKind::Arg => remove_user_stores,
+                LocalKind::ReturnPointer => false,
+            };
+            if removable && !borrowed.contains(local) {
+                candidates.insert(local);
+            }
+        }
+
+        // Removing a store also removes the uses in its right-hand side, which can make the
+        // stores in the predecessors of its block dead in turn.
+        while eliminate_dead_stores(tcx, source, body, &candidates) {}
+    }
+}
+
+/// Replaces the dead stores to the `candidates` with nops, and returns whether there were any.
+fn eliminate_dead_stores<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    body: &mut Body<'tcx>,
+    candidates: &BitSet<Local>,
+) -> bool {
+    let result = liveness::exact_liveness_of_locals(body);
+    let mut changed = false;
+
+    for (block, data) in body.basic_blocks_mut().iter_enumerated_mut() {
+        let mut live = result.outs[block].clone();
+        let location = Location { block, statement_index: data.statements.len() };
+        liveness::apply_terminator_effect(&mut live, data.terminator(), location);
+
+        for (statement_index, statement) in data.statements.iter_mut().enumerate().rev() {
+            let location = Location { block, statement_index };
+            let dead = match statement.kind {
+                StatementKind::Assign(
+                    Place {
+                        base: PlaceBase::Local(local),
+                        projection: None,
+                    },
+                    _,
+                ) => candidates.contains(local) && !live.contains(local),
+                _ => false,
+            };
+
+            if dead && transform::consider_optimizing(tcx, source, "DeadStoreElimination", || {
+                format!("eliminating dead store {:?} at {:?}", statement, location)
+            }) {
+                debug!("eliminating dead store {:?}", statement);
+                statement.make_nop();
+                changed = true;
+            } else {
+                liveness::apply_statement_effect(&mut live, statement, location);
+            }
+        }
+    }
+
+    changed
+}
@@ -0,0 +1,255 @@
+//! Destination propagation.
+//!
+//! This looks for copies between locals:
+//!
+//!     DEST = move SRC
+//!
+//! and, when `DEST` and `SRC` never hold different values that are both still needed, replaces
+//! `SRC` with `DEST` in the whole body. The copy becomes a self-assignment, which is removed. When
+//! `DEST` is the return place, this builds the returned value in place, like the named return
+//! value optimization of C++; more generally it lets a value be computed directly where it ends
+//! up, instead of being moved there through a chain of temporaries.
+//!
+//! Two locals conflict, and can't be merged, if one of them is written while the other is live.
+//! The copies between them don't count, since both hold the same value afterwards. A statement or
+//! terminator writing one of them while reading the other conflicts too: after merging, a call
+//! like `_2 = f(move _3)` would become `_3 = f(move _3)`, whose return place and argument can be
+//! the same memory when they are passed by reference. Locals that are borrowed anywhere in the
+//! body are never merged, because liveness doesn't account for accesses through references (see
+//! `util::liveness`).
+//!
+//! `SRC` must be a temporary, or a user variable when no debuginfo is emitted, since it
+//! disappears from the body. The storage statements of merged locals are removed: the storage of
+//! the merged local has to be live wherever the storage of either of them was.
+
+use rustc::mir::{
+    Body, Local, LocalKind, Location, Operand, Place, PlaceBase, Rvalue, Statement, StatementKind,
+};
+use rustc::mir::visit::{MutVisitor, PlaceContext, Visitor};
+use rustc::session::Session;
+use rustc::session::config::DebugInfo;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::indexed_vec::IndexVec;
+use crate::transform::{self, MirPass, MirSource};
+use crate::util::liveness::{self, LiveVarSet};
+
+pub struct DestinationPropagation;
+
+impl MirPass for DestinationPropagation {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
+
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+        let merge_user_variables = tcx.sess.opts.debuginfo == DebugInfo::None;
+        let borrowed = liveness::borrowed_locals(body);
+
+        loop {
+            let copies = find_copies(body, &borrowed, merge_user_variables);
+            if copies.is_empty() {
+                break;
+            }
+            let conflicts = Conflicts::new(body, &copies);
+
+            // Each local is merged at most once per round, so that the liveness computed at the
+            // start of the round stays valid for the locals that remain to be checked.
+            let mut merged = BitSet::new_empty(body.local_decls.len());
+            let mut replacements: IndexVec<Local, Local> = body.local_decls.indices().collect();
+            for (dest, src) in copies {
+                if merged.contains(dest) || merged.contains(src) {
+                    continue;
+                }
+                if conflicts.conflict(body, dest, src) {
+                    debug!("not merging {:?} into {:?}: they conflict", src, dest);
+                    continue;
+                }
+                if !transform::consider_optimizing(tcx, source, "DestinationPropagation", || {
+                    format!("merging {:?} into {:?}", src, dest)
+                }) {
+                    continue;
+                }
+
+                debug!("merging {:?} into {:?}", src, dest);
+                replacements[src] = dest;
+                merged.insert(src);
+                merged.insert(dest);
+            }
+
+            if merged.is_empty() {
+                break;
+            }
+            Replacer { replacements, merged }.visit_body(body);
+        }
+    }
+}
+
+/// If `statement` is a copy or move from a local to another one, returns them as `(DEST, SRC)`.
+fn as_copy(statement: &Statement<'_>) -> Option<(Local, Local)> {
+    match statement.kind {
+        StatementKind::Assign(
+            Place {
+                base: PlaceBase::Local(dest),
+                projection: None,
+            },
+            box Rvalue::Use(Operand::Copy(Place {
+                base: PlaceBase::Local(src),
+                projection: None,
+            })),
+        ) |
+        StatementKind::Assign(
+            Place {
+                base: PlaceBase::Local(dest),
+                projection: None,
+            },
+            box Rvalue::Use(Operand::Move(Place {
+                base: PlaceBase::Local(src),
+                projection: None,
+            })),
+        ) => Some((dest, src)),
+        _ => None,
+    }
+}
+
+/// Returns the copies whose locals may be merged, if they don't conflict.
+fn find_copies(
+    body: &Body<'_>,
+    borrowed: &BitSet<Local>,
+    merge_user_variables: bool,
+) -> Vec<(Local, Local)> {
+    body.basic_blocks()
+        .iter()
+        .flat_map(|data| data.statements.iter())
+        .filter_map(as_copy)
+        .filter(|&(dest, src)| {
+            dest != src && can_merge(body, borrowed, merge_user_variables, dest, src)
+        })
+        .collect()
+}
+
+fn can_merge(
+    body: &Body<'_>,
+    borrowed: &BitSet<Local>,
+    merge_user_variables: bool,
+    dest: Local,
+    src: Local,
+) -> bool {
+    let src_is_removable = match body.local_kind(src) {
+        LocalKind::Temp => true,
+        LocalKind::Var => merge_user_variables,
+        LocalKind::Arg | LocalKind::ReturnPointer => false,
+    };
+    src_is_removable &&
+        !borrowed.contains(dest) &&
+        !borrowed.contains(src) &&
+        body.local_decls[dest].ty == body.local_decls[src].ty
+}
+
+/// The accesses to the locals involved in copies, computed once per round so that checking a
+/// pair of locals only looks at the places writing them.
+struct Conflicts {
+    /// The locations of the statements and terminators that (partially) write each local.
+    writes: IndexVec<Local, Vec<Location>>,
+    /// The locals read by each of these statements and terminators.
+    reads: FxHashMap<Location, Vec<Local>>,
+    /// The locals live right after each of these statements and terminators.
+    live_after: FxHashMap<Location, LiveVarSet>,
+}
+
+impl Conflicts {
+    fn new(body: &Body<'_>, copies: &[(Local, Local)]) -> Self {
+        let mut candidates = BitSet::new_empty(body.local_decls.len());
+        for &(dest, src) in copies {
+            candidates.insert(dest);
+            candidates.insert(src);
+        }
+
+        let mut accesses = Accesses {
+            candidates: &candidates,
+            writes: body.local_decls.indices().map(|_| vec![]).collect(),
+            reads: FxHashMap::default(),
+        };
+        accesses.visit_body(body);
+        let Accesses { writes, reads, .. } = accesses;
+
+        let written: FxHashSet<Location> = writes.iter().flatten().cloned().collect();
+        let result = liveness::exact_liveness_of_locals(body);
+        let live_after = liveness::live_after_locations(body, &result, |location| {
+            written.contains(&location)
+        });
+
+        Conflicts { writes, reads, live_after }
+    }
+
+    /// Returns whether one of `a` and `b` is written while the other is live, or by a
+    /// statement or terminator that reads the other.
+    fn conflict(&self, body: &Body<'_>, a: Local, b: Local) -> bool {
+        let is_copy_between = |location: Location| {
+            let data = &body[location.block];
+            match data.statements.get(location.statement_index).and_then(as_copy) {
+                Some((dest, src)) => (dest, src) == (a, b) || (dest, src) == (b, a),
+                None => false,
+            }
+        };
+
+        [(a, b), (b, a)].iter().any(|&(written, other)| {
+            self.writes[written].iter().any(|location| {
+                !is_copy_between(*location) && (
+                    self.reads.get(location).map_or(false, |reads| reads.contains(&other)) ||
+                        self.live_after[location].contains(other)
+                )
+            })
+        })
+    }
+}
+
+/// Collects the statements and terminators that write the `candidates`, and the locals they
+/// read.
+struct Accesses<'a> {
+    candidates: &'a BitSet<Local>,
+    writes: IndexVec<Local, Vec<Location>>,
+    reads: FxHashMap<Location, Vec<Local>>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for Accesses<'a> {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, location: Location) {
+        if !self.candidates.contains(local) {
+            return;
+        }
+        if context.is_mutating_use() {
+            self.writes[local].push(location);
+        } else if context.is_nonmutating_use() {
+            self.reads.entry(location).or_default().push(local);
+        }
+    }
+}
+
+struct Replacer {
+    replacements: IndexVec<Local, Local>,
+    merged: BitSet<Local>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for Replacer {
+    fn visit_local(&mut self, local: &mut Local, _: PlaceContext, _: Location) {
+        *local = self.replacements[*local];
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        match statement.kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) if self.merged.contains(local) => {
+                statement.make_nop();
+                return;
+            }
+            _ => {}
+        }
+
+        self.super_statement(statement, location);
+
+        match as_copy(statement) {
+            Some((dest, src)) if dest == src => statement.make_nop(),
+            _ => {}
+        }
+    }
+}
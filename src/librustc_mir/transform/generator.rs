@@ -616,7 +616,7 @@ fn compute_storage_conflicts(
     // NOTE: Today we store a full conflict bitset for every local. Technically
     // this is twice as many bits as we need, since the relation is symmetric.
     // However, in practice these bitsets are not usually large. The layout code
-    // also needs to keep track of how many conflicts each local has, so it's
+    // also needs to iterate over all the conflicts of each local, so it's
     // simpler to keep it this way for now.
     let mut storage_conflicts = BitMatrix::new(stored_locals.count(), stored_locals.count());
     for (idx_a, local_a) in stored_locals.iter().enumerate() {
@@ -0,0 +1,403 @@
+//! Global value numbering.
+//!
+//! This finds computations whose result is already held by a local, because an identical
+//! computation dominates them, and replaces them with a copy of that local:
+//!
+//!     _5 = Len((*_1));
+//!     _6 = Lt(_2, move _5);       // becomes `_6 = Lt(_2, _5)`
+//!     assert(move _6, ...) -> bb1; // becomes `assert(_6, ...)`
+//!     ...
+//!     _9 = Len((*_1));            // becomes `_9 = _5`
+//!     _10 = Lt(_2, move _9);      // becomes `_10 = _6`
+//!     assert(move _10, ...);      // becomes `goto`, `_6` is known to be true here
+//!
+//! Values are only tracked for SSA-like locals: locals that are never borrowed and are written at
+//! most once (arguments being written on entry), so that a local holds the same value wherever
+//! its definition dominates. The values understood are constants, slice lengths, discriminants,
+//! fields, and unary, binary and checked binary operations. Memory behind a reference is only
+//! considered when the reference is shared and the pointee is `Freeze`, so that it can't change
+//! while the reference is live.
+//!
+//! An `Assert` is removed when a dominating `Assert` already checked the same value against the
+//! same expected result, which removes repeated bounds and overflow checks. This requires the
+//! target of the dominating `Assert` to have no other predecessor.
+//!
+//! The storage statements of the locals that are reused are removed, since they may now be used
+//! outside of their original storage range. This only lets them take more stack space: a reused
+//! local is written once, by a definition dominating all its uses, so it never needs the storage
+//! statements to be reset between its uses. Moves out of them become copies, since they are now
+//! read again afterwards.
+
+use rustc::hir;
+use rustc::mir::{
+    BasicBlock, BinOp, Body, Field, Local, LocalKind, Location, Operand, Place, PlaceBase,
+    Projection, ProjectionElem, Rvalue, Statement, StatementKind, Terminator, TerminatorKind,
+    UnOp,
+};
+use rustc::mir::tcx::PlaceTy;
+use rustc::mir::traversal;
+use rustc::mir::visit::{MutVisitor, PlaceContext, Visitor};
+use rustc::session::Session;
+use rustc::ty::{self, ParamEnv, Ty, TyCtxt};
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::graph::dominators::Dominators;
+use rustc_data_structures::indexed_vec::IndexVec;
+use syntax_pos::DUMMY_SP;
+use crate::transform::{self, MirPass, MirSource};
+
+pub struct GlobalValueNumbering;
+
+impl MirPass for GlobalValueNumbering {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
+
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+        let dominators = body.dominators();
+        let predecessors = body.predecessors().clone();
+        let blocks: Vec<_> = traversal::reverse_postorder(body).map(|(block, _)| block).collect();
+
+        let mut numbering = ValueNumbering::new(tcx, source, body);
+        for block in blocks {
+            let data = &mut body.basic_blocks_mut()[block];
+            for (statement_index, statement) in data.statements.iter_mut().enumerate() {
+                let location = Location { block, statement_index };
+                numbering.visit_statement(statement, location, &dominators);
+            }
+            let location = Location { block, statement_index: data.statements.len() };
+            let terminator = data.terminator_mut();
+            numbering.visit_terminator(terminator, location, &dominators, &predecessors);
+        }
+
+        if numbering.reused.is_empty() {
+            return;
+        }
+        ReusedLocals { reused: numbering.reused }.visit_body(body);
+    }
+}
+
+/// Keeps the locals that replaced a computation initialized and in storage wherever they are
+/// used.
+struct ReusedLocals {
+    reused: BitSet<Local>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for ReusedLocals {
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        match statement.kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) if self.reused.contains(local) => {
+                statement.make_nop();
+            }
+            _ => self.super_statement(statement, location),
+        }
+    }
+
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        // Moving out of a reused local would leave it uninitialized for its later copies. Its
+        // type is `Copy`, so copying it instead is the same.
+        if let Operand::Move(ref place) = *operand {
+            if let PlaceBase::Local(local) = place.base {
+                if self.reused.contains(local) {
+                    *operand = Operand::Copy(place.clone());
+                }
+            }
+        }
+        self.super_operand(operand, location);
+    }
+}
+
+newtype_index! {
+    struct ValueIndex {
+        DEBUG_FORMAT = "v{}"
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+enum Value<'tcx> {
+    /// The value of an SSA local that isn't known to be equal to anything else.
+    Opaque(Local),
+    Constant(&'tcx ty::Const<'tcx>),
+    /// The pointee of a shared reference to a `Freeze` type.
+    Deref(ValueIndex),
+    Field(ValueIndex, Field),
+    /// The length of the slice a pointer points to.
+    Len(ValueIndex),
+    Discriminant(ValueIndex),
+    UnaryOp(UnOp, ValueIndex),
+    BinaryOp(BinOp, ValueIndex, ValueIndex),
+    CheckedBinaryOp(BinOp, ValueIndex, ValueIndex),
+}
+
+struct ValueNumbering<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    local_tys: IndexVec<Local, Ty<'tcx>>,
+    ssa: BitSet<Local>,
+
+    values: FxHashMap<Value<'tcx>, ValueIndex>,
+    next_value: ValueIndex,
+    /// The value of each SSA local whose definition was visited.
+    local_values: IndexVec<Local, Option<ValueIndex>>,
+    /// The SSA locals holding each value, with the location of their definition.
+    available: FxHashMap<ValueIndex, Vec<(Local, Location)>>,
+    /// The results asserted for each value, with the target of the `Assert`.
+    asserted: FxHashMap<ValueIndex, Vec<(bool, BasicBlock)>>,
+    /// The locals that replaced a computation.
+    reused: BitSet<Local>,
+}
+
+impl ValueNumbering<'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &Body<'tcx>) -> Self {
+        let mut numbering = ValueNumbering {
+            tcx,
+            source,
+            param_env: tcx.param_env(source.def_id()),
+            local_tys: body.local_decls.iter().map(|decl| decl.ty).collect(),
+            ssa: ssa_locals(body),
+            values: FxHashMap::default(),
+            next_value: ValueIndex::new(0),
+            local_values: IndexVec::from_elem(None, &body.local_decls),
+            available: FxHashMap::default(),
+            asserted: FxHashMap::default(),
+            reused: BitSet::new_empty(body.local_decls.len()),
+        };
+        for arg in body.args_iter() {
+            if numbering.ssa.contains(arg) {
+                numbering.local_values[arg] = Some(numbering.intern(Value::Opaque(arg)));
+            }
+        }
+        numbering
+    }
+
+    fn intern(&mut self, value: Value<'tcx>) -> ValueIndex {
+        let next_value = &mut self.next_value;
+        *self.values.entry(value).or_insert_with(|| {
+            let index = *next_value;
+            *next_value = ValueIndex::new(index.index() + 1);
+            index
+        })
+    }
+
+    fn visit_statement(
+        &mut self,
+        statement: &mut Statement<'tcx>,
+        location: Location,
+        dominators: &Dominators<BasicBlock>,
+    ) {
+        let (local, rvalue) = match statement.kind {
+            StatementKind::Assign(
+                Place {
+                    base: PlaceBase::Local(local),
+                    projection: None,
+                },
+                ref mut rvalue,
+            ) => (local, rvalue),
+            _ => return,
+        };
+        if !self.ssa.contains(local) {
+            return;
+        }
+
+        let value = match self.rvalue_value(rvalue) {
+            Some(value) => value,
+            None => {
+                self.local_values[local] = Some(self.intern(Value::Opaque(local)));
+                return;
+            }
+        };
+        self.local_values[local] = Some(value);
+
+        let is_copy = match **rvalue {
+            Rvalue::Use(_) => true,
+            _ => false,
+        };
+        if !is_copy {
+            let available = self.find_available(value, local, location, dominators)
+                .filter(|&available| self.consider_optimizing(|| {
+                    format!("replacing {:?} at {:?} with {:?}", rvalue, location, available)
+                }));
+            if let Some(available) = available {
+                debug!("replacing {:?} at {:?} with {:?}", rvalue, location, available);
+                **rvalue = Rvalue::Use(Operand::Copy(Place::from(available)));
+                self.reused.insert(available);
+            }
+        }
+        self.available.entry(value).or_default().push((local, location));
+    }
+
+    fn visit_terminator(
+        &mut self,
+        terminator: &mut Terminator<'tcx>,
+        location: Location,
+        dominators: &Dominators<BasicBlock>,
+        predecessors: &IndexVec<BasicBlock, Vec<BasicBlock>>,
+    ) {
+        let (value, expected, target) = match terminator.kind {
+            TerminatorKind::Call {
+                destination: Some((
+                    Place {
+                        base: PlaceBase::Local(local),
+                        projection: None,
+                    },
+                    _,
+                )),
+                ..
+            } if self.ssa.contains(local) => {
+                self.local_values[local] = Some(self.intern(Value::Opaque(local)));
+                return;
+            }
+            TerminatorKind::Assert { ref cond, expected, target, .. } => {
+                match self.operand_value(cond) {
+                    Some(value) => (value, expected, target),
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+
+        let already_asserted = self.asserted.get(&value).map_or(false, |asserted| {
+            asserted.iter().any(|&(asserted_expected, asserted_target)| {
+                asserted_expected == expected &&
+                    dominators.is_dominated_by(location.block, asserted_target)
+            })
+        });
+        if already_asserted &&
+            self.consider_optimizing(|| format!("removing redundant assert at {:?}", location))
+        {
+            debug!("removing redundant assert at {:?}", location);
+            terminator.kind = TerminatorKind::Goto { target };
+        } else if predecessors[target].len() == 1 {
+            self.asserted.entry(value).or_default().push((expected, target));
+        }
+    }
+
+    fn consider_optimizing<T: Fn() -> String>(&self, msg: T) -> bool {
+        transform::consider_optimizing(self.tcx, self.source, "GlobalValueNumbering", msg)
+    }
+
+    /// Returns an SSA local whose definition dominates `location` and that holds `value`, which
+    /// can be copied into `local`.
+    fn find_available(
+        &self,
+        value: ValueIndex,
+        local: Local,
+        location: Location,
+        dominators: &Dominators<BasicBlock>,
+    ) -> Option<Local> {
+        let ty = self.local_tys[local];
+        self.available.get(&value)?.iter().find(|&&(available, defined_at)| {
+            self.local_tys[available] == ty &&
+                defined_at.dominates(location, dominators) &&
+                ty.is_copy_modulo_regions(self.tcx, self.param_env, DUMMY_SP)
+        }).map(|&(available, _)| available)
+    }
+
+    fn rvalue_value(&mut self, rvalue: &Rvalue<'tcx>) -> Option<ValueIndex> {
+        let value = match *rvalue {
+            Rvalue::Use(ref operand) => return self.operand_value(operand),
+            Rvalue::Len(ref place) => Value::Len(self.slice_pointer_value(place)?),
+            Rvalue::Discriminant(ref place) => Value::Discriminant(self.place_value(place)?),
+            Rvalue::UnaryOp(op, ref operand) => Value::UnaryOp(op, self.operand_value(operand)?),
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                Value::BinaryOp(op, self.operand_value(lhs)?, self.operand_value(rhs)?)
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                Value::CheckedBinaryOp(op, self.operand_value(lhs)?, self.operand_value(rhs)?)
+            }
+            _ => return None,
+        };
+        Some(self.intern(value))
+    }
+
+    fn operand_value(&mut self, operand: &Operand<'tcx>) -> Option<ValueIndex> {
+        match *operand {
+            Operand::Copy(ref place) | Operand::Move(ref place) => self.place_value(place),
+            Operand::Constant(ref constant) => Some(self.intern(Value::Constant(constant.literal))),
+        }
+    }
+
+    /// The length of a slice behind a pointer is part of the pointer, so it doesn't change even
+    /// when the slice is mutable: this returns the value of the pointer.
+    fn slice_pointer_value(&mut self, place: &Place<'tcx>) -> Option<ValueIndex> {
+        match *place {
+            Place {
+                base: PlaceBase::Local(local),
+                projection: Some(box Projection {
+                    base: None,
+                    elem: ProjectionElem::Deref,
+                }),
+            } => self.local_values[local],
+            _ => None,
+        }
+    }
+
+    fn place_value(&mut self, place: &Place<'tcx>) -> Option<ValueIndex> {
+        let local = match place.base {
+            PlaceBase::Local(local) => local,
+            PlaceBase::Static(_) => return None,
+        };
+        let mut value = self.local_values[local]?;
+        let mut place_ty = PlaceTy::from_ty(self.local_tys[local]);
+
+        place.iterate(|_, projections| {
+            for projection in projections {
+                value = match projection.elem {
+                    ProjectionElem::Deref => match place_ty.ty.sty {
+                        ty::Ref(_, pointee, hir::MutImmutable)
+                            if pointee.is_freeze(self.tcx, self.param_env, DUMMY_SP) =>
+                        {
+                            self.intern(Value::Deref(value))
+                        }
+                        _ => return None,
+                    },
+                    ProjectionElem::Field(field, _) => self.intern(Value::Field(value, field)),
+                    _ => return None,
+                };
+                place_ty = place_ty.projection_ty(self.tcx, &projection.elem);
+            }
+            Some(value)
+        })
+    }
+}
+
+struct SsaVisitor {
+    writes: IndexVec<Local, usize>,
+    borrowed: BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for SsaVisitor {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        if context.is_borrow() {
+            self.borrowed.insert(local);
+        }
+        if context.is_mutating_use() {
+            self.writes[local] += 1;
+        }
+    }
+}
+
+/// Returns the locals that are never borrowed and written at most once: temporaries and user
+/// variables written once, and arguments that aren't written.
+fn ssa_locals(body: &Body<'_>) -> BitSet<Local> {
+    let mut visitor = SsaVisitor {
+        writes: IndexVec::from_elem(0, &body.local_decls),
+        borrowed: BitSet::new_empty(body.local_decls.len()),
+    };
+    visitor.visit_body(body);
+
+    let mut ssa = BitSet::new_empty(body.local_decls.len());
+    for local in body.local_decls.indices() {
+        let writes = match body.local_kind(local) {
+            LocalKind::Arg => 0,
+            LocalKind::Temp | LocalKind::Var => 1,
+            LocalKind::ReturnPointer => continue,
+        };
+        if visitor.writes[local] == writes && !visitor.borrowed.contains(local) {
+            ssa.insert(local);
+        }
+    }
+    ssa
+}
@@ -8,25 +8,51 @@ use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 
 use rustc::mir::*;
 use rustc::mir::visit::*;
+use rustc::session::Session;
+use rustc::session::config::{DebugInfo, OptLevel};
 use rustc::ty::{self, Instance, InstanceDef, ParamEnv, Ty, TyCtxt};
 use rustc::ty::subst::{Subst, SubstsRef};
 
+use std::cmp;
 use std::collections::VecDeque;
 use std::iter;
-use crate::transform::{MirPass, MirSource};
+use std::io::Write;
+use crate::transform::{self, MirPass, MirSource};
+use crate::util::{dump_enabled, dump_mir, PassWhere};
 use super::simplify::{remove_dead_blocks, CfgSimplifier};
 
 use syntax::attr;
 use rustc_target::spec::abi::Abi;
 
-const DEFAULT_THRESHOLD: usize = 50;
-const HINT_THRESHOLD: usize = 100;
-
 const INSTR_COST: usize = 5;
 const CALL_PENALTY: usize = 25;
 
 const UNKNOWN_SIZE_COST: usize = 10;
 
+/// The number of assignments up to which a callee without branches is trivial.
+const TRIVIAL_ASSIGNMENTS: usize = 4;
+
+/// The threshold of trivial callees, whatever the optimization level: inlining them costs
+/// about as much as the call they replace.
+const TRIVIAL_THRESHOLD: usize = CALL_PENALTY + TRIVIAL_ASSIGNMENTS * INSTR_COST;
+
+/// Returns the costs up to which callees without and with `#[inline]` are inlined.
+///
+/// The defaults follow the ratios between the thresholds of the LLVM inliner at each
+/// optimization level, which are much lower when optimizing for size.
+fn thresholds(sess: &Session) -> (usize, usize) {
+    let default = match sess.opts.optimize {
+        OptLevel::No | OptLevel::Less | OptLevel::Default => 50,
+        OptLevel::Aggressive => 60,
+        OptLevel::Size => 15,
+        OptLevel::SizeMin => 5,
+    };
+    let threshold = sess.opts.debugging_opts.inline_mir_threshold.unwrap_or(default);
+    let hint_threshold = sess.opts.debugging_opts.inline_mir_hint_threshold
+        .unwrap_or(2 * threshold);
+    (threshold, hint_threshold)
+}
+
 pub struct Inline;
 
 #[derive(Copy, Clone, Debug)]
@@ -38,20 +64,29 @@ struct CallSite<'tcx> {
 }
 
 impl MirPass for Inline {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        // At the default MIR optimization level, only inline when optimizing: the inlined
+        // calls are then not emitted as LLVM IR only to be inlined by LLVM again.
+        let mir_opt_level = sess.opts.debugging_opts.mir_opt_level;
+        mir_opt_level >= 2 || (mir_opt_level == 1 && sess.opts.optimize != OptLevel::No)
+    }
+
     fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level >= 2 {
-            Inliner { tcx, source }.run_pass(body);
-        }
+        let report = dump_enabled(tcx, "Inline", source);
+        Inliner { tcx, source, report, decisions: Vec::new() }.run_pass(body);
     }
 }
 
 struct Inliner<'tcx> {
     tcx: TyCtxt<'tcx>,
     source: MirSource<'tcx>,
+    /// Whether the inlining decisions are dumped with `-Z dump-mir`.
+    report: bool,
+    decisions: Vec<String>,
 }
 
 impl Inliner<'tcx> {
-    fn run_pass(&self, caller_body: &mut Body<'tcx>) {
+    fn run_pass(&mut self, caller_body: &mut Body<'tcx>) {
         // Keep a queue of callsites to try inlining on. We take
         // advantage of the fact that queries detect cycles here to
         // allow us to try and fetch the fully optimized MIR of a
@@ -93,7 +128,7 @@ impl Inliner<'tcx> {
             while let Some(callsite) = callsites.pop_front() {
                 debug!("checking whether to inline callsite {:?}", callsite);
                 if !self.tcx.is_mir_available(callsite.callee) {
-                    debug!("checking whether to inline callsite {:?} - MIR unavailable", callsite);
+                    self.decide(callsite, "not inlined, MIR unavailable");
                     continue;
                 }
 
@@ -109,6 +144,7 @@ impl Inliner<'tcx> {
                         && self_node_id.as_u32() < callee_node_id.as_u32() {
                         self.tcx.optimized_mir(callsite.callee)
                     } else {
+                        self.decide(callsite, "not inlined, the callee could inline the caller");
                         continue;
                     }
                 } else {
@@ -163,6 +199,29 @@ impl Inliner<'tcx> {
             CfgSimplifier::new(caller_body).simplify();
             remove_dead_blocks(caller_body);
         }
+
+        if self.report && !self.decisions.is_empty() {
+            let decisions = &self.decisions;
+            dump_mir(self.tcx, None, "Inline", &"decisions", self.source, caller_body,
+                     |pass_where, out| {
+                if let PassWhere::BeforeCFG = pass_where {
+                    for decision in decisions {
+                        writeln!(out, "| {}", decision)?;
+                    }
+                    writeln!(out, "|")?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Records the inlining decision taken for `callsite`, to be dumped with `-Z dump-mir`.
+    fn decide(&mut self, callsite: CallSite<'tcx>, decision: &str) {
+        debug!("{:?}: {}", callsite, decision);
+        if self.report {
+            let callee = self.tcx.def_path_str(callsite.callee);
+            self.decisions.push(format!("{}: {}", callee, decision));
+        }
     }
 
     fn get_valid_function_call(&self,
@@ -171,9 +230,6 @@ impl Inliner<'tcx> {
                                caller_body: &Body<'tcx>,
                                param_env: ParamEnv<'tcx>,
     ) -> Option<CallSite<'tcx>> {
-        // Don't inline calls that are in cleanup blocks.
-        if bb_data.is_cleanup { return None; }
-
         // Only consider direct calls to functions
         let terminator = bb_data.terminator();
         if let TerminatorKind::Call { func: ref op, .. } = terminator.kind {
@@ -199,7 +255,7 @@ impl Inliner<'tcx> {
         None
     }
 
-    fn consider_optimizing(&self,
+    fn consider_optimizing(&mut self,
                            callsite: CallSite<'tcx>,
                            callee_body: &Body<'tcx>)
                            -> bool
@@ -209,9 +265,12 @@ impl Inliner<'tcx> {
             && self.tcx.consider_optimizing(|| format!("Inline {:?} into {:?}",
                                                        callee_body.span,
                                                        callsite))
+            && transform::consider_optimizing(self.tcx, self.source, "Inline", || {
+                format!("inlining {} in {:?}", self.tcx.def_path_str(callsite.callee), callsite.bb)
+            })
     }
 
-    fn should_inline(&self,
+    fn should_inline(&mut self,
                      callsite: CallSite<'tcx>,
                      callee_body: &Body<'tcx>)
                      -> bool
@@ -219,48 +278,72 @@ impl Inliner<'tcx> {
         debug!("should_inline({:?})", callsite);
         let tcx = self.tcx;
 
-        // Don't inline closures that have capture debuginfo
+        // Don't inline closures that have capture debuginfo, it would be lost.
         // FIXME: Handle closures better
-        if callee_body.__upvar_debuginfo_codegen_only_do_not_use.len() > 0 {
-            debug!("    upvar debuginfo present - not inlining");
+        if tcx.sess.opts.debuginfo != DebugInfo::None
+            && callee_body.__upvar_debuginfo_codegen_only_do_not_use.len() > 0
+        {
+            self.decide(callsite, "not inlined, upvar debuginfo present");
             return false;
         }
 
-        // Cannot inline generators which haven't been transformed yet
+        // Cannot inline generators which haven't been transformed yet. The calls in the body
+        // of a generator are inlined like in other functions, before it is transformed.
         if callee_body.yield_ty.is_some() {
-            debug!("    yield ty present - not inlining");
+            self.decide(callsite, "not inlined, the callee is a generator");
             return false;
         }
 
         let codegen_fn_attrs = tcx.codegen_fn_attrs(callsite.callee);
 
+        // The callee may use instructions which are only available with its target features,
+        // so the caller must enable all of them.
+        let caller_attrs = tcx.codegen_fn_attrs(self.source.def_id());
+        let has_all_features = codegen_fn_attrs.target_features.iter()
+            .all(|feature| caller_attrs.target_features.contains(feature));
+        if !has_all_features {
+            self.decide(callsite, "not inlined, the callee has target features the caller lacks");
+            return false;
+        }
+
+        // Naked functions are only their body of inline assembly, which relies on the calling
+        // convention and has no meaning in another function.
+        if codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::NAKED) {
+            self.decide(callsite, "not inlined, the callee is `#[naked]`");
+            return false;
+        }
+
         let hinted = match codegen_fn_attrs.inline {
             // Just treat inline(always) as a hint for now,
             // there are cases that prevent inlining that we
             // need to check for first.
             attr::InlineAttr::Always => true,
             attr::InlineAttr::Never => {
-                debug!("`#[inline(never)]` present - not inlining");
+                self.decide(callsite, "not inlined, `#[inline(never)]` present");
                 return false
             }
             attr::InlineAttr::Hint => true,
             attr::InlineAttr::None => false,
         };
 
-        // Only inline local functions if they would be eligible for cross-crate
-        // inlining. This is to ensure that the final crate doesn't have MIR that
-        // reference unexported symbols
-        if callsite.callee.is_local() {
-            if callsite.substs.non_erasable_generics().count() == 0 && !hinted {
-                debug!("    callee is an exported function - not inlining");
-                return false;
+        // Only inline functions which are eligible for cross-crate inlining. This is to
+        // ensure that the final crate doesn't have MIR that reference unexported symbols.
+        // The MIR of the other functions of other crates is only available for const
+        // evaluation, or with `-Z always-encode-mir`.
+        if callsite.substs.non_erasable_generics().count() == 0 && !hinted {
+            if callsite.callee.is_local() {
+                self.decide(callsite, "not inlined, the callee is an exported function");
+            } else {
+                self.decide(callsite, "not inlined, the callee isn't inlinable across crates");
             }
+            return false;
         }
 
+        let (default_threshold, hint_threshold) = thresholds(tcx.sess);
         let mut threshold = if hinted {
-            HINT_THRESHOLD
+            hint_threshold
         } else {
-            DEFAULT_THRESHOLD
+            default_threshold
         };
 
         // Significantly lower the threshold for inlining cold functions
@@ -274,6 +357,12 @@ impl Inliner<'tcx> {
         if callee_body.basic_blocks().len() <= 3 {
             threshold += threshold / 4;
         }
+
+        // Inlining a trivial callee barely grows the caller, even when optimizing for size.
+        let trivial = is_trivial(callee_body);
+        if trivial {
+            threshold = cmp::max(threshold, TRIVIAL_THRESHOLD);
+        }
         debug!("    final inline threshold = {}", threshold);
 
         // FIXME: Give a bonus to functions with only a single caller
@@ -369,20 +458,23 @@ impl Inliner<'tcx> {
         }
 
         if let attr::InlineAttr::Always = codegen_fn_attrs.inline {
-            debug!("INLINING {:?} because inline(always) [cost={}]", callsite, cost);
+            self.decide(callsite, &format!("inlined, `#[inline(always)]` [cost={}]", cost));
             true
         } else {
             if cost <= threshold {
-                debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
+                let trivial = if trivial { ", trivial callee" } else { "" };
+                self.decide(callsite, &format!("inlined{} [cost={} <= threshold={}]",
+                                               trivial, cost, threshold));
                 true
             } else {
-                debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
+                self.decide(callsite, &format!("not inlined [cost={} > threshold={}]",
+                                               cost, threshold));
                 false
             }
         }
     }
 
-    fn inline_call(&self,
+    fn inline_call(&mut self,
                    callsite: CallSite<'tcx>,
                    caller_body: &mut Body<'tcx>,
                    mut callee_body: Body<'tcx>) -> bool {
@@ -474,6 +566,7 @@ impl Inliner<'tcx> {
                 };
 
                 let return_block = destination.1;
+                let caller_in_cleanup = caller_body[callsite.bb].is_cleanup;
 
                 // Copy the arguments if needed.
                 let args: Vec<_> = self.make_call_args(args, &callsite, caller_body);
@@ -489,7 +582,8 @@ impl Inliner<'tcx> {
                     destination: dest,
                     return_block,
                     cleanup_block: cleanup,
-                    in_cleanup_block: false
+                    in_cleanup_block: false,
+                    caller_in_cleanup,
                 };
 
 
@@ -624,6 +718,40 @@ impl Inliner<'tcx> {
     }
 }
 
+/// Returns whether calling `callee_body` costs about as much as the body itself, like a
+/// `Deref` or `AsRef` impl returning a field, or a function forwarding to another one.
+///
+/// Such a body has no branches, only a few assignments and at most one call.
+fn is_trivial(callee_body: &Body<'_>) -> bool {
+    let mut assignments = 0;
+    let mut calls = 0;
+    for data in callee_body.basic_blocks() {
+        // The cleanup blocks only run when unwinding.
+        if data.is_cleanup {
+            continue;
+        }
+
+        for stmt in &data.statements {
+            match stmt.kind {
+                StatementKind::Assign(..) => assignments += 1,
+                StatementKind::StorageLive(_) |
+                StatementKind::StorageDead(_) |
+                StatementKind::Retag(..) |
+                StatementKind::Nop => {}
+                _ => return false,
+            }
+        }
+
+        match data.terminator().kind {
+            TerminatorKind::Goto { .. } |
+            TerminatorKind::Return => {}
+            TerminatorKind::Call { destination: Some(_), .. } => calls += 1,
+            _ => return false,
+        }
+    }
+    assignments <= TRIVIAL_ASSIGNMENTS && calls <= 1
+}
+
 fn type_size_of<'tcx>(
     tcx: TyCtxt<'tcx>,
     param_env: ty::ParamEnv<'tcx>,
@@ -650,6 +778,9 @@ struct Integrator<'a, 'tcx> {
     return_block: BasicBlock,
     cleanup_block: Option<BasicBlock>,
     in_cleanup_block: bool,
+    /// Whether the call being inlined is in a cleanup block, in which case all
+    /// the inlined blocks are cleanup blocks too.
+    caller_in_cleanup: bool,
 }
 
 impl<'a, 'tcx> Integrator<'a, 'tcx> {
@@ -658,6 +789,20 @@ impl<'a, 'tcx> Integrator<'a, 'tcx> {
         debug!("updating target `{:?}`, new: `{:?}`", tgt, new);
         new
     }
+
+    fn update_unwind(&self, unwind: &mut Option<BasicBlock>) {
+        if self.caller_in_cleanup {
+            // Panicking while unwinding aborts, so cleanup blocks have no unwind
+            // edges. The cleanup blocks of the callee become unreachable.
+            *unwind = None;
+        } else if let Some(tgt) = *unwind {
+            *unwind = Some(self.update_target(tgt));
+        } else if !self.in_cleanup_block {
+            // Unless this terminator is in a cleanup block, add an unwind edge to
+            // the original call's cleanup block
+            *unwind = self.cleanup_block;
+        }
+    }
 }
 
 impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
@@ -717,6 +862,7 @@ impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
         self.in_cleanup_block = data.is_cleanup;
         self.super_basic_block_data(block, data);
         self.in_cleanup_block = false;
+        data.is_cleanup |= self.caller_in_cleanup;
     }
 
     fn visit_retag(
@@ -752,35 +898,17 @@ impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
             TerminatorKind::Drop { ref mut target, ref mut unwind, .. } |
             TerminatorKind::DropAndReplace { ref mut target, ref mut unwind, .. } => {
                 *target = self.update_target(*target);
-                if let Some(tgt) = *unwind {
-                    *unwind = Some(self.update_target(tgt));
-                } else if !self.in_cleanup_block {
-                    // Unless this drop is in a cleanup block, add an unwind edge to
-                    // the original call's cleanup block
-                    *unwind = self.cleanup_block;
-                }
+                self.update_unwind(unwind);
             }
             TerminatorKind::Call { ref mut destination, ref mut cleanup, .. } => {
                 if let Some((_, ref mut tgt)) = *destination {
                     *tgt = self.update_target(*tgt);
                 }
-                if let Some(tgt) = *cleanup {
-                    *cleanup = Some(self.update_target(tgt));
-                } else if !self.in_cleanup_block {
-                    // Unless this call is in a cleanup block, add an unwind edge to
-                    // the original call's cleanup block
-                    *cleanup = self.cleanup_block;
-                }
+                self.update_unwind(cleanup);
             }
             TerminatorKind::Assert { ref mut target, ref mut cleanup, .. } => {
                 *target = self.update_target(*target);
-                if let Some(tgt) = *cleanup {
-                    *cleanup = Some(self.update_target(tgt));
-                } else if !self.in_cleanup_block {
-                    // Unless this assert is in a cleanup block, add an unwind edge to
-                    // the original call's cleanup block
-                    *cleanup = self.cleanup_block;
-                }
+                self.update_unwind(cleanup);
             }
             TerminatorKind::Return => {
                 *kind = TerminatorKind::Goto { target: self.return_block };
@@ -3,26 +3,27 @@
 use rustc::mir::{Constant, Location, Place, PlaceBase, Body, Operand, ProjectionElem, Rvalue,
     Local};
 use rustc::mir::visit::{MutVisitor, Visitor};
+use rustc::session::Session;
 use rustc::ty::{self, TyCtxt};
 use rustc::util::nodemap::{FxHashMap, FxHashSet};
 use rustc_data_structures::indexed_vec::Idx;
 use std::mem;
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{self, MirPass, MirSource};
 
 pub struct InstCombine;
 
 impl MirPass for InstCombine {
-    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, _: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_enabled(&self, sess: &Session) -> bool {
         // We only run when optimizing MIR (at any level).
-        if tcx.sess.opts.debugging_opts.mir_opt_level == 0 {
-            return
-        }
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
 
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
         // First, find optimization opportunities. This is done in a pre-pass to keep the MIR
         // read-only so that we can do global analyses on the MIR in the process (e.g.
         // `Place::ty()`).
         let optimizations = {
-            let mut optimization_finder = OptimizationFinder::new(body, tcx);
+            let mut optimization_finder = OptimizationFinder::new(body, tcx, source);
             optimization_finder.visit_body(body);
             optimization_finder.optimizations
         };
@@ -67,17 +68,29 @@ impl<'tcx> MutVisitor<'tcx> for InstCombineVisitor<'tcx> {
 struct OptimizationFinder<'b, 'tcx> {
     body: &'b Body<'tcx>,
     tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
     optimizations: OptimizationList<'tcx>,
 }
 
 impl OptimizationFinder<'b, 'tcx> {
-    fn new(body: &'b Body<'tcx>, tcx: TyCtxt<'tcx>) -> OptimizationFinder<'b, 'tcx> {
+    fn new(
+        body: &'b Body<'tcx>,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+    ) -> OptimizationFinder<'b, 'tcx> {
         OptimizationFinder {
             body,
             tcx,
+            source,
             optimizations: OptimizationList::default(),
         }
     }
+
+    fn consider_optimizing(&self, what: &str, location: Location) -> bool {
+        transform::consider_optimizing(self.tcx, self.source, "InstCombine", || {
+            format!("{} at {:?}", what, location)
+        })
+    }
 }
 
 impl Visitor<'tcx> for OptimizationFinder<'b, 'tcx> {
@@ -87,7 +100,8 @@ impl Visitor<'tcx> for OptimizationFinder<'b, 'tcx> {
             projection: Some(ref projection),
         }) = *rvalue {
             if let ProjectionElem::Deref = projection.elem {
-                if Place::ty_from(&base, &projection.base, self.body, self.tcx).ty.is_region_ptr() {
+                let ty = Place::ty_from(&base, &projection.base, self.body, self.tcx).ty;
+                if ty.is_region_ptr() && self.consider_optimizing("replacing `&*`", location) {
                     self.optimizations.and_stars.insert(location);
                 }
             }
@@ -96,9 +110,11 @@ impl Visitor<'tcx> for OptimizationFinder<'b, 'tcx> {
         if let Rvalue::Len(ref place) = *rvalue {
             let place_ty = place.ty(&self.body.local_decls, self.tcx).ty;
             if let ty::Array(_, len) = place_ty.sty {
-                let span = self.body.source_info(location).span;
-                let constant = Constant { span, literal: len, user_ty: None };
-                self.optimizations.arrays_lengths.insert(location, constant);
+                if self.consider_optimizing("replacing `Len([_; N])`", location) {
+                    let span = self.body.source_info(location).span;
+                    let constant = Constant { span, literal: len, user_ty: None };
+                    self.optimizations.arrays_lengths.insert(location, constant);
+                }
             }
         }
 
@@ -1,7 +1,9 @@
-use crate::{build, shim};
+use crate::{build, shim, util};
+use crate::dataflow::has_rustc_mir_with;
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc::mir::{Body, MirPhase, Promoted};
+use rustc::session::Session;
 use rustc::ty::{TyCtxt, InstanceDef};
 use rustc::ty::query::Providers;
 use rustc::ty::steal::Steal;
@@ -10,6 +12,7 @@ use rustc::hir::intravisit::{self, Visitor, NestedVisitorMap};
 use rustc::util::nodemap::DefIdSet;
 use std::borrow::Cow;
 use syntax::ast;
+use syntax::symbol::sym;
 use syntax_pos::Span;
 
 pub mod add_retag;
@@ -18,6 +21,7 @@ pub mod cleanup_post_borrowck;
 pub mod check_unsafety;
 pub mod simplify_branches;
 pub mod simplify;
+pub mod simplify_try;
 pub mod erase_regions;
 pub mod no_landing_pads;
 pub mod rustc_peek;
@@ -31,10 +35,14 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod gvn;
+pub mod dest_prop;
+pub mod dead_store_elimination;
 pub mod const_prop;
 pub mod generator;
 pub mod inline;
 pub mod uniform_array_move_out;
+pub mod validate;
 
 pub(crate) fn provide(providers: &mut Providers<'_>) {
     self::qualify_consts::provide(providers);
@@ -142,9 +150,75 @@ pub trait MirPass {
         default_name::<Self>()
     }
 
+    /// Whether this pass runs with the current options, typically the `-Z mir-opt-level`.
+    /// `-Z mir-enable-passes` overrides this in both directions for the optimization passes.
+    fn is_enabled(&self, _sess: &Session) -> bool {
+        true
+    }
+
     fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>);
 }
 
+/// The names of the optimization passes, in the order they run. Only these can be enabled or
+/// disabled by `-Z mir-enable-passes`: the other passes are needed for the MIR to be valid.
+fn optimization_pass_names() -> Vec<Cow<'static, str>> {
+    vec![
+        default_name::<inline::Inline>(),
+        default_name::<instcombine::InstCombine>(),
+        default_name::<const_prop::ConstProp>(),
+        default_name::<simplify_try::SimplifyArmIdentity>(),
+        default_name::<simplify_try::SimplifyBranchSame>(),
+        default_name::<gvn::GlobalValueNumbering>(),
+        default_name::<copy_prop::CopyPropagation>(),
+        default_name::<dest_prop::DestinationPropagation>(),
+        default_name::<dead_store_elimination::DeadStoreElimination>(),
+    ]
+}
+
+/// Reports the names given to `-Z mir-enable-passes` which aren't optimization passes.
+pub fn check_enabled_passes(sess: &Session) {
+    let optimizations = optimization_pass_names();
+    for (name, _) in &sess.opts.debugging_opts.mir_enable_passes {
+        if !optimizations.iter().any(|pass| pass == name) {
+            sess.struct_err(&format!(
+                "`-Z mir-enable-passes` can't enable or disable `{}`, which is not a MIR \
+                 optimization pass",
+                name,
+            )).note(&format!("the MIR optimization passes are {}", optimizations.join(", ")))
+                .emit();
+        }
+    }
+}
+
+/// Returns whether the optimization pass called `name` was enabled or disabled by
+/// `-Z mir-enable-passes`, the last mention of the pass winning.
+pub fn is_pass_forced(sess: &Session, name: &str) -> Option<bool> {
+    let forced = &sess.opts.debugging_opts.mir_enable_passes;
+    if forced.is_empty() || !optimization_pass_names().iter().any(|pass| pass == name) {
+        return None;
+    }
+    forced.iter()
+        .rev()
+        .find(|(pass, _)| pass == name)
+        .map(|&(_, enabled)| enabled)
+}
+
+/// Returns whether an optimization pass should make the change described by `msg` to the MIR
+/// of `source`. Optimization passes call this before each of their changes, so that
+/// `-Z mir-opt-bisect-limit` can single out the change that breaks a program.
+pub fn consider_optimizing<'tcx, T: Fn() -> String>(
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    pass: &str,
+    msg: T,
+) -> bool {
+    tcx.sess.consider_mir_optimization(|| match source.promoted {
+        Some(promoted) => format!("{} on {} ({:?}): {}",
+                                  pass, tcx.def_path_str(source.def_id()), promoted, msg()),
+        None => format!("{} on {}: {}", pass, tcx.def_path_str(source.def_id()), msg()),
+    })
+}
+
 pub fn run_passes(
     tcx: TyCtxt<'tcx>,
     body: &mut Body<'tcx>,
@@ -164,15 +238,30 @@ pub fn run_passes(
             promoted,
         };
         let mut index = 0;
+        let mut drops_elaborated = false;
         let mut run_pass = |pass: &dyn MirPass| {
+            let name = pass.name();
+            let enabled = is_pass_forced(tcx.sess, &name)
+                .unwrap_or_else(|| pass.is_enabled(tcx.sess));
+            if !enabled {
+                debug!("run_passes: skipping `{}` on {:?}", name, source);
+                index += 1;
+                return;
+            }
+
             let run_hooks = |body: &_, index, is_after| {
                 dump_mir::on_mir_pass(tcx, &format_args!("{:03}-{:03}", phase_index, index),
-                                      &pass.name(), source, body, is_after);
+                                      &name, source, body, is_after);
             };
             run_hooks(body, index, false);
             pass.run_pass(tcx, source, body);
             run_hooks(body, index, true);
 
+            drops_elaborated |= name == default_name::<elaborate_drops::ElaborateDrops>();
+            if tcx.sess.opts.debugging_opts.validate_mir {
+                validate::validate_body(tcx, source, body, &name, drops_elaborated);
+            }
+
             index += 1;
         };
 
@@ -242,7 +331,7 @@ fn optimized_mir(tcx: TyCtxt<'_>, def_id: DefId) -> &Body<'_> {
     }
 
     let mut body = tcx.mir_validated(def_id).steal();
-    run_passes(tcx, &mut body, InstanceDef::Item(def_id), MirPhase::Optimized, &[
+    let passes: &[&dyn MirPass] = &[
         // Remove all things only needed by analysis
         &no_landing_pads::NoLandingPads,
         &simplify_branches::SimplifyBranches::new("initial"),
@@ -284,7 +373,12 @@ fn optimized_mir(tcx: TyCtxt<'_>, def_id: DefId) -> &Body<'_> {
         &const_prop::ConstProp,
         &simplify_branches::SimplifyBranches::new("after-const-prop"),
         &deaggregator::Deaggregator,
+        &simplify_try::SimplifyArmIdentity,
+        &simplify_try::SimplifyBranchSame,
+        &gvn::GlobalValueNumbering,
         &copy_prop::CopyPropagation,
+        &dest_prop::DestinationPropagation,
+        &dead_store_elimination::DeadStoreElimination,
         &simplify_branches::SimplifyBranches::new("after-copy-prop"),
         &remove_noop_landing_pads::RemoveNoopLandingPads,
         &simplify::SimplifyCfg::new("final"),
@@ -292,7 +386,51 @@ fn optimized_mir(tcx: TyCtxt<'_>, def_id: DefId) -> &Body<'_> {
 
         &add_call_guards::CriticalCallEdges,
         &dump_mir::Marker("PreCodegen"),
-    ]);
+    ];
+
+    let attrs = tcx.get_attrs(def_id);
+    if let Some(from_text) = has_rustc_mir_with(&attrs, sym::from_text) {
+        return mir_from_text(tcx, def_id, &attrs, from_text, passes);
+    }
+
+    run_passes(tcx, &mut body, InstanceDef::Item(def_id), MirPhase::Optimized, passes);
+    tcx.arena.alloc(body)
+}
+
+/// Replaces the MIR of `def_id` by the one given by `#[rustc_mir(from_text = "...")]`, and
+/// only runs the optimized MIR pass named by `#[rustc_mir(run_pass = "...")]` on it. This
+/// lets a test check what a single pass does to a body written by hand.
+fn mir_from_text<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    attrs: &[ast::Attribute],
+    from_text: ast::MetaItem,
+    passes: &[&dyn MirPass],
+) -> &'tcx Body<'tcx> {
+    let text = match from_text.value_str() {
+        Some(text) => text,
+        None => tcx.sess.span_fatal(from_text.span, "expected `from_text = \"...\"`"),
+    };
+    let mut body = match util::parse::parse_mir(tcx, def_id, &text.as_str()) {
+        Ok(body) => body,
+        Err(err) => tcx.sess.span_fatal(from_text.span, &format!("invalid MIR: {}", err)),
+    };
+
+    let mut selected: Vec<&dyn MirPass> = passes.to_vec();
+    if let Some(run_pass) = has_rustc_mir_with(attrs, sym::run_pass) {
+        let name = match run_pass.value_str() {
+            Some(name) => name,
+            None => tcx.sess.span_fatal(run_pass.span, "expected `run_pass = \"...\"`"),
+        };
+        selected.retain(|pass| *pass.name() == *name.as_str());
+        if selected.is_empty() {
+            tcx.sess.span_fatal(run_pass.span, &format!("unknown MIR pass `{}`", name));
+        }
+    }
+    // Codegen relies on the critical edges after calls being split.
+    selected.push(&add_call_guards::CriticalCallEdges);
+
+    run_passes(tcx, &mut body, InstanceDef::Item(def_id), MirPhase::Optimized, &selected);
     tcx.arena.alloc(body)
 }
 
@@ -0,0 +1,451 @@
+//! Simplifies matches that rebuild their scrutinee, like:
+//!
+//!     match x {
+//!         Ok(v) => Ok(v),
+//!         Err(e) => Err(e),
+//!     }
+//!
+//! which `?` and many `map`-like functions produce, into just `x`.
+//!
+//! `SimplifyArmIdentity` turns each arm, which moves the fields of a variant out of the
+//! scrutinee and builds the same variant from them:
+//!
+//!     _3 = move ((_1 as Ok).0: T);
+//!     ((_0 as Ok).0: T) = move _3;
+//!     discriminant(_0) = 0;
+//!
+//! into a single move of the whole value, `_0 = move _1`. `SimplifyBranchSame` then replaces the
+//! switch over the discriminant by that move, when every arm does the same.
+//!
+//! The value is only moved as a whole when the source and destination have the same type, so
+//! that their layouts agree. The locals holding the fields in between disappear, so they must be
+//! temporaries, or user variables when no debuginfo is emitted.
+
+use rustc::mir::{
+    BasicBlock, BasicBlockData, Body, Field, Local, LocalDecls, LocalKind, Operand, Place,
+    PlaceBase, Projection, ProjectionElem, Rvalue, Statement, StatementKind, TerminatorKind,
+};
+use rustc::session::Session;
+use rustc::session::config::DebugInfo;
+use rustc::ty::{self, TyCtxt};
+use rustc::ty::layout::VariantIdx;
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::fx::FxHashMap;
+use crate::transform::{self, MirPass, MirSource};
+use crate::transform::simplify;
+
+pub struct SimplifyArmIdentity;
+
+impl MirPass for SimplifyArmIdentity {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
+
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+        let remove_user_variables = tcx.sess.opts.debuginfo == DebugInfo::None;
+        let mut removable = BitSet::new_empty(body.local_decls.len());
+        for local in body.local_decls.indices() {
+            match body.local_kind(local) {
+                LocalKind::Temp => removable.insert(local),
+                LocalKind::Var if remove_user_variables => removable.insert(local),
+                _ => false,
+            };
+        }
+
+        let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+        for (bb, data) in basic_blocks.iter_enumerated_mut() {
+            if let Some(identity) = find_arm_identity(local_decls, &removable, data) {
+                let apply = transform::consider_optimizing(tcx, source, "SimplifyArmIdentity", || {
+                    format!("simplifying arm identity {:?} = {:?} in {:?}",
+                            identity.dst, identity.src, bb)
+                });
+                if apply {
+                    debug!("simplifying arm identity {:?} = {:?}", identity.dst, identity.src);
+                    apply_arm_identity(identity, data);
+                }
+            }
+        }
+    }
+}
+
+/// An arm rebuilding the value of `src` in `dst`.
+struct ArmIdentity {
+    dst: Local,
+    src: Local,
+    /// Whether `src` is still used afterwards, because some of its fields are copied.
+    copied: bool,
+    /// The locals holding the fields on their way from `src` to `dst`.
+    temps: Vec<Local>,
+}
+
+/// Matches `((LOCAL as VARIANT).FIELD: TY)`.
+fn match_variant_field(place: &Place<'_>) -> Option<(Local, VariantIdx, Field)> {
+    match *place {
+        Place {
+            base: PlaceBase::Local(local),
+            projection: Some(box Projection {
+                base: Some(box Projection {
+                    base: None,
+                    elem: ProjectionElem::Downcast(_, variant),
+                }),
+                elem: ProjectionElem::Field(field, _),
+            }),
+        } => Some((local, variant, field)),
+        _ => None,
+    }
+}
+
+fn match_local(place: &Place<'_>) -> Option<Local> {
+    match *place {
+        Place {
+            base: PlaceBase::Local(local),
+            projection: None,
+        } => Some(local),
+        _ => None,
+    }
+}
+
+/// Checks whether the statements of `data` copy or move every field of a variant out of some
+/// local, possibly through other `removable` locals, into the same field of another local of the
+/// same type, and then set the discriminant of the latter to that variant. Storage statements
+/// are ignored.
+fn find_arm_identity(
+    local_decls: &LocalDecls<'_>,
+    removable: &BitSet<Local>,
+    data: &BasicBlockData<'_>,
+) -> Option<ArmIdentity> {
+    if data.is_cleanup {
+        return None;
+    }
+
+    // The field of the source held by each local.
+    let mut holds: FxHashMap<Local, (Local, VariantIdx, Field)> = FxHashMap::default();
+    let mut copied = false;
+    let mut temps = vec![];
+    let mut moved = vec![];
+    let mut dead = vec![];
+    let mut target = None;
+    let mut written = vec![];
+    let mut discriminant_set = false;
+
+    for statement in &data.statements {
+        match statement.kind {
+            StatementKind::StorageDead(local) => {
+                if temps.contains(&local) {
+                    dead.push(local);
+                }
+            }
+            StatementKind::StorageLive(_) |
+            StatementKind::Nop => {}
+            _ if discriminant_set => return None,
+            StatementKind::Assign(ref place, box Rvalue::Use(ref operand)) => {
+                let (from, is_copy) = match *operand {
+                    Operand::Copy(ref from) => (from, true),
+                    Operand::Move(ref from) => (from, false),
+                    Operand::Constant(_) => return None,
+                };
+
+                if let Some(temp) = match_local(place) {
+                    // `TEMP = (SRC as VARIANT).FIELD` or `TEMP = OTHER_TEMP`.
+                    if !removable.contains(temp) {
+                        return None;
+                    }
+                    let field = match match_local(from) {
+                        Some(from) => {
+                            if !is_copy {
+                                moved.push(from);
+                            }
+                            *holds.get(&from)?
+                        }
+                        None => {
+                            copied |= is_copy;
+                            match_variant_field(from)?
+                        }
+                    };
+                    holds.insert(temp, field);
+                    temps.push(temp);
+                } else {
+                    // `(DST as VARIANT).FIELD = TEMP`.
+                    let (dst, variant, field) = match_variant_field(place)?;
+                    let temp = match_local(from)?;
+                    if !is_copy {
+                        moved.push(temp);
+                    }
+                    let (src, src_variant, src_field) = *holds.get(&temp)?;
+                    if (src_variant, src_field) != (variant, field) {
+                        return None;
+                    }
+                    match target {
+                        None => target = Some((dst, src, variant)),
+                        Some(target) if target == (dst, src, variant) => {}
+                        Some(_) => return None,
+                    }
+                    written.push(field);
+                }
+            }
+            StatementKind::SetDiscriminant { ref place, variant_index } => {
+                let (dst, _, variant) = target?;
+                if match_local(place) != Some(dst) || variant_index != variant {
+                    return None;
+                }
+                discriminant_set = true;
+            }
+            _ => return None,
+        }
+    }
+
+    let (dst, src, variant) = target?;
+    if !discriminant_set || dst == src || temps.contains(&dst) || temps.contains(&src) {
+        return None;
+    }
+
+    // The locals in between must not be used after the arm: they must be moved out of, or
+    // their storage must end in the arm.
+    if !temps.iter().all(|temp| moved.contains(temp) || dead.contains(temp)) {
+        return None;
+    }
+
+    // Moving the whole value is only the same as moving each field when the layouts agree.
+    let ty = local_decls[src].ty;
+    if local_decls[dst].ty != ty {
+        return None;
+    }
+
+    // Each field of the variant must have been written exactly once.
+    let num_fields = match ty.sty {
+        ty::Adt(adt, _) => adt.variants[variant].fields.len(),
+        _ => return None,
+    };
+    let num_written = written.len();
+    written.sort();
+    written.dedup();
+    if written.len() != num_written || num_written != num_fields {
+        return None;
+    }
+
+    Some(ArmIdentity { dst, src, copied, temps })
+}
+
+/// Replaces the statements found by `find_arm_identity` by `DST = move SRC`.
+fn apply_arm_identity(identity: ArmIdentity, data: &mut BasicBlockData<'_>) {
+    for statement in &mut data.statements {
+        match statement.kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => {
+                if identity.temps.contains(&local) {
+                    statement.make_nop();
+                }
+            }
+            StatementKind::Nop => {}
+            StatementKind::SetDiscriminant { .. } => {
+                let src = Place::from(identity.src);
+                let operand = if identity.copied {
+                    Operand::Copy(src)
+                } else {
+                    Operand::Move(src)
+                };
+                statement.kind = StatementKind::Assign(
+                    Place::from(identity.dst),
+                    box Rvalue::Use(operand),
+                );
+            }
+            _ => statement.make_nop(),
+        }
+    }
+}
+
+/// Replaces a switch over the discriminant of a local whose arms all copy it to the same
+/// destination, either as simplified by `SimplifyArmIdentity` or by setting the discriminant
+/// of a fieldless variant, and then go to the same block.
+pub struct SimplifyBranchSame;
+
+impl MirPass for SimplifyBranchSame {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
+
+    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+        let mut changed = false;
+        for bb in body.basic_blocks().indices() {
+            let same_branches = find_same_branches(tcx, body, bb).filter(|same| {
+                transform::consider_optimizing(tcx, source, "SimplifyBranchSame", || {
+                    format!("replacing the switch in {:?} by {:?} = {:?}",
+                            bb, same.dst, same.operand)
+                })
+            });
+            if let Some(same) = same_branches {
+                debug!("replacing the switch in {:?} by {:?} = {:?}", bb, same.dst, same.operand);
+                let data = &mut body.basic_blocks_mut()[bb];
+                let source_info = data.terminator().source_info;
+                data.statements.extend(same.storage_before);
+                data.statements.push(Statement {
+                    source_info,
+                    kind: StatementKind::Assign(
+                        Place::from(same.dst),
+                        box Rvalue::Use(same.operand),
+                    ),
+                });
+                data.statements.extend(same.storage_after);
+                data.terminator_mut().kind = TerminatorKind::Goto { target: same.next };
+                changed = true;
+            }
+        }
+
+        if changed {
+            simplify::remove_dead_blocks(body);
+        }
+    }
+}
+
+/// A switch whose reachable arms all copy the same local into `dst` and then go to `next`.
+struct SameBranches<'tcx> {
+    dst: Local,
+    operand: Operand<'tcx>,
+    next: BasicBlock,
+    /// The storage statements of the arms, which are the same in all of them, before and after
+    /// their copy.
+    storage_before: Vec<Statement<'tcx>>,
+    storage_after: Vec<Statement<'tcx>>,
+}
+
+/// If `bb` switches over the discriminant of a local and all of the reachable arms copy that
+/// local into the same destination before going to the same block, with the same storage
+/// statements, returns what the arms do.
+fn find_same_branches<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &Body<'tcx>,
+    bb: BasicBlock,
+) -> Option<SameBranches<'tcx>> {
+    let data = &body[bb];
+    let (discr, values, targets) = match data.terminator().kind {
+        TerminatorKind::SwitchInt {
+            discr: Operand::Copy(ref discr),
+            ref values,
+            ref targets,
+            ..
+        } |
+        TerminatorKind::SwitchInt {
+            discr: Operand::Move(ref discr),
+            ref values,
+            ref targets,
+            ..
+        } => (match_local(discr)?, values, targets),
+        _ => return None,
+    };
+
+    // The discriminant must be read by the last statement of the block.
+    let src = match data.statements.last()?.kind {
+        StatementKind::Assign(ref place, box Rvalue::Discriminant(ref src))
+            if match_local(place) == Some(discr) => match_local(src)?,
+        _ => return None,
+    };
+    let adt = match body.local_decls[src].ty.sty {
+        ty::Adt(adt, _) if adt.is_enum() => adt,
+        _ => return None,
+    };
+    let discriminants: Vec<_> = adt.discriminants(tcx).collect();
+
+    // The variant of `src` in the arm at `index`, if it's known.
+    let arm_variant = |index: usize| -> Option<VariantIdx> {
+        let mut variants = discriminants.iter().filter(|(_, discr)| match values.get(index) {
+            Some(&value) => discr.val == value,
+            // The otherwise branch.
+            None => !values.contains(&discr.val),
+        });
+        match (variants.next(), variants.next()) {
+            (Some(&(variant, _)), None) => Some(variant),
+            _ => None,
+        }
+    };
+
+    let mut common = None;
+    let mut operand = None;
+    for (index, &target) in targets.iter().enumerate() {
+        let target_data = &body[target];
+        // The only statement of the arm which isn't a storage statement, and the storage
+        // statements before and after it.
+        let mut statement = None;
+        let mut storage = (vec![], vec![]);
+        for arm_statement in &target_data.statements {
+            match arm_statement.kind {
+                StatementKind::StorageLive(_) |
+                StatementKind::StorageDead(_) => {
+                    if statement.is_none() {
+                        storage.0.push(arm_statement);
+                    } else {
+                        storage.1.push(arm_statement);
+                    }
+                }
+                StatementKind::Nop => {}
+                _ if statement.is_none() => statement = Some(arm_statement),
+                _ => return None,
+            }
+        }
+        let next = match target_data.terminator().kind {
+            // Reaching `unreachable` is undefined behavior, so such arms can be ignored.
+            TerminatorKind::Unreachable if statement.is_none() => continue,
+            TerminatorKind::Goto { target } => target,
+            _ => return None,
+        };
+
+        let dst = match statement?.kind {
+            StatementKind::Assign(ref dst, box Rvalue::Use(ref arm_operand)) => {
+                match *arm_operand {
+                    Operand::Copy(ref from) |
+                    Operand::Move(ref from) if match_local(from) == Some(src) => {}
+                    _ => return None,
+                }
+                operand = match operand {
+                    // `src` is still used afterwards if any arm copies it.
+                    Some(Operand::Copy(place)) => Some(Operand::Copy(place)),
+                    _ => Some(arm_operand.clone()),
+                };
+                match_local(dst)?
+            }
+            // Only fieldless variants are rebuilt by just setting their discriminant.
+            StatementKind::SetDiscriminant { ref place, variant_index } => {
+                if arm_variant(index) != Some(variant_index) ||
+                    !adt.variants[variant_index].fields.is_empty()
+                {
+                    return None;
+                }
+                match_local(place)?
+            }
+            _ => return None,
+        };
+
+        // The arms are removed, so their storage statements must be the same to be kept.
+        match common {
+            None => common = Some((dst, next, storage)),
+            Some((common_dst, common_next, ref common_storage))
+                if (common_dst, common_next) == (dst, next) &&
+                    same_storage(&common_storage.0, &storage.0) &&
+                    same_storage(&common_storage.1, &storage.1) => {}
+            Some(_) => return None,
+        }
+    }
+
+    let (dst, next, (storage_before, storage_after)) = common?;
+    if dst == src || body.local_decls[dst].ty != body.local_decls[src].ty {
+        return None;
+    }
+    let operand = operand.unwrap_or_else(|| Operand::Copy(Place::from(src)));
+    Some(SameBranches {
+        dst,
+        operand,
+        next,
+        storage_before: storage_before.into_iter().cloned().collect(),
+        storage_after: storage_after.into_iter().cloned().collect(),
+    })
+}
+
+/// Returns whether two lists of storage statements start and end the storage of the same locals
+/// in the same order.
+fn same_storage(a: &[&Statement<'_>], b: &[&Statement<'_>]) -> bool {
+    let marker = |statement: &&Statement<'_>| match statement.kind {
+        StatementKind::StorageLive(local) => (true, local),
+        StatementKind::StorageDead(local) => (false, local),
+        _ => bug!("not a storage statement: {:?}", statement),
+    };
+    a.iter().map(marker).eq(b.iter().map(marker))
+}
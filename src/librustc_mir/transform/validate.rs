@@ -0,0 +1,264 @@
+//! Checks the invariants of the MIR after each pass, with `-Z validate-mir`.
+//!
+//! A pass breaking the MIR otherwise only shows up much later, as an assertion in LLVM or as
+//! a miscompilation. The violations are reported as delayed bugs, naming the pass that was
+//! run last and the offending statement or terminator.
+
+use rustc::mir::*;
+use rustc::mir::traversal;
+use rustc::mir::visit::{PlaceContext, Visitor};
+use rustc::ty::{ParamEnv, Ty, TyCtxt};
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::indexed_vec::Idx;
+use crate::dataflow::{do_dataflow, DataflowResultsCursor, DebugFormatted, MaybeStorageLive};
+use crate::transform::MirSource;
+
+#[derive(Copy, Clone, Debug)]
+enum EdgeKind {
+    Unwind,
+    Normal,
+}
+
+/// Validates `body`, which was just transformed by the pass `pass_name`. `drops_elaborated`
+/// tells whether drop elaboration has already been run on it.
+pub fn validate_body<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    body: &Body<'tcx>,
+    pass_name: &str,
+    drops_elaborated: bool,
+) {
+    let def_id = source.def_id();
+    let mut indices = IndexChecker {
+        cx: Cx { tcx, source, body, pass_name, failed: false },
+    };
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        if data.terminator.is_none() {
+            indices.cx.fail(body.terminator_loc(bb), "basic block without a terminator");
+        }
+    }
+    indices.visit_body(body);
+    if indices.cx.failed {
+        // The other checks index the MIR with the indices found in it.
+        return;
+    }
+
+    // The locals without storage statements, like the arguments, are always live.
+    let mut storage_ignored = BitSet::new_filled(body.local_decls.len());
+    for data in body.basic_blocks() {
+        for statement in &data.statements {
+            match statement.kind {
+                StatementKind::StorageLive(local) |
+                StatementKind::StorageDead(local) => { storage_ignored.remove(local); }
+                _ => {}
+            }
+        }
+    }
+    let dead_unwinds = BitSet::new_empty(body.basic_blocks().len());
+    let storage_live = do_dataflow(tcx, body, def_id, &[], &dead_unwinds,
+                                   MaybeStorageLive::new(body),
+                                   |bd, p| DebugFormatted::new(&bd.body().local_decls[p]));
+    let mut reachable = BitSet::new_empty(body.basic_blocks().len());
+    for (bb, _) in traversal::preorder(body) {
+        reachable.insert(bb);
+    }
+
+    let mut validator = Validator {
+        cx: indices.cx,
+        param_env: tcx.param_env(def_id),
+        drops_elaborated,
+        storage_live: DataflowResultsCursor::new(storage_live, body),
+        storage_ignored,
+        reachable,
+    };
+    validator.visit_body(body);
+}
+
+struct Cx<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    body: &'a Body<'tcx>,
+    pass_name: &'a str,
+    failed: bool,
+}
+
+impl Cx<'_, '_> {
+    fn fail(&mut self, location: Location, msg: impl AsRef<str>) {
+        let data = &self.body[location.block];
+        let (span, culprit) = if location.statement_index < data.statements.len() {
+            let statement = &data.statements[location.statement_index];
+            (statement.source_info.span, format!("{:?}", statement))
+        } else if let Some(terminator) = &data.terminator {
+            (terminator.source_info.span, format!("{:?}", terminator.kind))
+        } else {
+            (self.body.span, String::from("<no terminator>"))
+        };
+        self.tcx.sess.delay_span_bug(span, &format!(
+            "broken MIR in {:?} after pass `{}`, at {:?} (`{}`): {}",
+            self.source, self.pass_name, location, culprit, msg.as_ref(),
+        ));
+        self.failed = true;
+    }
+}
+
+/// Checks that the basic blocks and locals referred to exist.
+struct IndexChecker<'a, 'tcx> {
+    cx: Cx<'a, 'tcx>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for IndexChecker<'a, 'tcx> {
+    fn visit_local(&mut self, local: &Local, _context: PlaceContext, location: Location) {
+        if local.index() >= self.cx.body.local_decls.len() {
+            self.cx.fail(location, format!("local {:?} is out of range", local));
+        }
+    }
+
+    fn visit_terminator_kind(&mut self, kind: &TerminatorKind<'tcx>, location: Location) {
+        for &target in kind.successors() {
+            if target.index() >= self.cx.body.basic_blocks().len() {
+                self.cx.fail(location, format!("target {:?} is out of range", target));
+            }
+        }
+        self.super_terminator_kind(kind, location);
+    }
+}
+
+struct Validator<'a, 'tcx> {
+    cx: Cx<'a, 'tcx>,
+    param_env: ParamEnv<'tcx>,
+    drops_elaborated: bool,
+    storage_live: DataflowResultsCursor<'a, 'tcx, MaybeStorageLive<'a, 'tcx>>,
+    storage_ignored: BitSet<Local>,
+    reachable: BitSet<BasicBlock>,
+}
+
+impl<'a, 'tcx> Validator<'a, 'tcx> {
+    fn check_edge(&mut self, location: Location, target: BasicBlock, edge_kind: EdgeKind) {
+        let src_is_cleanup = self.cx.body[location.block].is_cleanup;
+        let target_is_cleanup = self.cx.body[target].is_cleanup;
+        match (src_is_cleanup, target_is_cleanup, edge_kind) {
+            // Unwinding from a normal block into a cleanup block.
+            (false, true, EdgeKind::Unwind) |
+            // Normal control flow, staying within normal or cleanup blocks.
+            (false, false, EdgeKind::Normal) |
+            (true, true, EdgeKind::Normal) => {}
+            _ => self.cx.fail(location, format!(
+                "{:?} edge to {:?} violates the unwind invariants (cleanup {:?} -> {:?})",
+                edge_kind, target, src_is_cleanup, target_is_cleanup,
+            )),
+        }
+    }
+
+    fn check_unwind(&mut self, location: Location, unwind: Option<BasicBlock>) {
+        if let Some(unwind) = unwind {
+            self.check_edge(location, unwind, EdgeKind::Unwind);
+        }
+    }
+
+    /// Whether a value of type `src` can be assigned to a place of type `dest`. Regions
+    /// don't matter anymore once borrowck has run, so they are ignored.
+    fn assign_valid_types(&self, src: Ty<'tcx>, dest: Ty<'tcx>) -> bool {
+        if src == dest {
+            return true;
+        }
+        let tcx = self.cx.tcx;
+        let param_env = self.param_env.with_reveal_all();
+        let src = tcx.normalize_erasing_regions(param_env, src);
+        let dest = tcx.normalize_erasing_regions(param_env, dest);
+        // Higher-ranked function pointers and trait objects can be assigned to their
+        // instantiations, which differ from them by their late-bound regions, which
+        // `normalize_erasing_regions` keeps: erase them too.
+        let erase_all_regions = |ty: Ty<'tcx>| {
+            tcx.fold_regions(&ty, &mut false, |_, _| tcx.lifetimes.re_erased)
+        };
+        src == dest || erase_all_regions(src) == erase_all_regions(dest)
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for Validator<'a, 'tcx> {
+    fn visit_local(&mut self, local: &Local, context: PlaceContext, location: Location) {
+        if !context.is_use() ||
+            self.storage_ignored.contains(*local) ||
+            !self.reachable.contains(location.block)
+        {
+            return;
+        }
+        self.storage_live.seek(location);
+        if !self.storage_live.contains(*local) {
+            self.cx.fail(location, format!("use of {:?}, whose storage is dead", local));
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, location: Location) {
+        if let StatementKind::Assign(dest, rvalue) = &statement.kind {
+            let body = self.cx.body;
+            let dest_ty = dest.ty(body, self.cx.tcx).ty;
+            let src_ty = rvalue.ty(body, self.cx.tcx);
+            if !self.assign_valid_types(src_ty, dest_ty) {
+                self.cx.fail(location, format!(
+                    "assignment of a value of type `{}` to a place of type `{}`",
+                    src_ty, dest_ty,
+                ));
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_terminator_kind(&mut self, kind: &TerminatorKind<'tcx>, location: Location) {
+        match *kind {
+            TerminatorKind::Goto { target } => {
+                self.check_edge(location, target, EdgeKind::Normal);
+            }
+            TerminatorKind::SwitchInt { ref targets, .. } => {
+                for &target in targets {
+                    self.check_edge(location, target, EdgeKind::Normal);
+                }
+            }
+            TerminatorKind::Resume => {
+                if !self.cx.body[location.block].is_cleanup {
+                    self.cx.fail(location, "`resume` outside of a cleanup block");
+                }
+            }
+            TerminatorKind::DropAndReplace { target, unwind, .. } if self.drops_elaborated => {
+                self.cx.fail(location, "`DropAndReplace` after drop elaboration");
+                self.check_edge(location, target, EdgeKind::Normal);
+                self.check_unwind(location, unwind);
+            }
+            TerminatorKind::Drop { target, unwind, .. } |
+            TerminatorKind::DropAndReplace { target, unwind, .. } => {
+                self.check_edge(location, target, EdgeKind::Normal);
+                self.check_unwind(location, unwind);
+            }
+            TerminatorKind::Call { ref destination, cleanup, .. } => {
+                if let Some((_, target)) = *destination {
+                    self.check_edge(location, target, EdgeKind::Normal);
+                }
+                self.check_unwind(location, cleanup);
+            }
+            TerminatorKind::Assert { target, cleanup, .. } => {
+                self.check_edge(location, target, EdgeKind::Normal);
+                self.check_unwind(location, cleanup);
+            }
+            TerminatorKind::Yield { resume, drop, .. } => {
+                self.check_edge(location, resume, EdgeKind::Normal);
+                if let Some(drop) = drop {
+                    self.check_edge(location, drop, EdgeKind::Normal);
+                }
+            }
+            TerminatorKind::FalseEdges { real_target, imaginary_target } => {
+                self.check_edge(location, real_target, EdgeKind::Normal);
+                self.check_edge(location, imaginary_target, EdgeKind::Normal);
+            }
+            TerminatorKind::FalseUnwind { real_target, unwind } => {
+                self.check_edge(location, real_target, EdgeKind::Normal);
+                self.check_unwind(location, unwind);
+            }
+            TerminatorKind::Abort |
+            TerminatorKind::Return |
+            TerminatorKind::Unreachable |
+            TerminatorKind::GeneratorDrop => {}
+        }
+        self.super_terminator_kind(kind, location);
+    }
+}
+
@@ -22,7 +22,8 @@
 //! This means that users of this analysis still have to check whether
 //! pre-existing references can be used to access the value (e.g., at movable
 //! generator yield points, all pre-existing references are invalidated, so this
-//! doesn't matter).
+//! doesn't matter). `borrowed_locals` returns the locals for which this may be
+//! the case.
 
 use rustc::mir::visit::{
     PlaceContext, Visitor, MutatingUseContext, NonMutatingUseContext, NonUseContext,
@@ -31,6 +32,7 @@ use rustc::mir::Local;
 use rustc::mir::*;
 use rustc::ty::{self, TyCtxt};
 use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 use rustc_data_structures::work_queue::WorkQueue;
 use std::fs;
@@ -67,6 +69,38 @@ pub fn liveness_of_locals(
         .map(|b| block(b, num_live_vars))
         .collect();
 
+    compute_outs(body, def_use)
+}
+
+/// Like `liveness_of_locals`, but with the liveness of each statement and
+/// terminator used by `apply_statement_effect`, `apply_terminator_effect` and
+/// `live_after_locations`: `return` uses the return place, and the uses of a
+/// statement are applied after its defs, so that the local in
+/// `_1 = f(move _1)` is live before the call.
+///
+/// This is what optimizations removing or merging stores need. The generator
+/// transform still uses `liveness_of_locals`, which only matters at
+/// suspension points.
+pub fn exact_liveness_of_locals(
+    body: &Body<'_>,
+) -> LivenessResult {
+    let num_live_vars = body.local_decls.len();
+
+    let def_use: IndexVec<_, DefsUses> = body
+        .basic_blocks()
+        .iter()
+        .map(|b| exact_block(b, num_live_vars))
+        .collect();
+
+    compute_outs(body, def_use)
+}
+
+fn compute_outs(
+    body: &Body<'_>,
+    def_use: IndexVec<BasicBlock, DefsUses>,
+) -> LivenessResult {
+    let num_live_vars = body.local_decls.len();
+
     let mut outs: IndexVec<_, LiveVarSet> = body
         .basic_blocks()
         .indices()
@@ -186,9 +220,57 @@ pub fn categorize(context: PlaceContext) -> Option<DefUse> {
     }
 }
 
-struct DefsUsesVisitor
-{
-    defs_uses: DefsUses,
+/// The locals defined and used by a single statement or terminator.
+#[derive(Default)]
+struct StatementDefsUses {
+    defs: Vec<Local>,
+    uses: Vec<Local>,
+}
+
+impl StatementDefsUses {
+    fn of_statement(statement: &Statement<'_>, location: Location) -> Self {
+        let mut defs_uses = StatementDefsUses::default();
+        defs_uses.visit_statement(statement, location);
+        defs_uses
+    }
+
+    fn of_terminator(terminator: &Terminator<'_>, location: Location) -> Self {
+        let mut defs_uses = StatementDefsUses::default();
+        defs_uses.visit_terminator(terminator, location);
+        defs_uses
+    }
+
+    /// Updates `bits`, the set of locals live after the statement, to the set
+    /// of locals live before it.
+    fn apply(&self, bits: &mut LiveVarSet) {
+        // Uses are applied after defs, so that the local in `_1 = f(move _1)`
+        // is live before the call.
+        for &local in &self.defs {
+            bits.remove(local);
+        }
+        for &local in &self.uses {
+            bits.insert(local);
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for StatementDefsUses {
+    fn visit_terminator_kind(&mut self, kind: &TerminatorKind<'tcx>, location: Location) {
+        // The return place is read by `return`, even though it doesn't appear
+        // in the terminator.
+        if let TerminatorKind::Return = *kind {
+            self.uses.push(RETURN_PLACE);
+        }
+        self.super_terminator_kind(kind, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        match categorize(context) {
+            Some(DefUse::Def) => self.defs.push(local),
+            Some(DefUse::Use) | Some(DefUse::Drop) => self.uses.push(local),
+            _ => (),
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Clone)]
@@ -216,6 +298,15 @@ impl DefsUses {
         self.defs.insert(index);
     }
 
+    fn add_statement(&mut self, statement: &StatementDefsUses) {
+        for &local in &statement.defs {
+            self.add_def(local);
+        }
+        for &local in &statement.uses {
+            self.add_use(local);
+        }
+    }
+
     fn add_use(&mut self, index: Local) {
         // Inverse of above.
         //
@@ -232,6 +323,11 @@ impl DefsUses {
     }
 }
 
+struct DefsUsesVisitor
+{
+    defs_uses: DefsUses,
+}
+
 impl<'tcx> Visitor<'tcx> for DefsUsesVisitor
 {
     fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
@@ -269,6 +365,97 @@ fn block(
     visitor.defs_uses
 }
 
+fn exact_block(
+    b: &BasicBlockData<'_>,
+    locals: usize,
+) -> DefsUses {
+    let mut defs_uses = DefsUses {
+        defs: LiveVarSet::new_empty(locals),
+        uses: LiveVarSet::new_empty(locals),
+    };
+
+    let dummy_location = Location {
+        block: BasicBlock::new(0),
+        statement_index: 0,
+    };
+
+    defs_uses.add_statement(&StatementDefsUses::of_terminator(b.terminator(), dummy_location));
+    for statement in b.statements.iter().rev() {
+        defs_uses.add_statement(&StatementDefsUses::of_statement(statement, dummy_location));
+    }
+
+    defs_uses
+}
+
+/// Updates `bits`, the set of locals live after `statement`, to the set of
+/// locals live before it.
+pub fn apply_statement_effect(
+    bits: &mut LiveVarSet,
+    statement: &Statement<'_>,
+    location: Location,
+) {
+    StatementDefsUses::of_statement(statement, location).apply(bits);
+}
+
+/// Updates `bits`, the set of locals live after `terminator`, to the set of
+/// locals live before it.
+pub fn apply_terminator_effect(
+    bits: &mut LiveVarSet,
+    terminator: &Terminator<'_>,
+    location: Location,
+) {
+    StatementDefsUses::of_terminator(terminator, location).apply(bits);
+}
+
+/// Returns the set of locals live right after each statement or terminator
+/// for which `is_wanted` returns true, where `result` was computed by
+/// `exact_liveness_of_locals`.
+pub fn live_after_locations(
+    body: &Body<'_>,
+    result: &LivenessResult,
+    mut is_wanted: impl FnMut(Location) -> bool,
+) -> FxHashMap<Location, LiveVarSet> {
+    let mut live_after = FxHashMap::default();
+    let mut bits = LiveVarSet::new_empty(body.local_decls.len());
+    for (block, data) in body.basic_blocks().iter_enumerated() {
+        bits.overwrite(&result.outs[block]);
+
+        let location = body.terminator_loc(block);
+        if is_wanted(location) {
+            live_after.insert(location, bits.clone());
+        }
+        apply_terminator_effect(&mut bits, data.terminator(), location);
+
+        for (statement_index, statement) in data.statements.iter().enumerate().rev() {
+            let location = Location { block, statement_index };
+            if is_wanted(location) {
+                live_after.insert(location, bits.clone());
+            }
+            apply_statement_effect(&mut bits, statement, location);
+        }
+    }
+    live_after
+}
+
+struct BorrowedLocals(LiveVarSet);
+
+impl<'tcx> Visitor<'tcx> for BorrowedLocals {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        if context.is_borrow() {
+            self.0.insert(local);
+        }
+    }
+}
+
+/// Returns the locals that are borrowed anywhere in `body`. As explained in
+/// the module documentation, these may still be accessed through a reference
+/// when this analysis considers them dead.
+pub fn borrowed_locals(body: &Body<'_>) -> LiveVarSet {
+    let mut visitor = BorrowedLocals(LiveVarSet::new_empty(body.local_decls.len()));
+    visitor.visit_body(body);
+    visitor.0
+}
+
 pub fn dump_mir<'tcx>(
     tcx: TyCtxt<'tcx>,
     pass_name: &str,
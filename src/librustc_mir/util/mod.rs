@@ -9,6 +9,7 @@ mod graphviz;
 pub(crate) mod pretty;
 pub mod liveness;
 pub mod collect_writes;
+pub mod parse;
 
 pub use self::aggregate::expand_aggregate;
 pub use self::alignment::is_disaligned;
@@ -0,0 +1,1219 @@
+//! Parses the MIR printed by `-Z dump-mir` and `--emit=mir` back into a `Body`.
+//!
+//! This lets tests run a pass on exactly the MIR it should transform, with
+//! `#[rustc_mir(from_text = "...", run_pass = "...")]`, rather than on Rust source that happens
+//! to lower to it. Only the subset of the syntax found in optimized MIR is supported: there are
+//! no user type annotations, closures, generators, statics, promoteds or string constants.
+//! Comments are skipped, so the names of user variables and the source spans are lost.
+
+use rustc::hir::{self, def::{CtorKind, DefKind}};
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
+use rustc::mir::*;
+use rustc::mir::interpret::{sign_extend, truncate, PanicInfo, Scalar};
+use rustc::ty::{self, AdtDef, Ty, TyCtxt};
+use rustc::ty::adjustment::PointerCast;
+use rustc::ty::layout::{Size, VariantIdx};
+use rustc::ty::subst::{InternalSubsts, Subst, UnpackedKind};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::indexed_vec::{Idx, IndexVec};
+use rustc_target::spec::abi::Abi;
+use std::borrow::Cow;
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+use syntax::ast;
+use syntax_pos::Span;
+
+/// An error in the MIR text, at the given line and column.
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub col: usize,
+    pub msg: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.col, self.msg)
+    }
+}
+
+type PResult<T> = Result<T, ParseError>;
+
+/// The largest index of a local, basic block, scope, field or variant. The locals and scopes
+/// are stored in vectors as large as their largest index.
+const MAX_INDEX: usize = 0xffff;
+
+/// Parses `text`, the MIR of the function `def_id` as printed by `write_mir_fn`. Its
+/// signature has to be the one of the function.
+pub fn parse_mir<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId, text: &str) -> PResult<Body<'tcx>> {
+    let mut parser = Parser::new(tcx, def_id, text);
+    match tcx.def_kind(def_id) {
+        Some(DefKind::Fn) | Some(DefKind::Method) => {}
+        _ => return Err(parser.error("only the MIR of functions can be parsed")),
+    }
+    let body = parser.body()?;
+    if !parser.rest().is_empty() {
+        return Err(parser.error("expected the end of the MIR"));
+    }
+    Ok(body)
+}
+
+/// The successors of a terminator, as printed after its `->`.
+enum Targets {
+    None,
+    Single(BasicBlock),
+    Labeled(Vec<(String, BasicBlock)>),
+}
+
+enum Line<'tcx> {
+    Statement(StatementKind<'tcx>),
+    Terminator(TerminatorKind<'tcx>),
+}
+
+struct Parser<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    span: Span,
+    text: &'a str,
+    pos: usize,
+    /// The ADTs which can be named, by their printed paths: the ones of the crate, and the
+    /// ones in the signature of the function.
+    adts: FxHashMap<String, &'tcx AdtDef>,
+    /// The functions of the crate, by their printed paths.
+    fns: FxHashMap<String, DefId>,
+    /// The type parameters in scope, by name.
+    ty_params: FxHashMap<String, Ty<'tcx>>,
+    /// The locals, once all of them have been declared.
+    local_decls: IndexVec<Local, LocalDecl<'tcx>>,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl<'a, 'tcx> Parser<'a, 'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, def_id: DefId, text: &'a str) -> Self {
+        let mut adts = FxHashMap::default();
+        for item in tcx.hir().krate().items.values() {
+            match item.node {
+                hir::ItemKind::Struct(..) | hir::ItemKind::Enum(..) | hir::ItemKind::Union(..) => {
+                    let adt_def_id = tcx.hir().local_def_id(item.hir_id);
+                    adts.insert(tcx.def_path_str(adt_def_id), tcx.adt_def(adt_def_id));
+                }
+                _ => {}
+            }
+        }
+        let sig = tcx.fn_sig(def_id);
+        for &ty in sig.skip_binder().inputs_and_output.iter() {
+            for ty in ty.walk() {
+                if let ty::Adt(adt, _) = ty.sty {
+                    adts.insert(tcx.def_path_str(adt.did), adt);
+                }
+            }
+        }
+
+        let mut fns = FxHashMap::default();
+        for &fn_def_id in tcx.mir_keys(LOCAL_CRATE).iter() {
+            if let Some(DefKind::Fn) = tcx.def_kind(fn_def_id) {
+                fns.insert(tcx.def_path_str(fn_def_id), fn_def_id);
+            }
+        }
+
+        let mut ty_params = FxHashMap::default();
+        for kind in InternalSubsts::identity_for_item(tcx, def_id) {
+            if let UnpackedKind::Type(ty) = kind.unpack() {
+                if let ty::Param(param) = ty.sty {
+                    ty_params.insert(param.name.to_string(), ty);
+                }
+            }
+        }
+
+        Parser {
+            tcx,
+            def_id,
+            span: tcx.def_span(def_id),
+            text,
+            pos: 0,
+            adts,
+            fns,
+            ty_params,
+            local_decls: IndexVec::new(),
+        }
+    }
+
+    fn error(&self, msg: impl Into<String>) -> ParseError {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let col = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        ParseError { line, col, msg: msg.into() }
+    }
+
+    /// Skips whitespace and comments, and returns the rest of the text.
+    fn rest(&mut self) -> &'a str {
+        loop {
+            let rest = &self.text[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                return trimmed;
+            }
+        }
+    }
+
+    /// Whether the text continues with `token`, which isn't followed by an identifier
+    /// character if it ends with one.
+    fn check(&mut self, token: &str) -> bool {
+        let rest = self.rest();
+        rest.starts_with(token) && !(
+            token.ends_with(is_ident_char) &&
+                rest[token.len()..].starts_with(is_ident_char)
+        )
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.check(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> PResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", token)))
+        }
+    }
+
+    fn ident(&mut self) -> PResult<&'a str> {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("expected an identifier"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn number<T: FromStr>(&mut self) -> PResult<T> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        match rest[..len].parse() {
+            Ok(n) => {
+                self.pos += len;
+                Ok(n)
+            }
+            Err(_) => Err(self.error("expected a number")),
+        }
+    }
+
+    /// Parses the index of a local, basic block, scope, field or variant.
+    fn index(&mut self) -> PResult<usize> {
+        let start = self.pos;
+        match self.number::<usize>() {
+            Ok(index) if index <= MAX_INDEX => Ok(index),
+            _ => {
+                self.pos = start;
+                Err(self.error(format!("expected an index up to {}", MAX_INDEX)))
+            }
+        }
+    }
+
+    /// Parses a path, skipping the generic arguments in it, and returns it along with its
+    /// last segment.
+    fn value_path(&mut self) -> PResult<(String, &'a str)> {
+        let mut path = String::new();
+        loop {
+            let segment = self.ident()?;
+            path.push_str(segment);
+            if self.eat("::<") {
+                let mut depth = 1;
+                while depth > 0 {
+                    let rest = self.rest();
+                    match rest.chars().next() {
+                        Some('<') => depth += 1,
+                        Some('>') => depth -= 1,
+                        Some(_) => {}
+                        None => return Err(self.error("unterminated generic arguments")),
+                    }
+                    self.pos += rest.chars().next().unwrap().len_utf8();
+                }
+            }
+            if !self.eat("::") {
+                return Ok((path, segment));
+            }
+            path.push_str("::");
+        }
+    }
+
+    /// Parses a path without generic arguments.
+    fn path(&mut self) -> PResult<String> {
+        let mut path = self.ident()?.to_string();
+        while !self.check("::<") && self.eat("::") {
+            path.push_str("::");
+            path.push_str(self.ident()?);
+        }
+        Ok(path)
+    }
+
+    fn local(&mut self) -> PResult<Local> {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+        if rest.starts_with('_') && len > 1 && rest[1..len].bytes().all(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+            Ok(Local::new(self.index()?))
+        } else {
+            Err(self.error("expected a local"))
+        }
+    }
+
+    fn check_local(&mut self) -> bool {
+        let start = self.pos;
+        let found = self.local().is_ok();
+        self.pos = start;
+        found
+    }
+
+    fn block(&mut self) -> PResult<BasicBlock> {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+        if rest.starts_with("bb") && len > 2 && rest[2..len].bytes().all(|b| b.is_ascii_digit()) {
+            self.pos += 2;
+            Ok(BasicBlock::new(self.index()?))
+        } else {
+            Err(self.error("expected a basic block"))
+        }
+    }
+
+    fn local_decl(
+        &self,
+        mutability: Mutability,
+        ty: Ty<'tcx>,
+        scope: SourceScope,
+    ) -> LocalDecl<'tcx> {
+        let source_info = SourceInfo { span: self.span, scope };
+        LocalDecl {
+            mutability,
+            ty,
+            user_ty: UserTypeProjections::none(),
+            name: None,
+            source_info,
+            visibility_scope: scope,
+            internal: false,
+            is_user_variable: None,
+            is_block_tail: None,
+        }
+    }
+
+    fn body(&mut self) -> PResult<Body<'tcx>> {
+        self.expect("fn")?;
+        let name = self.path()?;
+        let expected = self.tcx.def_path_str(self.def_id);
+        if name != expected {
+            return Err(self.error(format!("expected the MIR of `{}`, found `{}`", expected, name)));
+        }
+
+        let mut decls = vec![None];
+        self.expect("(")?;
+        while !self.eat(")") {
+            if decls.len() > 1 {
+                self.expect(",")?;
+            }
+            if self.local()?.index() != decls.len() {
+                return Err(self.error(format!("expected the argument `_{}`", decls.len())));
+            }
+            self.expect(":")?;
+            let ty = self.ty()?;
+            decls.push(Some(self.local_decl(Mutability::Not, ty, OUTERMOST_SOURCE_SCOPE)));
+        }
+        let arg_count = decls.len() - 1;
+        self.expect("->")?;
+        let return_ty = self.ty()?;
+
+        let sig = self.tcx.fn_sig(self.def_id);
+        let sig = self.tcx.erase_regions(&self.tcx.erase_late_bound_regions(&sig));
+        let matches = sig.output() == return_ty && sig.inputs().iter()
+            .zip(&decls[1..])
+            .all(|(&ty, decl)| decl.as_ref().map(|decl| decl.ty) == Some(ty));
+        if !matches || sig.inputs().len() != arg_count {
+            return Err(self.error(format!("the signature of `{}` is `{}`", expected, sig)));
+        }
+
+        self.expect("{")?;
+        let mut scopes = vec![Some(SourceScopeData { span: self.span, parent_scope: None })];
+        self.scope(OUTERMOST_SOURCE_SCOPE, &mut decls, &mut scopes)?;
+        for (index, decl) in decls.into_iter().enumerate() {
+            match decl {
+                Some(decl) => { self.local_decls.push(decl); }
+                None => return Err(self.error(format!("`_{}` is not declared", index))),
+            }
+        }
+        if self.local_decls[RETURN_PLACE].ty != return_ty {
+            return Err(self.error(format!("the return place is not of type `{}`", return_ty)));
+        }
+        let mut source_scopes = IndexVec::new();
+        for (index, scope) in scopes.into_iter().enumerate() {
+            match scope {
+                Some(scope) => { source_scopes.push(scope); }
+                None => return Err(self.error(format!("scope {} is not declared", index))),
+            }
+        }
+
+        let mut blocks = IndexVec::new();
+        while !self.eat("}") {
+            let data = self.basic_block(blocks.next_index())?;
+            blocks.push(data);
+        }
+        for data in blocks.iter() {
+            for target in data.terminator().successors() {
+                if target.index() >= blocks.len() {
+                    return Err(self.error(format!("`{:?}` is not defined", target)));
+                }
+            }
+        }
+
+        Ok(Body::new(
+            blocks,
+            source_scopes,
+            ClearCrossCrate::Clear,
+            IndexVec::new(),
+            None,
+            mem::replace(&mut self.local_decls, IndexVec::new()),
+            IndexVec::new(),
+            arg_count,
+            vec![],
+            self.span,
+            vec![],
+        ))
+    }
+
+    /// Parses the declarations of the locals in `scope` and its children.
+    fn scope(
+        &mut self,
+        scope: SourceScope,
+        decls: &mut Vec<Option<LocalDecl<'tcx>>>,
+        scopes: &mut Vec<Option<SourceScopeData>>,
+    ) -> PResult<()> {
+        loop {
+            if self.eat("let") {
+                let mutability = if self.eat("mut") { Mutability::Mut } else { Mutability::Not };
+                let local = self.local()?;
+                self.expect(":")?;
+                let ty = self.ty()?;
+                self.expect(";")?;
+                if decls.len() <= local.index() {
+                    decls.resize_with(local.index() + 1, || None);
+                }
+                if decls[local.index()].is_some() {
+                    return Err(self.error(format!("`{:?}` is declared twice", local)));
+                }
+                decls[local.index()] = Some(self.local_decl(mutability, ty, scope));
+            } else if self.eat("scope") {
+                let index = self.index()?;
+                if scopes.len() <= index {
+                    scopes.resize_with(index + 1, || None);
+                }
+                if scopes[index].is_some() {
+                    return Err(self.error(format!("scope {} is declared twice", index)));
+                }
+                scopes[index] = Some(SourceScopeData {
+                    span: self.span,
+                    parent_scope: Some(scope),
+                });
+                self.expect("{")?;
+                self.scope(SourceScope::new(index), decls, scopes)?;
+                self.expect("}")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn basic_block(&mut self, expected: BasicBlock) -> PResult<BasicBlockData<'tcx>> {
+        if self.block()? != expected {
+            return Err(self.error(format!("expected `{:?}`", expected)));
+        }
+        let mut data = BasicBlockData::new(None);
+        if self.eat("(") {
+            self.expect("cleanup")?;
+            self.expect(")")?;
+            data.is_cleanup = true;
+        }
+        self.expect(":")?;
+        self.expect("{")?;
+        let source_info = SourceInfo { span: self.span, scope: OUTERMOST_SOURCE_SCOPE };
+        loop {
+            let line = self.line()?;
+            self.expect(";")?;
+            match line {
+                Line::Statement(kind) => data.statements.push(Statement { source_info, kind }),
+                Line::Terminator(kind) => {
+                    data.terminator = Some(Terminator { source_info, kind });
+                    break;
+                }
+            }
+        }
+        self.expect("}")?;
+        Ok(data)
+    }
+
+    /// Parses a statement or a terminator.
+    fn line(&mut self) -> PResult<Line<'tcx>> {
+        let terminator = if self.eat("goto") {
+            match self.targets()? {
+                Targets::Single(target) => TerminatorKind::Goto { target },
+                _ => return Err(self.error("expected a single target")),
+            }
+        } else if self.eat("return") {
+            TerminatorKind::Return
+        } else if self.eat("resume") {
+            TerminatorKind::Resume
+        } else if self.eat("abort") {
+            TerminatorKind::Abort
+        } else if self.eat("unreachable") {
+            TerminatorKind::Unreachable
+        } else if self.eat("generator_drop") {
+            TerminatorKind::GeneratorDrop
+        } else if self.eat("switchInt") {
+            self.expect("(")?;
+            let discr = self.operand()?;
+            self.expect(")")?;
+            self.switch_int(discr)?
+        } else if self.eat("drop") {
+            self.expect("(")?;
+            let location = self.place()?;
+            self.expect(")")?;
+            let (target, unwind) = self.main_and_unwind("return", "unwind")?;
+            TerminatorKind::Drop { location, target, unwind }
+        } else if self.eat("replace") {
+            self.expect("(")?;
+            let location = self.place()?;
+            self.expect("<-")?;
+            let value = self.operand()?;
+            self.expect(")")?;
+            let (target, unwind) = self.main_and_unwind("return", "unwind")?;
+            TerminatorKind::DropAndReplace { location, value, target, unwind }
+        } else if self.eat("assert") {
+            self.expect("(")?;
+            let expected = !self.eat("!");
+            let cond = self.operand()?;
+            self.expect(",")?;
+            let msg = self.assert_message()?;
+            self.expect(")")?;
+            let (target, cleanup) = self.main_and_unwind("success", "unwind")?;
+            TerminatorKind::Assert { cond, expected, msg, target, cleanup }
+        } else if self.eat("falseEdges") {
+            match self.main_and_unwind("real", "imaginary")? {
+                (real_target, Some(imaginary_target)) => {
+                    TerminatorKind::FalseEdges { real_target, imaginary_target }
+                }
+                _ => return Err(self.error("expected a real and an imaginary target")),
+            }
+        } else if self.eat("falseUnwind") {
+            let (real_target, unwind) = self.main_and_unwind("real", "cleanup")?;
+            TerminatorKind::FalseUnwind { real_target, unwind }
+        } else if self.eat("StorageLive") {
+            return Ok(Line::Statement(StatementKind::StorageLive(self.parenthesized_local()?)));
+        } else if self.eat("StorageDead") {
+            return Ok(Line::Statement(StatementKind::StorageDead(self.parenthesized_local()?)));
+        } else if self.eat("nop") {
+            return Ok(Line::Statement(StatementKind::Nop));
+        } else if self.eat("discriminant") {
+            self.expect("(")?;
+            let place = self.place()?;
+            self.expect(")")?;
+            self.expect("=")?;
+            let variant_index = VariantIdx::new(self.index()?);
+            return Ok(Line::Statement(StatementKind::SetDiscriminant { place, variant_index }));
+        } else if self.check("const") {
+            let func = self.operand()?;
+            self.call(func, None)?
+        } else {
+            let place = self.place()?;
+            self.expect("=")?;
+            let start = self.pos;
+            if let Ok(func) = self.operand() {
+                if self.check("(") {
+                    return Ok(Line::Terminator(self.call(func, Some(place))?));
+                }
+            }
+            self.pos = start;
+            let ty = place.ty(&self.local_decls, self.tcx).ty;
+            let rvalue = self.rvalue(ty)?;
+            return Ok(Line::Statement(StatementKind::Assign(place, box rvalue)));
+        };
+        Ok(Line::Terminator(terminator))
+    }
+
+    fn parenthesized_local(&mut self) -> PResult<Local> {
+        self.expect("(")?;
+        let local = self.declared_local()?;
+        self.expect(")")?;
+        Ok(local)
+    }
+
+    fn declared_local(&mut self) -> PResult<Local> {
+        let local = self.local()?;
+        if local.index() >= self.local_decls.len() {
+            return Err(self.error(format!("`{:?}` is not declared", local)));
+        }
+        Ok(local)
+    }
+
+    fn targets(&mut self) -> PResult<Targets> {
+        if !self.eat("->") {
+            return Ok(Targets::None);
+        }
+        if !self.eat("[") {
+            return Ok(Targets::Single(self.block()?));
+        }
+        let mut targets = vec![];
+        while !self.eat("]") {
+            if !targets.is_empty() {
+                self.expect(",")?;
+            }
+            let label = self.ident()?.to_string();
+            self.expect(":")?;
+            targets.push((label, self.block()?));
+        }
+        Ok(Targets::Labeled(targets))
+    }
+
+    /// Parses the targets of a terminator with a main target and an optional second one,
+    /// labeled `main` and `second` when both are present.
+    fn main_and_unwind(
+        &mut self,
+        main: &str,
+        second: &str,
+    ) -> PResult<(BasicBlock, Option<BasicBlock>)> {
+        match self.targets()? {
+            Targets::Single(target) => return Ok((target, None)),
+            Targets::Labeled(ref targets) if targets.len() == 2 &&
+                targets[0].0 == main && targets[1].0 == second => {
+                return Ok((targets[0].1, Some(targets[1].1)));
+            }
+            Targets::Labeled(ref targets) if targets.len() == 1 && targets[0].0 == main => {
+                return Ok((targets[0].1, None));
+            }
+            _ => {}
+        }
+        Err(self.error(format!("expected `-> [{}: bbN, {}: bbN]`", main, second)))
+    }
+
+    fn switch_int(&mut self, discr: Operand<'tcx>) -> PResult<TerminatorKind<'tcx>> {
+        let switch_ty = discr.ty(&self.local_decls, self.tcx);
+        let mut values = vec![];
+        let mut targets = vec![];
+        self.expect("->")?;
+        if self.eat("[") {
+            loop {
+                if self.eat("otherwise") {
+                    self.expect(":")?;
+                    targets.push(self.block()?);
+                    self.expect("]")?;
+                    break;
+                }
+                let value = self.literal()?;
+                let bits = if value.ty == switch_ty {
+                    value.try_eval_bits(self.tcx, ty::ParamEnv::empty(), switch_ty)
+                } else {
+                    None
+                };
+                match bits {
+                    Some(bits) => values.push(bits),
+                    None => {
+                        return Err(self.error(format!("expected a value of type `{}`", switch_ty)));
+                    }
+                }
+                self.expect(":")?;
+                targets.push(self.block()?);
+                self.expect(",")?;
+            }
+        } else {
+            targets.push(self.block()?);
+        }
+        Ok(TerminatorKind::SwitchInt { discr, switch_ty, values: Cow::from(values), targets })
+    }
+
+    fn call(
+        &mut self,
+        func: Operand<'tcx>,
+        destination: Option<Place<'tcx>>,
+    ) -> PResult<TerminatorKind<'tcx>> {
+        let args = self.operands()?;
+        // Calls which don't return only have their unwind target printed.
+        let (target, cleanup) = match (destination.is_some(), self.targets()?) {
+            (false, Targets::None) => (None, None),
+            (false, Targets::Single(cleanup)) => (None, Some(cleanup)),
+            (true, Targets::Single(target)) => (Some(target), None),
+            (true, Targets::Labeled(ref targets)) if targets.len() == 2 &&
+                targets[0].0 == "return" && targets[1].0 == "unwind" => {
+                (Some(targets[0].1), Some(targets[1].1))
+            }
+            _ => return Err(self.error("unexpected targets for a call")),
+        };
+        let destination = destination.map(|place| (place, target.unwrap()));
+        Ok(TerminatorKind::Call { func, args, destination, cleanup, from_hir_call: true })
+    }
+
+    fn assert_message(&mut self) -> PResult<AssertMessage<'tcx>> {
+        self.expect("\"")?;
+        let msg = if self.eat("index out of bounds: the len is") {
+            let len = self.operand()?;
+            self.expect("but the index is")?;
+            let index = self.operand()?;
+            PanicInfo::BoundsCheck { len, index }
+        } else {
+            let text = self.text;
+            let rest = &text[self.pos..];
+            let end = rest.find('"').ok_or_else(|| self.error("unterminated message"))?;
+            let msgs = [
+                PanicInfo::Overflow(BinOp::Add),
+                PanicInfo::Overflow(BinOp::Sub),
+                PanicInfo::Overflow(BinOp::Mul),
+                PanicInfo::Overflow(BinOp::Div),
+                PanicInfo::Overflow(BinOp::Rem),
+                PanicInfo::OverflowNeg,
+                PanicInfo::Overflow(BinOp::Shr),
+                PanicInfo::Overflow(BinOp::Shl),
+                PanicInfo::DivisionByZero,
+                PanicInfo::RemainderByZero,
+                PanicInfo::GeneratorResumedAfterReturn,
+                PanicInfo::GeneratorResumedAfterPanic,
+            ];
+            let msg = msgs.iter().find(|msg| msg.description() == &rest[..end]).cloned();
+            let msg = msg.ok_or_else(|| self.error("unknown assertion message"))?;
+            self.pos += end;
+            msg
+        };
+        self.expect("\"")?;
+        Ok(msg)
+    }
+
+    /// Parses operands in parentheses, separated by commas.
+    fn operands(&mut self) -> PResult<Vec<Operand<'tcx>>> {
+        self.expect("(")?;
+        let mut operands = vec![];
+        while !self.eat(")") {
+            if !operands.is_empty() {
+                self.expect(",")?;
+                // Tuples with a single field are printed with a trailing comma.
+                if self.eat(")") {
+                    break;
+                }
+            }
+            operands.push(self.operand()?);
+        }
+        Ok(operands)
+    }
+
+    fn operand(&mut self) -> PResult<Operand<'tcx>> {
+        if self.eat("const") {
+            let literal = self.literal()?;
+            Ok(Operand::Constant(box Constant { span: self.span, user_ty: None, literal }))
+        } else if self.eat("move") {
+            Ok(Operand::Move(self.place()?))
+        } else {
+            Ok(Operand::Copy(self.place()?))
+        }
+    }
+
+    /// Parses the value of a constant, after its `const`.
+    fn literal(&mut self) -> PResult<&'tcx ty::Const<'tcx>> {
+        let tcx = self.tcx;
+        if self.eat("true") {
+            return Ok(ty::Const::from_bool(tcx, true));
+        }
+        if self.eat("false") {
+            return Ok(ty::Const::from_bool(tcx, false));
+        }
+        if self.eat("Scalar(<ZST>)") {
+            self.expect(":")?;
+            let ty = self.ty()?;
+            return Ok(ty::Const::zero_sized(tcx, ty));
+        }
+        if self.check("'") {
+            let c = self.char_literal()?;
+            return Ok(ty::Const::from_scalar(tcx, Scalar::from_char(c), tcx.types.char));
+        }
+
+        let rest = self.rest();
+        if rest.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+            let len = rest[1..].find(|c: char| !c.is_ascii_digit() && c != '.' && c != 'e')
+                .map_or(rest.len(), |len| len + 1);
+            let number = &rest[..len];
+            self.pos += len;
+            let suffix = self.ident()?;
+            let ty = self.primitive_ty(suffix)
+                .filter(|ty| ty.is_integral() || ty.is_floating_point())
+                .ok_or_else(|| self.error(format!("unknown literal suffix `{}`", suffix)))?;
+            let size = self.size_of(ty);
+            let bits = match ty.sty {
+                ty::Float(ast::FloatTy::F32) => {
+                    number.parse::<f32>().ok().map(|f| f.to_bits().into())
+                }
+                ty::Float(ast::FloatTy::F64) => {
+                    number.parse::<f64>().ok().map(|f| f.to_bits().into())
+                }
+                _ if ty.is_signed() => number.parse::<i128>().ok()
+                    .map(|n| n as u128)
+                    .filter(|&n| sign_extend(truncate(n, size), size) == n),
+                _ => number.parse::<u128>().ok().filter(|&n| truncate(n, size) == n),
+            };
+            let bits = bits.ok_or_else(|| self.error(format!("invalid `{}`", ty)))?;
+            let scalar = Scalar::from_uint(truncate(bits, size), size);
+            return Ok(ty::Const::from_scalar(tcx, scalar, ty));
+        }
+
+        let path = self.path()?;
+        let segments: Vec<_> = path.split("::").collect();
+        if let ["std", name, bound @ "MIN"] | ["std", name, bound @ "MAX"] = segments[..] {
+            if let Some(ty) = self.primitive_ty(name).filter(|ty| ty.is_integral()) {
+                let size = self.size_of(ty);
+                let bits = match (ty.is_signed(), bound) {
+                    (false, "MIN") => 0,
+                    (false, _) => truncate(u128::max_value(), size),
+                    (true, "MIN") => 1 << (size.bits() - 1),
+                    (true, _) => (1 << (size.bits() - 1)) - 1,
+                };
+                return Ok(ty::Const::from_scalar(tcx, Scalar::from_uint(bits, size), ty));
+            }
+        }
+        let def_id = match self.fns.get(&path) {
+            Some(&def_id) => def_id,
+            None => return Err(self.error(format!("unknown function `{}`", path))),
+        };
+        let tys = if self.eat("::<") { self.ty_list(">")? } else { vec![] };
+        let substs = self.substs(def_id, &tys)?;
+        Ok(ty::Const::zero_sized(tcx, tcx.type_of(def_id).subst(tcx, substs)))
+    }
+
+    /// Parses a character printed with its `Debug` implementation.
+    fn char_literal(&mut self) -> PResult<char> {
+        self.expect("'")?;
+        let text = self.text;
+        let rest = &text[self.pos..];
+        let (c, len) = if rest.starts_with("\\u{") {
+            let end = rest.find('}').unwrap_or(0);
+            let c = rest.get(3..end)
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(std::char::from_u32);
+            (c, end + 1)
+        } else if rest.starts_with('\\') {
+            let c = match rest[1..].chars().next() {
+                Some('n') => Some('\n'),
+                Some('r') => Some('\r'),
+                Some('t') => Some('\t'),
+                Some('0') => Some('\0'),
+                Some(c @ '\\') | Some(c @ '\'') | Some(c @ '"') => Some(c),
+                _ => None,
+            };
+            (c, 2)
+        } else {
+            let c = rest.chars().next();
+            (c, c.map_or(0, char::len_utf8))
+        };
+        let c = c.ok_or_else(|| self.error("invalid character literal"))?;
+        self.pos += len;
+        self.expect("'")?;
+        Ok(c)
+    }
+
+    fn size_of(&self, ty: Ty<'tcx>) -> Size {
+        self.tcx.layout_of(ty::ParamEnv::empty().and(ty)).unwrap().size
+    }
+
+    /// Returns the substitutions of the item `def_id` for the types `tys`, with erased
+    /// regions.
+    fn substs(&self, def_id: DefId, tys: &[Ty<'tcx>]) -> PResult<ty::subst::SubstsRef<'tcx>> {
+        let generics = self.tcx.generics_of(def_id);
+        let expected = generics.params.iter().filter(|param| match param.kind {
+            ty::GenericParamDefKind::Lifetime => false,
+            _ => true,
+        }).count();
+        if generics.parent.is_some() || tys.len() != expected {
+            return Err(self.error(format!(
+                "expected {} type arguments for `{}`",
+                expected,
+                self.tcx.def_path_str(def_id),
+            )));
+        }
+        let mut tys = tys.iter();
+        Ok(InternalSubsts::for_item(self.tcx, def_id, |param, _| match param.kind {
+            ty::GenericParamDefKind::Lifetime => self.tcx.lifetimes.re_erased.into(),
+            _ => (*tys.next().unwrap()).into(),
+        }))
+    }
+
+    fn place(&mut self) -> PResult<Place<'tcx>> {
+        let mut place = if self.eat("(") {
+            if self.eat("*") {
+                let place = self.place()?;
+                self.expect(")")?;
+                place.deref()
+            } else {
+                let place = self.place()?;
+                if self.eat(".") {
+                    let field = Field::new(self.index()?);
+                    self.expect(":")?;
+                    let ty = self.ty()?;
+                    self.expect(")")?;
+                    place.field(field, ty)
+                } else if self.eat("as") {
+                    let ty = place.ty(&self.local_decls, self.tcx).ty;
+                    let adt = match ty.sty {
+                        ty::Adt(adt, _) if adt.is_enum() => adt,
+                        _ => return Err(self.error(format!("`{}` is not an enum", ty))),
+                    };
+                    let place = if self.eat("variant#") {
+                        place.downcast_unnamed(VariantIdx::new(self.index()?))
+                    } else {
+                        let variant = self.variant(adt)?;
+                        place.downcast(adt, variant)
+                    };
+                    self.expect(")")?;
+                    place
+                } else {
+                    return Err(self.error("expected `.` or `as`"));
+                }
+            }
+        } else {
+            Place::from(self.declared_local()?)
+        };
+
+        while self.eat("[") {
+            let elem = if self.eat(":") {
+                self.expect("-")?;
+                ProjectionElem::Subslice { from: 0, to: self.number()? }
+            } else if self.check_local() {
+                ProjectionElem::Index(self.declared_local()?)
+            } else {
+                let from_end = self.eat("-");
+                let offset = self.number()?;
+                if !from_end && self.eat(":") {
+                    let to = if self.eat("-") { self.number()? } else { 0 };
+                    ProjectionElem::Subslice { from: offset, to }
+                } else {
+                    self.expect("of")?;
+                    let min_length = self.number()?;
+                    ProjectionElem::ConstantIndex { offset, min_length, from_end }
+                }
+            };
+            self.expect("]")?;
+            place = place.elem(elem);
+        }
+        Ok(place)
+    }
+
+    fn variant(&mut self, adt: &'tcx AdtDef) -> PResult<VariantIdx> {
+        let name = self.ident()?;
+        adt.variants.iter_enumerated()
+            .find(|(_, variant)| variant.ident.as_str() == name)
+            .map(|(index, _)| index)
+            .ok_or_else(|| self.error(format!("`{:?}` has no variant `{}`", adt, name)))
+    }
+
+    /// Parses the value assigned to a place of type `expected`.
+    fn rvalue(&mut self, expected: Ty<'tcx>) -> PResult<Rvalue<'tcx>> {
+        if self.eat("&") {
+            let kind = if self.eat("mut") {
+                BorrowKind::Mut { allow_two_phase_borrow: false }
+            } else if self.eat("shallow") {
+                BorrowKind::Shallow
+            } else {
+                BorrowKind::Shared
+            };
+            return Ok(Rvalue::Ref(self.tcx.lifetimes.re_erased, kind, self.place()?));
+        }
+
+        if self.eat("[") {
+            let elem_ty = match expected.sty {
+                ty::Array(elem_ty, _) => elem_ty,
+                _ => return Err(self.error(format!("expected a value of type `{}`", expected))),
+            };
+            let mut operands = vec![];
+            while !self.eat("]") {
+                if !operands.is_empty() {
+                    self.expect(",")?;
+                }
+                operands.push(self.operand()?);
+                if operands.len() == 1 && self.eat(";") {
+                    let count = self.number()?;
+                    self.expect("]")?;
+                    return Ok(Rvalue::Repeat(operands.pop().unwrap(), count));
+                }
+            }
+            return Ok(Rvalue::Aggregate(box AggregateKind::Array(elem_ty), operands));
+        }
+
+        if self.check("(") {
+            // Either a tuple, or an operand whose place is in parentheses.
+            let start = self.pos;
+            if let Ok(rvalue) = self.use_or_cast() {
+                if self.check(";") {
+                    return Ok(rvalue);
+                }
+            }
+            self.pos = start;
+            return Ok(Rvalue::Aggregate(box AggregateKind::Tuple, self.operands()?));
+        }
+
+        if self.check("const") || self.check("move") || self.check_local() {
+            return self.use_or_cast();
+        }
+
+        let start = self.pos;
+        let name = self.ident()?;
+        if self.check("(") {
+            match name {
+                "discriminant" | "Len" => {
+                    self.expect("(")?;
+                    let place = self.place()?;
+                    self.expect(")")?;
+                    return Ok(match name {
+                        "discriminant" => Rvalue::Discriminant(place),
+                        _ => Rvalue::Len(place),
+                    });
+                }
+                "Not" | "Neg" => {
+                    self.expect("(")?;
+                    let operand = self.operand()?;
+                    self.expect(")")?;
+                    let op = if name == "Not" { UnOp::Not } else { UnOp::Neg };
+                    return Ok(Rvalue::UnaryOp(op, operand));
+                }
+                "SizeOf" | "Box" => {
+                    self.expect("(")?;
+                    let ty = self.ty()?;
+                    self.expect(")")?;
+                    let op = if name == "SizeOf" { NullOp::SizeOf } else { NullOp::Box };
+                    return Ok(Rvalue::NullaryOp(op, ty));
+                }
+                _ => {}
+            }
+            let checked = name.starts_with("Checked");
+            let op = match name.trim_start_matches("Checked") {
+                "Add" => Some(BinOp::Add),
+                "Sub" => Some(BinOp::Sub),
+                "Mul" => Some(BinOp::Mul),
+                "Div" => Some(BinOp::Div),
+                "Rem" => Some(BinOp::Rem),
+                "BitXor" => Some(BinOp::BitXor),
+                "BitAnd" => Some(BinOp::BitAnd),
+                "BitOr" => Some(BinOp::BitOr),
+                "Shl" => Some(BinOp::Shl),
+                "Shr" => Some(BinOp::Shr),
+                "Eq" => Some(BinOp::Eq),
+                "Lt" => Some(BinOp::Lt),
+                "Le" => Some(BinOp::Le),
+                "Ne" => Some(BinOp::Ne),
+                "Ge" => Some(BinOp::Ge),
+                "Gt" => Some(BinOp::Gt),
+                "Offset" => Some(BinOp::Offset),
+                _ => None,
+            };
+            if let Some(op) = op {
+                let mut operands = self.operands()?;
+                if operands.len() != 2 {
+                    return Err(self.error(format!("expected two operands for `{}`", name)));
+                }
+                let rhs = operands.pop().unwrap();
+                let lhs = operands.pop().unwrap();
+                return Ok(if checked {
+                    Rvalue::CheckedBinaryOp(op, lhs, rhs)
+                } else {
+                    Rvalue::BinaryOp(op, lhs, rhs)
+                });
+            }
+        }
+        self.pos = start;
+        self.adt_aggregate(expected)
+    }
+
+    fn use_or_cast(&mut self) -> PResult<Rvalue<'tcx>> {
+        let operand = self.operand()?;
+        if !self.eat("as") {
+            return Ok(Rvalue::Use(operand));
+        }
+        let ty = self.ty()?;
+        self.expect("(")?;
+        let kind = if self.eat("Misc") {
+            CastKind::Misc
+        } else {
+            self.expect("Pointer")?;
+            self.expect("(")?;
+            let kind = match self.ident()? {
+                "ReifyFnPointer" => PointerCast::ReifyFnPointer,
+                "UnsafeFnPointer" => PointerCast::UnsafeFnPointer,
+                "MutToConstPointer" => PointerCast::MutToConstPointer,
+                "Unsize" => PointerCast::Unsize,
+                _ => return Err(self.error("unsupported pointer cast")),
+            };
+            self.expect(")")?;
+            CastKind::Pointer(kind)
+        };
+        self.expect(")")?;
+        Ok(Rvalue::Cast(kind, operand, ty))
+    }
+
+    /// Parses the construction of a value of the ADT `expected`, like `Foo::A(move _1,)` or
+    /// `Bar { x: const 1i32 }`.
+    fn adt_aggregate(&mut self, expected: Ty<'tcx>) -> PResult<Rvalue<'tcx>> {
+        let (adt, substs) = match expected.sty {
+            ty::Adt(adt, substs) => (adt, substs),
+            _ => return Err(self.error(format!("expected a value of type `{}`", expected))),
+        };
+        let (path, name) = self.value_path()?;
+        let variant_index = if adt.is_enum() {
+            adt.variants.iter_enumerated()
+                .find(|(_, variant)| variant.ident.as_str() == name)
+                .map(|(index, _)| index)
+                .ok_or_else(|| {
+                    self.error(format!("`{}` is not a variant of `{}`", path, expected))
+                })?
+        } else {
+            VariantIdx::new(0)
+        };
+        let variant = &adt.variants[variant_index];
+        let mut active_field = None;
+        let operands = match variant.ctor_kind {
+            CtorKind::Const => vec![],
+            CtorKind::Fn => self.operands()?,
+            CtorKind::Fictive => {
+                let mut operands: Vec<Option<Operand<'tcx>>> = vec![None; variant.fields.len()];
+                if self.eat("{") {
+                    while !self.eat("}") {
+                        if operands.iter().any(Option::is_some) {
+                            self.expect(",")?;
+                        }
+                        let name = self.ident()?;
+                        let index = variant.fields.iter().position(|f| f.ident.as_str() == name)
+                            .ok_or_else(|| self.error(format!("unknown field `{}`", name)))?;
+                        self.expect(":")?;
+                        operands[index] = Some(self.operand()?);
+                        active_field = Some(index);
+                    }
+                }
+                if adt.is_union() {
+                    operands.into_iter().filter_map(|operand| operand).collect()
+                } else {
+                    active_field = None;
+                    operands.into_iter().collect::<Option<_>>()
+                        .ok_or_else(|| self.error("missing fields"))?
+                }
+            }
+        };
+        Ok(Rvalue::Aggregate(box AggregateKind::Adt(adt, variant_index, substs, None, active_field),
+                             operands))
+    }
+
+    fn primitive_ty(&self, name: &str) -> Option<Ty<'tcx>> {
+        let types = &self.tcx.types;
+        Some(match name {
+            "bool" => types.bool,
+            "char" => types.char,
+            "str" => self.tcx.mk_str(),
+            "isize" => types.isize,
+            "i8" => types.i8,
+            "i16" => types.i16,
+            "i32" => types.i32,
+            "i64" => types.i64,
+            "i128" => types.i128,
+            "usize" => types.usize,
+            "u8" => types.u8,
+            "u16" => types.u16,
+            "u32" => types.u32,
+            "u64" => types.u64,
+            "u128" => types.u128,
+            "f32" => types.f32,
+            "f64" => types.f64,
+            _ => return None,
+        })
+    }
+
+    /// Parses types separated by commas up to `close`, skipping lifetimes.
+    fn ty_list(&mut self, close: &str) -> PResult<Vec<Ty<'tcx>>> {
+        let mut tys = vec![];
+        let mut first = true;
+        while !self.eat(close) {
+            if !first {
+                self.expect(",")?;
+                if self.eat(close) {
+                    break;
+                }
+            }
+            first = false;
+            if self.eat("'") {
+                self.ident()?;
+            } else {
+                tys.push(self.ty()?);
+            }
+        }
+        Ok(tys)
+    }
+
+    fn ty(&mut self) -> PResult<Ty<'tcx>> {
+        let tcx = self.tcx;
+        if self.eat("!") {
+            return Ok(tcx.types.never);
+        }
+        if self.eat("(") {
+            return Ok(tcx.intern_tup(&self.ty_list(")")?));
+        }
+        if self.eat("&") {
+            if self.eat("'") {
+                self.ident()?;
+            }
+            let mutbl = if self.eat("mut") { hir::MutMutable } else { hir::MutImmutable };
+            let ty = self.ty()?;
+            return Ok(tcx.mk_ref(tcx.lifetimes.re_erased, ty::TypeAndMut { ty, mutbl }));
+        }
+        if self.eat("*") {
+            let mutbl = if self.eat("mut") {
+                hir::MutMutable
+            } else {
+                self.expect("const")?;
+                hir::MutImmutable
+            };
+            let ty = self.ty()?;
+            return Ok(tcx.mk_ptr(ty::TypeAndMut { ty, mutbl }));
+        }
+        if self.eat("[") {
+            let ty = self.ty()?;
+            if self.eat("]") {
+                return Ok(tcx.mk_slice(ty));
+            }
+            self.expect(";")?;
+            let len = self.number()?;
+            self.eat("usize");
+            self.expect("]")?;
+            return Ok(tcx.mk_array(ty, len));
+        }
+        if self.eat("fn") {
+            let inputs = self.expect("(").and_then(|_| self.ty_list(")"))?;
+            let output = if self.eat("->") { self.ty()? } else { tcx.mk_unit() };
+            let sig = tcx.mk_fn_sig(inputs.into_iter(), output, false, hir::Unsafety::Normal,
+                                    Abi::Rust);
+            return Ok(tcx.mk_fn_ptr(ty::Binder::bind(sig)));
+        }
+
+        let path = self.path()?;
+        if let Some(ty) = self.primitive_ty(&path) {
+            return Ok(ty);
+        }
+        if let Some(&ty) = self.ty_params.get(&path) {
+            return Ok(ty);
+        }
+        let adt = match self.adts.get(&path) {
+            Some(&adt) => adt,
+            None => return Err(self.error(format!("unknown type `{}`", path))),
+        };
+        let tys = if self.eat("<") { self.ty_list(">")? } else { vec![] };
+        let substs = self.substs(adt.did, &tys)?;
+        Ok(tcx.mk_adt(adt, substs))
+    }
+}
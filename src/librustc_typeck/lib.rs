@@ -108,6 +108,7 @@ use rustc::ty::subst::SubstsRef;
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::ty::query::Providers;
 use rustc::util;
+use rustc_data_structures::sync::{ParallelIterator, par_iter};
 use syntax_pos::{DUMMY_SP, Span};
 use util::common::time;
 
@@ -305,9 +306,9 @@ pub fn check_crate(tcx: TyCtxt<'_>) -> Result<(), ErrorReported> {
     // FIXME(matthewjasper) We shouldn't need to do this.
     tcx.sess.track_errors(|| {
         time(tcx.sess, "type collecting", || {
-            for &module in tcx.hir().krate().modules.keys() {
+            par_iter(&tcx.hir().krate().modules).for_each(|(&module, _)| {
                 tcx.ensure().collect_mod_item_types(tcx.hir().local_def_id_from_node_id(module));
-            }
+            });
         });
     })?;
 
@@ -340,9 +341,9 @@ pub fn check_crate(tcx: TyCtxt<'_>) -> Result<(), ErrorReported> {
     })?;
 
     time(tcx.sess, "item-types checking", || {
-        for &module in tcx.hir().krate().modules.keys() {
+        par_iter(&tcx.hir().krate().modules).for_each(|(&module, _)| {
             tcx.ensure().check_mod_item_types(tcx.hir().local_def_id_from_node_id(module));
-        }
+        });
     });
 
     time(tcx.sess, "item-bodies checking", || tcx.typeck_item_bodies(LOCAL_CRATE));
@@ -7,6 +7,7 @@ use std::mem;
 use std::fmt::{self, Write};
 use std::ops;
 
+use rustc::util::nodemap::FxHashSet;
 use syntax::symbol::{Symbol, sym};
 use syntax::ast::{MetaItem, MetaItemKind, NestedMetaItem, LitKind};
 use syntax::parse::ParseSess;
@@ -120,6 +121,35 @@ impl Cfg {
         }
     }
 
+    /// Removes every sub-configuration listed in `hidden`, returning `None` if nothing is left.
+    ///
+    /// This is used to drop configuration options that are meaningless to readers of the
+    /// documentation (e.g., `rustdoc` or `test`) from the requirements derived from `#[cfg]`.
+    pub fn strip_hidden(&self, hidden: &FxHashSet<Cfg>) -> Option<Cfg> {
+        if hidden.contains(self) {
+            return None;
+        }
+        match *self {
+            Cfg::False | Cfg::True | Cfg::Cfg(..) => Some(self.clone()),
+            Cfg::Not(ref child) => child.strip_hidden(hidden).map(|child| !child),
+            Cfg::Any(ref sub_cfgs) | Cfg::All(ref sub_cfgs) => {
+                let mut sub_cfgs: Vec<Cfg> = sub_cfgs
+                    .iter()
+                    .filter_map(|sub_cfg| sub_cfg.strip_hidden(hidden))
+                    .collect();
+                match sub_cfgs.len() {
+                    0 => None,
+                    1 => sub_cfgs.pop(),
+                    _ => if let Cfg::Any(..) = *self {
+                        Some(Cfg::Any(sub_cfgs))
+                    } else {
+                        Some(Cfg::All(sub_cfgs))
+                    },
+                }
+            }
+        }
+    }
+
     /// Whether the configuration consists of just `Cfg` or `Not`.
     fn is_simple(&self) -> bool {
         match *self {
@@ -5,6 +5,7 @@ use syntax::ast::*;
 use syntax::attr;
 use syntax::symbol::Symbol;
 use syntax::with_default_globals;
+use rustc::util::nodemap::FxHashSet;
 
 fn word_cfg(s: &str) -> Cfg {
     Cfg::Cfg(Symbol::intern(s), None)
@@ -410,3 +411,45 @@ fn test_render_long_html() {
         );
     })
 }
+
+#[test]
+fn test_strip_hidden() {
+    with_default_globals(|| {
+        let hidden: FxHashSet<Cfg> = vec![
+            word_cfg("rustdoc"),
+            word_cfg("test"),
+            name_value_cfg("feature", "nightly"),
+        ].into_iter().collect();
+
+        assert_eq!(word_cfg("unix").strip_hidden(&hidden), Some(word_cfg("unix")));
+        assert_eq!(word_cfg("rustdoc").strip_hidden(&hidden), None);
+        assert_eq!((!word_cfg("test")).strip_hidden(&hidden), None);
+        assert_eq!(
+            (!word_cfg("windows")).strip_hidden(&hidden),
+            Some(!word_cfg("windows"))
+        );
+        assert_eq!(
+            (word_cfg("unix") | word_cfg("rustdoc")).strip_hidden(&hidden),
+            Some(word_cfg("unix"))
+        );
+        assert_eq!(
+            (word_cfg("rustdoc") | word_cfg("test")).strip_hidden(&hidden),
+            None
+        );
+        assert_eq!(
+            (
+                name_value_cfg("feature", "nightly") &
+                name_value_cfg("feature", "serde") &
+                word_cfg("unix")
+            ).strip_hidden(&hidden),
+            Some(name_value_cfg("feature", "serde") & word_cfg("unix"))
+        );
+        assert_eq!(
+            (
+                (word_cfg("unix") | word_cfg("rustdoc")) &
+                !(name_value_cfg("feature", "nightly") | word_cfg("windows"))
+            ).strip_hidden(&hidden),
+            Some(word_cfg("unix") & !word_cfg("windows"))
+        );
+    })
+}
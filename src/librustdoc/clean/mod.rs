@@ -47,6 +47,7 @@ use std::u32;
 
 use crate::core::{self, DocContext};
 use crate::doctree;
+use crate::guide::Chapter;
 use crate::html::render::{cache, ExternalLocation};
 use crate::html::item_type::ItemType;
 
@@ -134,6 +135,7 @@ pub struct Crate {
     pub external_traits: Rc<RefCell<FxHashMap<DefId, Trait>>>,
     pub masked_crates: FxHashSet<CrateNum>,
     pub collapsed: bool,
+    pub guide: Vec<Chapter>,
 }
 
 impl Clean<Crate> for hir::Crate {
@@ -223,6 +225,7 @@ impl Clean<Crate> for hir::Crate {
             external_traits: cx.external_traits.clone(),
             masked_crates,
             collapsed: false,
+            guide: Vec::new(),
         }
     }
 }
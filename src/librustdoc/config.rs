@@ -16,6 +16,7 @@ use syntax::edition::{Edition, DEFAULT_EDITION};
 
 use crate::core::new_handler;
 use crate::externalfiles::ExternalHtml;
+use crate::guide;
 use crate::html;
 use crate::html::{static_files};
 use crate::html::markdown::{IdMap};
@@ -87,6 +88,12 @@ pub struct Options {
     /// Whether to run the `calculate-doc-coverage` pass, which counts the number of public items
     /// with and without documentation.
     pub show_coverage: bool,
+    /// Whether to only run the documentation lints and check the links between the generated
+    /// pages, without writing any output.
+    pub check: bool,
+    /// The chapters of the guide given with `--guide`, rendered next to the API documentation
+    /// and tested along with the crate's doctests.
+    pub guide: Vec<guide::Chapter>,
 
     // Options that alter generated documentation pages
 
@@ -131,6 +138,8 @@ impl fmt::Debug for Options {
             .field("manual_passes", &self.manual_passes)
             .field("display_warnings", &self.display_warnings)
             .field("show_coverage", &self.show_coverage)
+            .field("check", &self.check)
+            .field("guide", &self.guide)
             .field("crate_version", &self.crate_version)
             .field("render_options", &self.render_options)
             .finish()
@@ -407,6 +416,18 @@ impl Options {
             }
         }
 
+        let guide = match matches.opt_str("guide") {
+            Some(dir) => {
+                let dir = PathBuf::from(dir);
+                if !dir.is_dir() {
+                    diag.struct_err("option `--guide` argument must be a directory").emit();
+                    return Err(1);
+                }
+                guide::load(&dir, &diag).map_err(|()| 1)?
+            }
+            None => Vec::new(),
+        };
+
         let target = matches.opt_str("target").map(|target| {
             if target.ends_with(".json") {
                 TargetTriple::TargetPath(PathBuf::from(target))
@@ -448,6 +469,7 @@ impl Options {
         let generate_search_filter = !matches.opt_present("disable-per-crate-search");
         let persist_doctests = matches.opt_str("persist-doctests").map(PathBuf::from);
         let generate_redirect_pages = matches.opt_present("generate-redirect-pages");
+        let check = matches.opt_present("check");
 
         let (lint_opts, describe_lints, lint_cap) = get_cmd_lint_options(matches, error_format);
 
@@ -473,6 +495,8 @@ impl Options {
             manual_passes,
             display_warnings,
             show_coverage,
+            check,
+            guide,
             crate_version,
             persist_doctests,
             render_options: RenderOptions {
@@ -33,6 +33,7 @@ use crate::clean;
 use crate::clean::{Clean, MAX_DEF_ID, AttributesExt};
 use crate::html::render::RenderInfo;
 
+use crate::linkcheck;
 use crate::passes;
 
 pub use rustc::session::config::{Input, Options, CodegenOptions};
@@ -243,6 +244,8 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
         mut default_passes,
         mut manual_passes,
         display_warnings,
+        check,
+        guide,
         render_options,
         ..
     } = options;
@@ -251,6 +254,7 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
     let input = Input::File(input);
 
     let intra_link_resolution_failure_name = lint::builtin::INTRA_DOC_LINK_RESOLUTION_FAILURE.name;
+    let broken_doc_links = lint::builtin::BROKEN_DOC_LINKS.name;
     let warnings_lint_name = lint::builtin::WARNINGS.name;
     let missing_docs = rustc_lint::builtin::MISSING_DOCS.name;
     let missing_doc_example = rustc_lint::builtin::MISSING_DOC_CODE_EXAMPLES.name;
@@ -260,6 +264,7 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
     // command line, otherwise they'll get ignored and we don't want that.
     let mut whitelisted_lints = vec![warnings_lint_name.to_owned(),
                                      intra_link_resolution_failure_name.to_owned(),
+                                     broken_doc_links.to_owned(),
                                      missing_docs.to_owned(),
                                      missing_doc_example.to_owned(),
                                      private_doc_tests.to_owned()];
@@ -275,7 +280,8 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
 
     let lint_opts = lints().filter_map(|lint| {
         if lint.name == warnings_lint_name ||
-            lint.name == intra_link_resolution_failure_name {
+            lint.name == intra_link_resolution_failure_name ||
+            lint.name == broken_doc_links {
             None
         } else {
             Some((lint.name_lower(), lint::Allow))
@@ -384,6 +390,7 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
             debug!("crate: {:?}", tcx.hir().krate());
 
             let mut krate = tcx.hir().krate().clean(&ctxt);
+            krate.guide = guide;
 
             fn report_deprecated_attr(name: &str, diag: &errors::Handler) {
                 let mut msg = diag.struct_warn(&format!("the `#![doc({})]` attribute is \
@@ -453,6 +460,10 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
                 krate = (pass.pass)(krate, &ctxt);
             }
 
+            if check {
+                krate = linkcheck::check_links(krate, &ctxt, &render_options, edition);
+            }
+
             ctxt.sess().abort_if_errors();
 
             (krate, ctxt.renderinfo.into_inner(), render_options)
@@ -8,12 +8,17 @@
 //! fs::read is still done directly via the fs module; if in future rustdoc
 //! needs to read-after-write from a file, then it would be added to this
 //! abstraction.
+//!
+//! It can also keep everything in memory instead of writing it out, which is
+//! used by `--check` to look at the generated pages without touching the disk.
 
 use errors;
+use rustc_data_structures::fx::FxHashMap;
 
+use std::cell::RefCell;
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::mpsc::{channel, Receiver, Sender};
 
@@ -63,6 +68,8 @@ impl ErrorStorage {
 pub struct DocFS {
     sync_only: bool,
     errors: Arc<ErrorStorage>,
+    /// When set, nothing is written to disk: the contents of every file are kept here instead.
+    in_memory: Option<RefCell<FxHashMap<PathBuf, Vec<u8>>>>,
 }
 
 impl DocFS {
@@ -70,6 +77,28 @@ impl DocFS {
         DocFS {
             sync_only: false,
             errors: Arc::clone(errors),
+            in_memory: None,
+        }
+    }
+
+    /// Creates a `DocFS` which keeps all the written files in memory, to be retrieved with
+    /// `take_files`.
+    pub fn in_memory(errors: &Arc<ErrorStorage>) -> DocFS {
+        DocFS {
+            in_memory: Some(RefCell::new(FxHashMap::default())),
+            ..DocFS::new(errors)
+        }
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory.is_some()
+    }
+
+    /// Returns the files written so far by an in-memory `DocFS`, keyed by their path.
+    pub fn take_files(&self) -> FxHashMap<PathBuf, Vec<u8>> {
+        match self.in_memory {
+            Some(ref files) => files.replace(FxHashMap::default()),
+            None => FxHashMap::default(),
         }
     }
 
@@ -78,6 +107,9 @@ impl DocFS {
     }
 
     pub fn create_dir_all<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if self.in_memory.is_some() {
+            return Ok(());
+        }
         // For now, dir creation isn't a huge time consideration, do it
         // synchronously, which avoids needing ordering between write() actions
         // and directory creation.
@@ -90,7 +122,10 @@ impl DocFS {
         C: AsRef<[u8]>,
         E: PathError,
     {
-        if !self.sync_only && cfg!(windows) {
+        if let Some(ref files) = self.in_memory {
+            files.borrow_mut().insert(path.as_ref().to_path_buf(), contents.as_ref().to_vec());
+            Ok(())
+        } else if !self.sync_only && cfg!(windows) {
             // A possible future enhancement after more detailed profiling would
             // be to create the file sync so errors are reported eagerly.
             let contents = contents.as_ref().to_vec();
@@ -0,0 +1,118 @@
+//! Loading of the chapters given with `--guide`.
+//!
+//! A guide is a directory of Markdown files along with a `SUMMARY.md` file listing the chapters,
+//! in order, as links:
+//!
+//! ```markdown
+//! # Guide
+//!
+//! - [Getting started](intro.md)
+//! - [Advanced usage](advanced.md)
+//! ```
+//!
+//! Each chapter is rendered to `<crate>/guide/<chapter>.html`. Chapters are documentation like any
+//! other: intra-doc links are resolved from the crate root, and code blocks are run as doctests.
+
+use std::path::{Path, PathBuf};
+
+use errors;
+use rustc::util::nodemap::FxHashSet;
+use syntax_pos::DUMMY_SP;
+
+use crate::clean::{Attributes, DocFragment};
+use crate::externalfiles::load_string;
+
+#[cfg(test)]
+mod tests;
+
+/// The name of the file listing the chapters of a guide.
+const SUMMARY: &str = "SUMMARY.md";
+
+#[derive(Clone, Debug)]
+pub struct Chapter {
+    /// The title given to the chapter in `SUMMARY.md`.
+    pub title: String,
+    /// The name of the generated page, without its extension.
+    pub name: String,
+    /// The chapter's file, as written in `SUMMARY.md`.
+    pub file: String,
+    /// The path to the chapter's file.
+    pub path: PathBuf,
+    /// The contents of the chapter, along with the intra-doc links resolved in it.
+    pub attrs: Attributes,
+}
+
+/// Loads the chapters listed in the `SUMMARY.md` file of `dir`.
+pub fn load(dir: &Path, diag: &errors::Handler) -> Result<Vec<Chapter>, ()> {
+    let summary = dir.join(SUMMARY);
+    let summary_contents = load_string(&summary, diag).map_err(|_| ())?;
+
+    let mut chapters = Vec::new();
+    let mut names = FxHashSet::default();
+    for (i, line) in summary_contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (title, file) = match parse_summary_line(line) {
+            Some(chapter) => chapter,
+            None => {
+                diag.struct_err(&format!("{}:{}: expected a link to a chapter",
+                                         summary.display(), i + 1))
+                    .help("chapters are listed as `- [Title](chapter.md)`")
+                    .emit();
+                return Err(());
+            }
+        };
+
+        let path = dir.join(file);
+        let name = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(name) if names.insert(name.to_string()) => name.to_string(),
+            _ => {
+                diag.struct_err(&format!("{}:{}: chapter `{}` is listed more than once",
+                                         summary.display(), i + 1, file))
+                    .note("the chapters of a guide must have distinct file names")
+                    .emit();
+                return Err(());
+            }
+        };
+
+        let contents = load_string(&path, diag).map_err(|_| ())?;
+        let mut attrs = Attributes::default();
+        attrs.doc_strings.push(DocFragment::Include(0, DUMMY_SP, path.display().to_string(),
+                                                    contents));
+
+        chapters.push(Chapter {
+            title: title.to_string(),
+            name,
+            file: file.to_string(),
+            path,
+            attrs,
+        });
+    }
+
+    if chapters.is_empty() {
+        diag.struct_err(&format!("`{}` doesn't list any chapter", summary.display())).emit();
+        return Err(());
+    }
+
+    Ok(chapters)
+}
+
+/// Parses a line of the form `- [Title](file.md)`. The list marker is optional. A line with
+/// anything after the link, such as a second link, is rejected.
+fn parse_summary_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start_matches(|c| c == '-' || c == '*').trim_start();
+    if !line.starts_with('[') || !line.ends_with(')') {
+        return None;
+    }
+    let (title, file) = line[1..line.len() - 1].split_at(line.find("](")? - 1);
+    let (title, file) = (title.trim(), file[2..].trim());
+    // The link must end at the end of the line.
+    if title.is_empty() || file.is_empty() || file.contains(')') {
+        None
+    } else {
+        Some((title, file))
+    }
+}
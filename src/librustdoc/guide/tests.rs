@@ -0,0 +1,21 @@
+use super::parse_summary_line;
+
+#[test]
+fn test_parse_summary_line() {
+    assert_eq!(parse_summary_line("- [Getting started](intro.md)"),
+               Some(("Getting started", "intro.md")));
+    assert_eq!(parse_summary_line("* [Usage]( usage.md )"), Some(("Usage", "usage.md")));
+    assert_eq!(parse_summary_line("[Foreword](foreword.md)"), Some(("Foreword", "foreword.md")));
+    assert_eq!(parse_summary_line("- [With [brackets]](a.md)"), Some(("With [brackets]", "a.md")));
+}
+
+#[test]
+fn test_parse_summary_line_invalid() {
+    assert_eq!(parse_summary_line("- Getting started"), None);
+    assert_eq!(parse_summary_line("- [Getting started]"), None);
+    assert_eq!(parse_summary_line("- [](intro.md)"), None);
+    assert_eq!(parse_summary_line("- [Getting started]()"), None);
+    assert_eq!(parse_summary_line("- [Getting started](intro.md) and more"), None);
+    assert_eq!(parse_summary_line("- [A](a.md) [B](b.md)"), None);
+    assert_eq!(parse_summary_line("- [A](a.md) (b.md)"), None);
+}
@@ -64,6 +64,7 @@ use crate::config::RenderOptions;
 use crate::docfs::{DocFS, ErrorStorage, PathError};
 use crate::doctree;
 use crate::fold::DocFolder;
+use crate::guide::Chapter;
 use crate::html::escape::Escape;
 use crate::html::format::{AsyncSpace, ConstnessSpace};
 use crate::html::format::{GenericBounds, WhereClause, href, AbiSpace, DefaultSpace};
@@ -215,6 +216,8 @@ struct SharedContext {
     pub generate_redirect_pages: bool,
     /// The fs handle we are working with.
     pub fs: DocFS,
+    /// The page names and titles of the guide's chapters, in order.
+    pub guide: Vec<(String, String)>,
 }
 
 impl SharedContext {
@@ -520,11 +523,31 @@ pub fn initial_ids() -> Vec<String> {
 }
 
 /// Generates the documentation for `crate` into the directory `dst`
-pub fn run(mut krate: clean::Crate,
+pub fn run(krate: clean::Crate,
            options: RenderOptions,
            renderinfo: RenderInfo,
            diag: &errors::Handler,
            edition: Edition) -> Result<(), Error> {
+    render(krate, options, renderinfo, diag, edition, DocFS::new).map(|_| ())
+}
+
+/// Generates the documentation for `crate` without writing anything to disk, and returns the
+/// contents of every file that would have been written, keyed by its path.
+pub fn run_in_memory(krate: clean::Crate,
+                     options: RenderOptions,
+                     renderinfo: RenderInfo,
+                     diag: &errors::Handler,
+                     edition: Edition) -> Result<FxHashMap<PathBuf, Vec<u8>>, Error> {
+    render(krate, options, renderinfo, diag, edition, DocFS::in_memory)
+}
+
+fn render(mut krate: clean::Crate,
+          options: RenderOptions,
+          renderinfo: RenderInfo,
+          diag: &errors::Handler,
+          edition: Edition,
+          new_fs: fn(&Arc<ErrorStorage>) -> DocFS)
+          -> Result<FxHashMap<PathBuf, Vec<u8>>, Error> {
     // need to save a copy of the options for rendering the index page
     let md_opts = options.clone();
     let RenderOptions {
@@ -571,7 +594,8 @@ pub fn run(mut krate: clean::Crate,
         static_root_path,
         generate_search_filter,
         generate_redirect_pages,
-        fs: DocFS::new(&errors),
+        fs: new_fs(&errors),
+        guide: krate.guide.iter().map(|c| (c.name.clone(), c.title.clone())).collect(),
     };
 
     // If user passed in `--playground-url` arg, we fill in crate name here
@@ -722,14 +746,18 @@ pub fn run(mut krate: clean::Crate,
     Arc::get_mut(&mut cx.shared).unwrap().fs.set_sync_only(false);
 
     // And finally render the whole crate's documentation
+    let shared = cx.shared.clone();
     let ret = cx.krate(krate);
+    let files = shared.fs.take_files();
+    // The `DocFS` has to be dropped for the remaining errors to be collected.
+    drop(shared);
     let nb_errors = Arc::get_mut(&mut errors).map_or_else(|| 0, |errors| errors.write_errors(diag));
-    if ret.is_err() {
-        ret
+    if let Err(e) = ret {
+        Err(e)
     } else if nb_errors > 0 {
         Err(Error::new(io::Error::new(io::ErrorKind::Other, "I/O error"), ""))
     } else {
-        Ok(())
+        Ok(files)
     }
 }
 
@@ -814,7 +842,11 @@ fn write_shared(
     // Write out the shared files. Note that these are shared among all rustdoc
     // docs placed in the output directory, so this needs to be a synchronized
     // operation with respect to all other rustdocs running around.
-    let _lock = flock::Lock::panicking_new(&cx.dst.join(".lock"), true, true, true);
+    let _lock = if cx.shared.fs.is_in_memory() {
+        None
+    } else {
+        Some(flock::Lock::panicking_new(&cx.dst.join(".lock"), true, true, true))
+    };
 
     // Add all the static files. These may already exist, but we just
     // overwrite them anyway to make sure that they're fresh and up-to-date.
@@ -1994,6 +2026,58 @@ impl fmt::Display for AllTypes {
     }
 }
 
+/// A chapter of the guide, rendered with links to the previous and next chapters.
+struct GuideChapter<'a> {
+    cx: &'a Context,
+    chapters: &'a [Chapter],
+    index: usize,
+    krate: CrateNum,
+}
+
+impl<'a> fmt::Display for GuideChapter<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chapter = &self.chapters[self.index];
+        write!(f, "<h1 class='fqn'><span class='in-band'>{}</span></h1>",
+               Escape(&chapter.title))?;
+
+        let mut links = chapter.attrs.links(&self.krate);
+        // Chapters link to each other with the paths of their Markdown files.
+        links.extend(self.chapters.iter().map(|c| (c.file.clone(), format!("{}.html", c.name))));
+        let dox = chapter.attrs.collapsed_doc_value().unwrap_or_default();
+        render_markdown(f, self.cx, &dox, links, "", false)?;
+
+        write!(f, "<nav class='guide-nav'>")?;
+        if let Some(prev) = self.index.checked_sub(1).map(|i| &self.chapters[i]) {
+            write!(f, "<a class='prev' href='{}.html'>&#x2190; {}</a>",
+                   prev.name, Escape(&prev.title))?;
+        }
+        if let Some(next) = self.chapters.get(self.index + 1) {
+            write!(f, "<a class='next' href='{}.html'>{} &#x2192;</a>",
+                   next.name, Escape(&next.title))?;
+        }
+        write!(f, "</nav>")
+    }
+}
+
+/// Renders the list of the guide's chapters for the sidebar, `prefix` being the path from the
+/// current page to the guide's directory.
+fn guide_sidebar_block(chapters: &[(String, String)], prefix: &str,
+                       current: Option<&str>) -> String {
+    if chapters.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("<div class='block guide'><h3>Guide</h3><ul>");
+    for (name, title) in chapters {
+        out.push_str(&format!("<li><a href='{}{}.html'{}>{}</a></li>",
+                              prefix, name,
+                              if current == Some(&name[..]) { " class='current'" } else { "" },
+                              Escape(title)));
+    }
+    out.push_str("</ul></div>");
+    out
+}
+
 #[derive(Debug)]
 struct Settings<'a> {
     // (id, explanation, default value)
@@ -2096,6 +2180,7 @@ impl Context {
         let settings_file = self.dst.join("settings.html");
 
         let crate_name = krate.name.clone();
+        let crate_num = item.def_id.krate;
         item.name = Some(krate.name);
 
         let mut all = AllTypes::new();
@@ -2145,6 +2230,63 @@ impl Context {
                  &final_file);
         self.shared.fs.write(&final_file, &v)?;
 
+        // Render the chapters of the guide.
+        if !krate.guide.is_empty() {
+            let guide_dir = self.dst.join(&crate_name).join("guide");
+            self.shared.ensure_dir(&guide_dir)?;
+            CURRENT_LOCATION_KEY.with(|slot| {
+                *slot.borrow_mut() = vec![crate_name.clone(), "guide".to_string()];
+            });
+
+            for (index, chapter) in krate.guide.iter().enumerate() {
+                let title = format!("{} - {}", chapter.title, crate_name);
+                let desc = format!("{} - Guide of the Rust `{}` crate.", chapter.title,
+                                   crate_name);
+                let page = layout::Page {
+                    title: &title,
+                    css_class: "guide",
+                    root_path: "../../",
+                    static_root_path: self.shared.static_root_path.as_deref(),
+                    description: &desc,
+                    keywords: BASIC_KEYWORDS,
+                    resource_suffix: &self.shared.resource_suffix,
+                    extra_scripts: &[],
+                    static_extra_scripts: &[],
+                };
+                let sidebar = format!("<p class='location'>Crate {}</p>\
+                                       <div class='sidebar-elems'>\
+                                           <a id='all-types' href='../index.html'>\
+                                               <p>Back to the crate</p>\
+                                           </a>\
+                                           {}\
+                                       </div>",
+                                      crate_name,
+                                      guide_sidebar_block(&self.shared.guide, "",
+                                                          Some(&chapter.name)));
+
+                {
+                    self.id_map.borrow_mut().reset();
+                    self.id_map.borrow_mut().populate(initial_ids());
+                }
+
+                let chapter_file = guide_dir.join(format!("{}.html", chapter.name));
+                let mut v = Vec::new();
+                try_err!(layout::render(&mut v, &self.shared.layout,
+                                        &page, &sidebar,
+                                        &GuideChapter {
+                                            cx: &self,
+                                            chapters: &krate.guide,
+                                            index,
+                                            krate: crate_num,
+                                        },
+                                        self.shared.css_file_extension.is_some(),
+                                        &self.shared.themes,
+                                        self.shared.generate_search_filter),
+                         &chapter_file);
+                self.shared.fs.write(&chapter_file, &v)?;
+            }
+        }
+
         // Generating settings page.
         let settings = Settings::new(self.shared.static_root_path.as_deref().unwrap_or("./"),
                                      &self.shared.resource_suffix);
@@ -2535,7 +2677,7 @@ impl<'a> fmt::Display for Item<'a> {
     }
 }
 
-fn item_path(ty: ItemType, name: &str) -> String {
+crate fn item_path(ty: ItemType, name: &str) -> String {
     match ty {
         ItemType::Module => format!("{}index.html", SlashChecker(name)),
         _ => format!("{}.{}.html", ty.css_class(), name),
@@ -4519,6 +4661,7 @@ impl<'a> fmt::Display for Sidebar<'a> {
         if it.is_crate() {
             write!(fmt, "<a id='all-types' href='all.html'><p>See all {}'s items</p></a>",
                    it.name.as_ref().expect("crates always have a name"))?;
+            write!(fmt, "{}", guide_sidebar_block(&cx.shared.guide, "guide/", None))?;
         }
         match it.inner {
             clean::StructItem(ref s) => sidebar_struct(fmt, it, s)?,
@@ -57,6 +57,7 @@ mod core;
 mod docfs;
 mod doctree;
 mod fold;
+mod guide;
 pub mod html {
     crate mod highlight;
     crate mod escape;
@@ -68,6 +69,7 @@ pub mod html {
     crate mod static_files;
     crate mod toc;
 }
+mod linkcheck;
 mod markdown;
 mod passes;
 mod visit_ast;
@@ -356,6 +358,19 @@ fn opts() -> Vec<RustcOptGroup> {
                       "show-coverage",
                       "calculate percentage of public items with documentation")
         }),
+        unstable("check", |o| {
+            o.optflag("",
+                      "check",
+                      "run the documentation lints and check the links between generated pages, \
+                       without writing any output")
+        }),
+        unstable("guide", |o| {
+            o.optopt("",
+                     "guide",
+                     "directory of Markdown chapters to render as a guide, listed in its \
+                      SUMMARY.md file",
+                     "PATH")
+        }),
     ]
 }
 
@@ -410,12 +425,17 @@ fn main_options(options: config::Options) -> i32 {
                      options.debugging_options.ui_testing,
                      options.edition);
     let show_coverage = options.show_coverage;
+    let check = options.check;
     rust_input(options, move |out| {
         if show_coverage {
             // if we ran coverage, bail early, we don't need to also generate docs at this point
             // (also we didn't load in any of the useful passes)
             return rustc_driver::EXIT_SUCCESS;
         }
+        if check {
+            // the links have been checked against an in-memory rendering, nothing to write
+            return rustc_driver::EXIT_SUCCESS;
+        }
 
         let Output { krate, renderinfo, renderopts } = out;
         info!("going to format");
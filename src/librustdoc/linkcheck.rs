@@ -0,0 +1,308 @@
+//! Validation of the relative links and anchors written in documentation, used by `--check`.
+//!
+//! The crate is rendered in memory, and every link to another page or to an `#anchor` found in
+//! the Markdown of the documented items is looked up in the set of generated pages. Pages which
+//! were not generated by this run (e.g., the documentation of a dependency) are looked up in the
+//! output directory instead. Problems are reported through the `broken_doc_links` lint.
+
+use std::cell::RefCell;
+use std::fs;
+use std::mem;
+use std::path::{Component, Path, PathBuf};
+
+use rustc::hir::def_id::DefId;
+use rustc::lint;
+use rustc::util::nodemap::{FxHashMap, FxHashSet};
+use syntax::edition::Edition;
+
+use crate::clean::{self, GetDefId, Item};
+use crate::config::RenderOptions;
+use crate::core::DocContext;
+use crate::fold::DocFolder;
+use crate::html::markdown::markdown_links;
+use crate::html::render::{self, cache, item_path};
+use crate::passes::{source_span_for_markdown_range, span_of_attrs};
+
+/// Renders `krate` without writing anything to disk, and reports the links in its documentation
+/// that point to a page or an anchor which doesn't exist.
+pub fn check_links(
+    krate: clean::Crate,
+    cx: &DocContext<'_>,
+    options: &RenderOptions,
+    edition: Edition,
+) -> clean::Crate {
+    let renderinfo = mem::take(&mut *cx.renderinfo.borrow_mut());
+    let diag = cx.sess().diagnostic();
+    let files = match render::run_in_memory(krate.clone(), options.clone(), renderinfo, diag,
+                                            edition) {
+        Ok(files) => files,
+        Err(e) => {
+            diag.struct_err(&format!("couldn't generate documentation: {}", e.error))
+                .note(&format!("failed to create or modify \"{}\"", e.file.display()))
+                .emit();
+            return krate;
+        }
+    };
+
+    let mut checker = LinkChecker {
+        cx,
+        pages: Pages::new(&options.output, files),
+        current_dir: PathBuf::from(&krate.name),
+        current_page: None,
+        parent_is_mod: false,
+        in_stripped: false,
+    };
+    checker.fold_crate(krate)
+}
+
+/// Why a link could not be followed.
+enum BrokenLink {
+    /// No page exists at the given path.
+    Page,
+    /// The page exists, but has no element with the given `id`.
+    Anchor,
+}
+
+/// The set of pages a link can point to, relative to the output directory.
+struct Pages {
+    root: PathBuf,
+    /// The pages generated by this run, along with the `id`s defined by the HTML ones.
+    generated: FxHashMap<PathBuf, Option<FxHashSet<String>>>,
+    /// Pages looked up in the output directory, or `None` if they don't exist there either.
+    on_disk: RefCell<FxHashMap<PathBuf, Option<Option<FxHashSet<String>>>>>,
+}
+
+impl Pages {
+    fn new(root: &Path, files: FxHashMap<PathBuf, Vec<u8>>) -> Pages {
+        let generated = files.into_iter().filter_map(|(path, contents)| {
+            let path = path.strip_prefix(root).ok()?.to_path_buf();
+            let ids = page_ids(&path, &contents);
+            Some((path, ids))
+        }).collect();
+
+        Pages {
+            root: root.to_path_buf(),
+            generated,
+            on_disk: Default::default(),
+        }
+    }
+
+    /// Checks that `page` exists and, if given, that it defines the anchor `fragment`.
+    fn check(&self, page: &Path, fragment: Option<&str>) -> Result<(), BrokenLink> {
+        let check_ids = |ids: &Option<FxHashSet<String>>| {
+            match (fragment, ids) {
+                (Some(fragment), Some(ids)) if !is_line_anchor(fragment) => {
+                    if ids.contains(fragment) { Ok(()) } else { Err(BrokenLink::Anchor) }
+                }
+                _ => Ok(()),
+            }
+        };
+
+        if let Some(ids) = self.generated.get(page) {
+            return check_ids(ids);
+        }
+
+        let mut on_disk = self.on_disk.borrow_mut();
+        let ids = on_disk.entry(page.to_path_buf()).or_insert_with(|| {
+            fs::read(self.root.join(page)).ok().map(|contents| page_ids(page, &contents))
+        });
+        match *ids {
+            Some(ref ids) => check_ids(ids),
+            None => Err(BrokenLink::Page),
+        }
+    }
+}
+
+/// Collects the `id` (and `name`) attributes defined by `page` if it is an HTML page.
+fn page_ids(page: &Path, contents: &[u8]) -> Option<FxHashSet<String>> {
+    if page.extension().map_or(true, |ext| ext != "html") {
+        return None;
+    }
+
+    let html = String::from_utf8_lossy(contents);
+    let mut ids = FxHashSet::default();
+    for attr in &[" id=", " name="] {
+        let mut rest = &html[..];
+        while let Some(pos) = rest.find(attr) {
+            rest = &rest[pos + attr.len()..];
+            let quote = match rest.chars().next() {
+                Some(quote @ '"') | Some(quote @ '\'') => quote,
+                _ => continue,
+            };
+            rest = &rest[1..];
+            if let Some(end) = rest.find(quote) {
+                ids.insert(rest[..end].to_string());
+                rest = &rest[end + 1..];
+            }
+        }
+    }
+    Some(ids)
+}
+
+/// Line anchors of source pages, like `#10-20`, are handled by JavaScript.
+fn is_line_anchor(fragment: &str) -> bool {
+    fragment.chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+/// Joins `link` to `dir`, resolving `.` and `..` components. Returns `None` for links which
+/// leave the output directory or aren't relative.
+fn resolve_relative(dir: &Path, link: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in dir.join(link).components() {
+        match component {
+            Component::Normal(c) => path.push(c),
+            Component::CurDir => {}
+            Component::ParentDir => if !path.pop() {
+                return None;
+            },
+            Component::RootDir | Component::Prefix(..) => return None,
+        }
+    }
+    if link.ends_with('/') {
+        path.push("index.html");
+    }
+    Some(path)
+}
+
+/// Returns the page documenting the item `did`, relative to the output directory.
+fn page_of(did: DefId) -> Option<PathBuf> {
+    let cache = cache();
+    let &(ref fqp, ty) = cache.paths.get(&did)?;
+    let (name, dirs) = fqp.split_last()?;
+    let mut page: PathBuf = dirs.iter().collect();
+    page.push(item_path(ty, name));
+    Some(page)
+}
+
+struct LinkChecker<'a, 'tcx> {
+    cx: &'a DocContext<'tcx>,
+    pages: Pages,
+    /// The directory of the module being visited, relative to the output directory.
+    current_dir: PathBuf,
+    /// The page where the documentation of the item being visited is rendered, if known.
+    current_page: Option<PathBuf>,
+    parent_is_mod: bool,
+    /// Whether the item being visited is inside a stripped module, whose documentation is not
+    /// rendered where it is defined.
+    in_stripped: bool,
+}
+
+impl<'a, 'tcx> LinkChecker<'a, 'tcx> {
+    fn check_item(&self, item: &Item) {
+        let hir_id = match self.cx.as_local_hir_id(item.def_id) {
+            Some(hir_id) => hir_id,
+            // If non-local, no need to check anything.
+            None => return,
+        };
+        let dox = match item.attrs.collapsed_doc_value() {
+            Some(dox) => dox,
+            None => return,
+        };
+        let dir = match self.current_page {
+            Some(ref page) => page.parent().unwrap_or(&self.current_dir).to_path_buf(),
+            None => self.current_dir.clone(),
+        };
+
+        for (link, link_range) in markdown_links(&dox) {
+            // Resolved intra-doc links are rewritten by the renderer.
+            if item.attrs.links.iter().any(|l| l.0 == link) {
+                continue;
+            }
+            // Links to other sites can't be checked.
+            if link.starts_with('/') || link.contains("://") || link.starts_with("mailto:") {
+                continue;
+            }
+
+            let (path, fragment) = match link.find('#') {
+                Some(pos) => (&link[..pos], Some(&link[pos + 1..])),
+                None => (&link[..], None),
+            };
+            let path = path.split('?').next().unwrap_or(path);
+
+            let page = if path.is_empty() {
+                match self.current_page {
+                    Some(ref page) => page.clone(),
+                    None => continue,
+                }
+            } else if path.contains('/') || path.ends_with(".html") {
+                match resolve_relative(&dir, path) {
+                    Some(page) => page,
+                    None => continue,
+                }
+            } else {
+                // Anything else is an intra-doc link, reported by
+                // `intra_doc_link_resolution_failure` if it doesn't resolve.
+                continue;
+            };
+
+            let label = match self.pages.check(&page, fragment) {
+                Ok(()) => continue,
+                Err(BrokenLink::Page) => "this page does not exist".to_string(),
+                Err(BrokenLink::Anchor) => {
+                    format!("`{}` has no item or section with this anchor", page.display())
+                }
+            };
+
+            let sp = span_of_attrs(&item.attrs).substitute_dummy(item.source.span());
+            let mut diag = self.cx.tcx.struct_span_lint_hir(
+                lint::builtin::BROKEN_DOC_LINKS,
+                hir_id,
+                sp,
+                &format!("unresolved link to `{}`", link),
+            );
+            match link_range.and_then(|range| {
+                source_span_for_markdown_range(self.cx, &dox, &range, &item.attrs)
+            }) {
+                Some(sp) => {
+                    diag.set_span(sp);
+                    diag.span_label(sp, label);
+                }
+                None => {
+                    diag.note(&label);
+                }
+            }
+            diag.emit();
+        }
+    }
+}
+
+impl<'a, 'tcx> DocFolder for LinkChecker<'a, 'tcx> {
+    fn fold_item(&mut self, item: Item) -> Option<Item> {
+        let old_dir = self.current_dir.clone();
+        let old_page = self.current_page.clone();
+        let old_parent_is_mod = self.parent_is_mod;
+        let old_in_stripped = self.in_stripped;
+
+        if item.is_mod() {
+            // The crate root has an empty name, its pages are in the crate's directory.
+            if let Some(name) = item.name.as_ref().filter(|name| !name.is_empty()) {
+                self.current_dir.push(name);
+            }
+            self.current_page = Some(self.current_dir.join("index.html"));
+            self.in_stripped |= item.is_stripped();
+        } else if let clean::ImplItem(ref imp) = item.inner {
+            // The items of an impl are documented on the page of the implementing type.
+            self.current_page = imp.for_.def_id().and_then(page_of);
+        } else if self.parent_is_mod {
+            self.current_page = item.name.as_ref().map(|name| {
+                self.current_dir.join(item_path(item.type_(), name))
+            });
+        }
+
+        match item.inner {
+            // These are not rendered with their own documentation.
+            clean::ExternCrateItem(..) | clean::ImportItem(..) => {}
+            _ if self.in_stripped || item.is_stripped() => {}
+            _ => self.check_item(&item),
+        }
+
+        self.parent_is_mod = item.is_mod();
+        let ret = self.fold_item_recur(item);
+
+        self.current_dir = old_dir;
+        self.current_page = old_page;
+        self.parent_is_mod = old_parent_is_mod;
+        self.in_stripped = old_in_stripped;
+        ret
+    }
+}
@@ -12,6 +12,7 @@ use syntax::feature_gate::UnstableFeatures;
 use syntax::symbol::Symbol;
 use syntax_pos::DUMMY_SP;
 
+use std::mem;
 use std::ops::Range;
 
 use crate::core::DocContext;
@@ -211,73 +212,17 @@ impl<'a, 'tcx> LinkCollector<'a, 'tcx> {
             Err(())
         }
     }
-}
-
-impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
-    fn fold_item(&mut self, mut item: Item) -> Option<Item> {
-        let item_hir_id = if item.is_mod() {
-            if let Some(id) = self.cx.tcx.hir().as_local_hir_id(item.def_id) {
-                Some(id)
-            } else {
-                debug!("attempting to fold on a non-local item: {:?}", item);
-                return self.fold_item_recur(item);
-            }
-        } else {
-            None
-        };
-
-        // FIXME: get the resolver to work with non-local resolve scopes.
-        let parent_node = self.cx.as_local_hir_id(item.def_id).and_then(|hir_id| {
-            // FIXME: this fails hard for impls in non-module scope, but is necessary for the
-            // current `resolve()` implementation.
-            match self.cx.tcx.hir().get_module_parent_node(hir_id) {
-                id if id != hir_id => Some(id),
-                _ => None,
-            }
-        });
-
-        if parent_node.is_some() {
-            debug!("got parent node for {} {:?}, id {:?}", item.type_(), item.name, item.def_id);
-        }
-
-        let current_item = match item.inner {
-            ModuleItem(..) => {
-                if item.attrs.inner_docs {
-                    if item_hir_id.unwrap() != hir::CRATE_HIR_ID {
-                        item.name.clone()
-                    } else {
-                        None
-                    }
-                } else {
-                    match parent_node.or(self.mod_ids.last().cloned()) {
-                        Some(parent) if parent != hir::CRATE_HIR_ID => {
-                            // FIXME: can we pull the parent module's name from elsewhere?
-                            Some(self.cx.tcx.hir().name(parent).to_string())
-                        }
-                        _ => None,
-                    }
-                }
-            }
-            ImplItem(Impl { ref for_, .. }) => {
-                for_.def_id().map(|did| self.cx.tcx.item_name(did).to_string())
-            }
-            // we don't display docs on `extern crate` items anyway, so don't process them.
-            ExternCrateItem(..) => return self.fold_item_recur(item),
-            ImportItem(Import::Simple(ref name, ..)) => Some(name.clone()),
-            MacroItem(..) => None,
-            _ => item.name.clone(),
-        };
-
-        if item.is_mod() && item.attrs.inner_docs {
-            self.mod_ids.push(item_hir_id.unwrap());
-        }
 
+    /// Resolves the intra-doc links found in `dox`, the documentation of `item`, and records them
+    /// in its attributes.
+    fn resolve_links(&self,
+                     item: &mut Item,
+                     dox: &str,
+                     current_item: &Option<String>,
+                     parent_node: Option<hir::HirId>) {
         let cx = self.cx;
-        let dox = item.attrs.collapsed_doc_value().unwrap_or_else(String::new);
-
-        look_for_tests(&cx, &dox, &item, true);
 
-        for (ori_link, link_range) in markdown_links(&dox) {
+        for (ori_link, link_range) in markdown_links(dox) {
             // Bail early for real links.
             if ori_link.contains('/') {
                 continue;
@@ -324,10 +269,10 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
 
                 match kind {
                     Some(ns @ ValueNS) => {
-                        if let Ok(res) = self.resolve(path_str, ns, &current_item, parent_node) {
+                        if let Ok(res) = self.resolve(path_str, ns, current_item, parent_node) {
                             res
                         } else {
-                            resolution_failure(cx, &item, path_str, &dox, link_range);
+                            resolution_failure(cx, item, path_str, dox, link_range);
                             // This could just be a normal link or a broken link
                             // we could potentially check if something is
                             // "intra-doc-link-like" and warn in that case.
@@ -335,10 +280,10 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
                         }
                     }
                     Some(ns @ TypeNS) => {
-                        if let Ok(res) = self.resolve(path_str, ns, &current_item, parent_node) {
+                        if let Ok(res) = self.resolve(path_str, ns, current_item, parent_node) {
                             res
                         } else {
-                            resolution_failure(cx, &item, path_str, &dox, link_range);
+                            resolution_failure(cx, item, path_str, dox, link_range);
                             // This could just be a normal link.
                             continue;
                         }
@@ -348,10 +293,10 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
                         let candidates = PerNS {
                             macro_ns: macro_resolve(cx, path_str).map(|res| (res, None)),
                             type_ns: self
-                                .resolve(path_str, TypeNS, &current_item, parent_node)
+                                .resolve(path_str, TypeNS, current_item, parent_node)
                                 .ok(),
                             value_ns: self
-                                .resolve(path_str, ValueNS, &current_item, parent_node)
+                                .resolve(path_str, ValueNS, current_item, parent_node)
                                 .ok()
                                 .and_then(|(res, fragment)| {
                                     // Constructors are picked up in the type namespace.
@@ -363,7 +308,7 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
                         };
 
                         if candidates.is_empty() {
-                            resolution_failure(cx, &item, path_str, &dox, link_range);
+                            resolution_failure(cx, item, path_str, dox, link_range);
                             // this could just be a normal link
                             continue;
                         }
@@ -374,9 +319,9 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
                         } else {
                             ambiguity_error(
                                 cx,
-                                &item,
+                                item,
                                 path_str,
-                                &dox,
+                                dox,
                                 link_range,
                                 candidates.map(|candidate| candidate.map(|(res, _)| res)),
                             );
@@ -387,7 +332,7 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
                         if let Some(res) = macro_resolve(cx, path_str) {
                             (res, None)
                         } else {
-                            resolution_failure(cx, &item, path_str, &dox, link_range);
+                            resolution_failure(cx, item, path_str, dox, link_range);
                             continue
                         }
                     }
@@ -401,6 +346,74 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
                 item.attrs.links.push((ori_link, Some(id), fragment));
             }
         }
+    }
+}
+
+impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
+    fn fold_item(&mut self, mut item: Item) -> Option<Item> {
+        let item_hir_id = if item.is_mod() {
+            if let Some(id) = self.cx.tcx.hir().as_local_hir_id(item.def_id) {
+                Some(id)
+            } else {
+                debug!("attempting to fold on a non-local item: {:?}", item);
+                return self.fold_item_recur(item);
+            }
+        } else {
+            None
+        };
+
+        // FIXME: get the resolver to work with non-local resolve scopes.
+        let parent_node = self.cx.as_local_hir_id(item.def_id).and_then(|hir_id| {
+            // FIXME: this fails hard for impls in non-module scope, but is necessary for the
+            // current `resolve()` implementation.
+            match self.cx.tcx.hir().get_module_parent_node(hir_id) {
+                id if id != hir_id => Some(id),
+                _ => None,
+            }
+        });
+
+        if parent_node.is_some() {
+            debug!("got parent node for {} {:?}, id {:?}", item.type_(), item.name, item.def_id);
+        }
+
+        let current_item = match item.inner {
+            ModuleItem(..) => {
+                if item.attrs.inner_docs {
+                    if item_hir_id.unwrap() != hir::CRATE_HIR_ID {
+                        item.name.clone()
+                    } else {
+                        None
+                    }
+                } else {
+                    match parent_node.or(self.mod_ids.last().cloned()) {
+                        Some(parent) if parent != hir::CRATE_HIR_ID => {
+                            // FIXME: can we pull the parent module's name from elsewhere?
+                            Some(self.cx.tcx.hir().name(parent).to_string())
+                        }
+                        _ => None,
+                    }
+                }
+            }
+            ImplItem(Impl { ref for_, .. }) => {
+                for_.def_id().map(|did| self.cx.tcx.item_name(did).to_string())
+            }
+            // we don't display docs on `extern crate` items anyway, so don't process them.
+            ExternCrateItem(..) => return self.fold_item_recur(item),
+            ImportItem(Import::Simple(ref name, ..)) => Some(name.clone()),
+            MacroItem(..) => None,
+            _ => item.name.clone(),
+        };
+
+        if item.is_mod() && item.attrs.inner_docs {
+            self.mod_ids.push(item_hir_id.unwrap());
+        }
+
+        let cx = self.cx;
+        let dox = item.attrs.collapsed_doc_value().unwrap_or_else(String::new);
+
+        look_for_tests(&cx, &dox, &item, true);
+
+        self.resolve_links(&mut item, &dox, &current_item, parent_node);
 
         if item.is_mod() && !item.attrs.inner_docs {
             self.mod_ids.push(item_hir_id.unwrap());
@@ -423,6 +436,25 @@ impl<'a, 'tcx> DocFolder for LinkCollector<'a, 'tcx> {
     fn fold_crate(&mut self, mut c: Crate) -> Crate {
         c.module = c.module.take().and_then(|module| self.fold_item(module));
 
+        // The chapters of the guide are resolved as if they were documentation on the crate root.
+        if let Some(root) = c.module.as_ref().map(|module| module.def_id) {
+            for chapter in &mut c.guide {
+                let mut item = Item {
+                    source: Span::empty(),
+                    name: Some(chapter.name.clone()),
+                    attrs: mem::take(&mut chapter.attrs),
+                    inner: ModuleItem(Module { items: Vec::new(), is_crate: true }),
+                    visibility: Some(Public),
+                    def_id: root,
+                    stability: None,
+                    deprecation: None,
+                };
+                let dox = item.attrs.collapsed_doc_value().unwrap_or_else(String::new);
+                self.resolve_links(&mut item, &dox, &None, Some(hir::CRATE_HIR_ID));
+                chapter.attrs = item.attrs;
+            }
+        }
+
         c
     }
 }
@@ -494,6 +526,9 @@ fn resolution_failure(
                 before=link_range.start - last_new_line_offset,
                 found=link_range.len(),
             ));
+            if let [DocFragment::Include(_, _, ref filename, _)] = attrs.doc_strings[..] {
+                diag.note(&format!("the documentation is included from `{}`", filename));
+            }
         }
     };
     diag.help("to escape `[` and `]` characters, just add '\\' before them like \
@@ -1,6 +1,9 @@
 use std::sync::Arc;
 
-use crate::clean::{Crate, Item};
+use rustc::util::nodemap::FxHashSet;
+use syntax::symbol::sym;
+
+use crate::clean::{Crate, Item, AttributesExt};
 use crate::clean::cfg::Cfg;
 use crate::core::DocContext;
 use crate::fold::DocFolder;
@@ -12,18 +15,104 @@ pub const PROPAGATE_DOC_CFG: Pass = Pass {
     description: "propagates `#[doc(cfg(...))]` to child items",
 };
 
-pub fn propagate_doc_cfg(cr: Crate, _: &DocContext<'_>) -> Crate {
-    CfgPropagator { parent_cfg: None }.fold_crate(cr)
+pub fn propagate_doc_cfg(cr: Crate, cx: &DocContext<'_>) -> Crate {
+    let hidden_cfg = if cx.tcx.features().doc_auto_cfg {
+        Some(hidden_cfg(&cr, cx))
+    } else {
+        None
+    };
+
+    CfgPropagator { parent_cfg: None, hidden_cfg }.fold_crate(cr)
+}
+
+/// Collects the configuration options that should never show up in the requirements derived from
+/// `#[cfg]`: the ones that are always set (or never set) while documenting, plus those listed in
+/// `#![doc(cfg_hide(...))]`.
+fn hidden_cfg(cr: &Crate, cx: &DocContext<'_>) -> FxHashSet<Cfg> {
+    let mut hidden: FxHashSet<Cfg> = [sym::rustdoc, sym::doctest, sym::test].iter()
+        .map(|&name| Cfg::Cfg(name, None))
+        .collect();
+
+    let attrs = match cr.module {
+        Some(ref module) => &module.attrs,
+        None => return hidden,
+    };
+    for attr in attrs.lists(sym::doc).filter(|attr| attr.check_name(sym::cfg_hide)) {
+        for nested in attr.meta_item_list().unwrap_or(&[]) {
+            match nested.meta_item() {
+                Some(mi) => match Cfg::parse(mi) {
+                    Ok(cfg) => { hidden.insert(cfg); }
+                    Err(e) => cx.sess().span_err(e.span, e.msg),
+                },
+                None => cx.sess().span_err(nested.span(), "expected a cfg-pattern"),
+            }
+        }
+    }
+
+    hidden
+}
+
+/// Whether `cfg` is already one of the requirements expressed by `parent`.
+fn is_required_by(cfg: &Cfg, parent: &Cfg) -> bool {
+    match *parent {
+        Cfg::All(ref sub_cfgs) => sub_cfgs.contains(cfg),
+        ref parent => parent == cfg,
+    }
 }
 
 struct CfgPropagator {
     parent_cfg: Option<Arc<Cfg>>,
+    /// The configuration options to leave out of the requirements derived from `#[cfg]`, or
+    /// `None` if `#![feature(doc_auto_cfg)]` is not enabled.
+    hidden_cfg: Option<FxHashSet<Cfg>>,
+}
+
+impl CfgPropagator {
+    /// Derives the requirements of `item` from its own `#[cfg]` attributes.
+    fn auto_cfg(&self, item: &Item) -> Option<Cfg> {
+        let hidden = self.hidden_cfg.as_ref()?;
+        let mut cfg = Cfg::True;
+
+        for attr in item.attrs.other_attrs.iter().filter(|attr| attr.check_name(sym::cfg)) {
+            let list = match attr.meta_item_list() {
+                Some(list) => list,
+                None => continue,
+            };
+            // Malformed `#[cfg]` attributes have already been reported by the compiler.
+            let mi = match list.first().and_then(|nested| nested.meta_item()) {
+                Some(mi) if list.len() == 1 => mi,
+                _ => continue,
+            };
+            if let Some(new_cfg) = Cfg::parse(mi).ok().and_then(|c| c.strip_hidden(hidden)) {
+                if let Some(ref parent) = self.parent_cfg {
+                    if is_required_by(&new_cfg, parent) {
+                        continue;
+                    }
+                }
+                cfg &= new_cfg;
+            }
+        }
+
+        if cfg == Cfg::True { None } else { Some(cfg) }
+    }
 }
 
 impl DocFolder for CfgPropagator {
     fn fold_item(&mut self, mut item: Item) -> Option<Item> {
         let old_parent_cfg = self.parent_cfg.clone();
 
+        if let Some(auto_cfg) = self.auto_cfg(&item) {
+            item.attrs.cfg = match item.attrs.cfg.take() {
+                None => Some(Arc::new(auto_cfg)),
+                Some(mut cfg) => {
+                    if !is_required_by(&auto_cfg, &cfg) {
+                        *Arc::make_mut(&mut cfg) &= auto_cfg;
+                    }
+                    Some(cfg)
+                }
+            };
+        }
+
         let new_cfg = match (self.parent_cfg.take(), item.attrs.cfg.take()) {
             (None, None) => None,
             (Some(rc), None) | (None, Some(rc)) => Some(rc),
@@ -112,6 +112,14 @@ pub fn run(options: Options) -> i32 {
             });
         });
 
+        // The chapters of the guide are tested along with the crate's documentation.
+        let codes = ErrorCodes::from(compiler.session().opts.unstable_features.is_nightly_build());
+        for chapter in &options.guide {
+            collector.set_markdown_file(chapter.path.clone());
+            let doc = chapter.attrs.collapsed_doc_value().unwrap_or_default();
+            markdown::find_testable_code(&doc, &mut collector, codes);
+        }
+
         Ok(collector.tests)
     }).expect("compiler aborted in rustdoc!");
 
@@ -696,6 +704,16 @@ impl Collector {
         self.position = position;
     }
 
+    /// Collects the next tests from the Markdown file `filename` instead of the crate's source,
+    /// naming them after the headers of the file.
+    pub fn set_markdown_file(&mut self, filename: PathBuf) {
+        self.source_map = None;
+        self.filename = Some(filename);
+        self.position = DUMMY_SP;
+        self.use_headers = true;
+        self.names.clear();
+    }
+
     fn get_filename(&self) -> FileName {
         if let Some(ref source_map) = self.source_map {
             let filename = source_map.span_to_filename(self.position);
@@ -0,0 +1,361 @@
+//! Support for capturing a stack backtrace of an OS thread.
+//!
+//! This module contains the [`Backtrace`] type, which can be captured at some point of a
+//! program, for example when an error is created, and printed later on. Its `Display`
+//! implementation prints the backtrace in the same format as the one printed on panics with
+//! `RUST_BACKTRACE=1`, and the alternate flag (`{:#}`) prints it in the format of
+//! `RUST_BACKTRACE=full`.
+//!
+//! Capturing a backtrace only unwinds the stack of the current thread and records the
+//! instruction pointer of each frame: the frames are symbolized, which can be much slower,
+//! the first time the backtrace is printed.
+//!
+//! As capturing a backtrace still has a cost, [`Backtrace::capture`] only does it if it is
+//! enabled by an environment variable:
+//!
+//! * If `RUST_LIB_BACKTRACE` is set, backtraces are captured unless its value is `0`.
+//! * Otherwise, if `RUST_BACKTRACE` is set, backtraces are captured unless its value is `0`.
+//! * Otherwise, backtraces are not captured.
+//!
+//! The variables are read once, the first time a backtrace is captured. Setting
+//! `RUST_LIB_BACKTRACE=0` with `RUST_BACKTRACE=1` shows the backtraces of panics, but not the
+//! ones captured by libraries. [`Backtrace::force_capture`] captures a backtrace regardless of
+//! the environment.
+//!
+//! Backtraces are not supported on all platforms, nor in builds of the standard library
+//! without the `backtrace` feature, in which case the captured backtraces are empty, with a
+//! [`BacktraceStatus::Unsupported`] status.
+//!
+//! [`Backtrace`]: struct.Backtrace.html
+//! [`Backtrace::capture`]: struct.Backtrace.html#method.capture
+//! [`Backtrace::force_capture`]: struct.Backtrace.html#method.force_capture
+//! [`BacktraceStatus::Unsupported`]: enum.BacktraceStatus.html#variant.Unsupported
+
+#![unstable(feature = "backtrace", issue = "53487")]
+
+use crate::env;
+use crate::fmt;
+use crate::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "backtrace")]
+use crate::ffi::c_void;
+#[cfg(feature = "backtrace")]
+use crate::sync::{Mutex, MutexGuard};
+#[cfg(feature = "backtrace")]
+use crate::sys_common::backtrace::{self as sys_backtrace, PrintFormat, Printer, SymbolInfo};
+#[cfg(feature = "backtrace")]
+use backtrace::{BytesOrWideString, SymbolName};
+
+/// A captured stack backtrace of an OS thread.
+///
+/// A `Backtrace` is captured with [`capture`] or [`force_capture`], and printed with its
+/// `Display` implementation, in the format of the backtraces of panics, or with its `Debug`
+/// implementation, as a list of the functions and locations of its frames.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(backtrace)]
+///
+/// use std::backtrace::Backtrace;
+///
+/// let backtrace = Backtrace::force_capture();
+/// println!("{}", backtrace);
+/// ```
+///
+/// [`capture`]: #method.capture
+/// [`force_capture`]: #method.force_capture
+pub struct Backtrace {
+    inner: Inner,
+}
+
+/// The current status of a backtrace, indicating whether it was captured or whether it is
+/// empty for some other reason.
+#[non_exhaustive]
+#[derive(Debug, PartialEq, Eq)]
+pub enum BacktraceStatus {
+    /// Capturing a backtrace is not supported, likely because it's not implemented for the
+    /// current platform.
+    Unsupported,
+    /// Capturing a backtrace has been disabled through either the `RUST_LIB_BACKTRACE` or
+    /// `RUST_BACKTRACE` environment variables.
+    Disabled,
+    /// A backtrace has been captured and the `Backtrace` should print reasonable information
+    /// when rendered.
+    Captured,
+}
+
+enum Inner {
+    Unsupported,
+    Disabled,
+    #[cfg(feature = "backtrace")]
+    Captured(Mutex<Capture>),
+}
+
+#[cfg(feature = "backtrace")]
+struct Capture {
+    /// The index of the first frame of the caller of `capture` or `force_capture`. The frames
+    /// before it, inside the standard library, are only printed in the full format.
+    actual_start: usize,
+    resolved: bool,
+    frames: Vec<BacktraceFrame>,
+}
+
+#[cfg(feature = "backtrace")]
+struct BacktraceFrame {
+    ip: usize,
+    /// The symbols of the frame, which are only known once the backtrace has been resolved.
+    /// A frame can have several symbols when functions were inlined into it.
+    symbols: Vec<BacktraceSymbol>,
+}
+
+#[cfg(feature = "backtrace")]
+struct BacktraceSymbol {
+    name: Option<Vec<u8>>,
+    filename: Option<BytesOrWide>,
+    lineno: Option<u32>,
+}
+
+#[cfg(feature = "backtrace")]
+enum BytesOrWide {
+    Bytes(Vec<u8>),
+    Wide(Vec<u16>),
+}
+
+fn _assert_send_sync() {
+    fn _assert<T: Send + Sync>() {}
+    _assert::<Backtrace>();
+}
+
+impl Backtrace {
+    /// Returns whether backtrace captures are enabled by the environment variables.
+    fn enabled() -> bool {
+        // Cache the result of reading the environment variables, as they are read every time
+        // a backtrace is captured.
+        static ENABLED: AtomicUsize = AtomicUsize::new(0);
+        match ENABLED.load(Ordering::SeqCst) {
+            0 => {}
+            1 => return false,
+            _ => return true,
+        }
+        let enabled = match env::var_os("RUST_LIB_BACKTRACE") {
+            Some(s) => s != "0",
+            None => match env::var_os("RUST_BACKTRACE") {
+                Some(s) => s != "0",
+                None => false,
+            },
+        };
+        ENABLED.store(enabled as usize + 1, Ordering::SeqCst);
+        enabled
+    }
+
+    /// Captures a stack backtrace of the current thread.
+    ///
+    /// This function captures a stack backtrace if it is enabled by the `RUST_LIB_BACKTRACE`
+    /// or `RUST_BACKTRACE` environment variables, and returns a disabled backtrace
+    /// otherwise, as described in the [module documentation]. This makes it cheap enough to
+    /// be called when an error is created, leaving the decision of whether backtraces are
+    /// worth their cost to the user.
+    ///
+    /// The returned backtrace starts at the caller of this function.
+    ///
+    /// [module documentation]: index.html
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn capture() -> Backtrace {
+        if !Backtrace::enabled() {
+            return Backtrace { inner: Inner::Disabled };
+        }
+        Backtrace::create(Backtrace::capture as usize)
+    }
+
+    /// Forcibly captures a stack backtrace of the current thread, regardless of the
+    /// environment variables.
+    ///
+    /// This function is meant for the cases where a backtrace is explicitly requested,
+    /// like a debugging tool printing where a thread is. Capturing a backtrace can be slow,
+    /// so it shouldn't be done each time an error is created.
+    #[inline(never)] // want to make sure there's a frame here to remove
+    pub fn force_capture() -> Backtrace {
+        Backtrace::create(Backtrace::force_capture as usize)
+    }
+
+    /// Captures the frames of the current thread. `ip` is the address of the function called
+    /// by the user, whose frame is the last one of the standard library.
+    #[cfg(feature = "backtrace")]
+    fn create(ip: usize) -> Backtrace {
+        // See `sys_common::backtrace::print` for why libbacktrace isn't used in tests.
+        if cfg!(test) {
+            return Backtrace { inner: Inner::Unsupported };
+        }
+
+        let _lock = sys_backtrace::lock();
+        let mut frames = Vec::new();
+        let mut actual_start = None;
+        unsafe {
+            backtrace::trace_unsynchronized(|frame| {
+                frames.push(BacktraceFrame { ip: frame.ip() as usize, symbols: Vec::new() });
+                if frame.symbol_address() as usize == ip && actual_start.is_none() {
+                    actual_start = Some(frames.len());
+                }
+                true
+            });
+        }
+
+        // If no frames came out, assume that this is an unsupported platform, since the
+        // unwinder always finds at least the frame of this function.
+        let inner = if frames.is_empty() {
+            Inner::Unsupported
+        } else {
+            Inner::Captured(Mutex::new(Capture {
+                actual_start: actual_start.unwrap_or(0),
+                resolved: false,
+                frames,
+            }))
+        };
+        Backtrace { inner }
+    }
+
+    #[cfg(not(feature = "backtrace"))]
+    fn create(_ip: usize) -> Backtrace {
+        Backtrace { inner: Inner::Unsupported }
+    }
+
+    /// Returns the status of this backtrace, indicating whether it was captured or whether
+    /// it is empty.
+    pub fn status(&self) -> BacktraceStatus {
+        match self.inner {
+            Inner::Unsupported => BacktraceStatus::Unsupported,
+            Inner::Disabled => BacktraceStatus::Disabled,
+            #[cfg(feature = "backtrace")]
+            Inner::Captured(_) => BacktraceStatus::Captured,
+        }
+    }
+}
+
+impl fmt::Display for Backtrace {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            Inner::Unsupported => fmt.write_str("unsupported backtrace"),
+            Inner::Disabled => fmt.write_str("disabled backtrace"),
+            #[cfg(feature = "backtrace")]
+            Inner::Captured(ref capture) => lock(capture).fmt_display(fmt),
+        }
+    }
+}
+
+impl fmt::Debug for Backtrace {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            Inner::Unsupported => fmt.write_str("unsupported backtrace"),
+            Inner::Disabled => fmt.write_str("disabled backtrace"),
+            #[cfg(feature = "backtrace")]
+            Inner::Captured(ref capture) => lock(capture).fmt_debug(fmt),
+        }
+    }
+}
+
+/// Locks the capture of a backtrace, even when it is poisoned: a panic while a backtrace was
+/// being resolved or written out, e.g. by the writer behind the formatter, must not make it
+/// impossible to print that backtrace again.
+#[cfg(feature = "backtrace")]
+fn lock(capture: &Mutex<Capture>) -> MutexGuard<'_, Capture> {
+    capture.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(feature = "backtrace")]
+impl Capture {
+    /// Symbolizes the frames, unless it has already been done.
+    fn resolve(&mut self) {
+        if self.resolved {
+            return;
+        }
+        self.resolved = true;
+
+        let _lock = sys_backtrace::lock();
+        for frame in self.frames.iter_mut() {
+            let symbols = &mut frame.symbols;
+            unsafe {
+                backtrace::resolve_unsynchronized(frame.ip as *mut c_void, |symbol| {
+                    symbols.push(BacktraceSymbol {
+                        name: symbol.name().map(|name| name.as_bytes().to_vec()),
+                        filename: symbol.filename_raw().map(|filename| match filename {
+                            BytesOrWideString::Bytes(b) => BytesOrWide::Bytes(b.to_vec()),
+                            BytesOrWideString::Wide(w) => BytesOrWide::Wide(w.to_vec()),
+                        }),
+                        lineno: symbol.lineno(),
+                    });
+                });
+            }
+        }
+    }
+
+    fn fmt_display(&mut self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.resolve();
+
+        let (format, frames) = if fmt.alternate() {
+            (PrintFormat::Full, &self.frames[..])
+        } else {
+            (PrintFormat::Short, &self.frames[self.actual_start..])
+        };
+        writeln!(fmt, "stack backtrace:")?;
+        let mut printer = Printer::new(format, fmt);
+        for frame in frames {
+            let ip = frame.ip as *mut c_void;
+            if frame.symbols.is_empty() {
+                printer.output(ip, None);
+            }
+            for symbol in &frame.symbols {
+                printer.output(ip, Some(symbol.info()));
+            }
+            if printer.is_done() {
+                break;
+            }
+        }
+        printer.finish()
+    }
+
+    fn fmt_debug(&mut self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.resolve();
+
+        let frames = &self.frames[self.actual_start..];
+        fmt.debug_list()
+            .entries(frames.iter().flat_map(|frame| &frame.symbols))
+            .finish()
+    }
+}
+
+#[cfg(feature = "backtrace")]
+impl BacktraceSymbol {
+    fn info(&self) -> SymbolInfo<'_> {
+        SymbolInfo {
+            name: self.name.as_ref().map(|name| SymbolName::new(name)),
+            filename: self.filename.as_ref().map(|filename| match filename {
+                BytesOrWide::Bytes(b) => BytesOrWideString::Bytes(b),
+                BytesOrWide::Wide(w) => BytesOrWideString::Wide(w),
+            }),
+            lineno: self.lineno,
+        }
+    }
+}
+
+#[cfg(feature = "backtrace")]
+impl fmt::Debug for BacktraceSymbol {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.info();
+        match info.name {
+            Some(name) => write!(fmt, "{{ fn: \"{:#}\"", name)?,
+            None => fmt.write_str("{ fn: <unknown>")?,
+        }
+        if let Some(filename) = &self.filename {
+            let filename = match filename {
+                BytesOrWide::Bytes(b) => String::from_utf8_lossy(b).into_owned(),
+                BytesOrWide::Wide(w) => String::from_utf16_lossy(w),
+            };
+            write!(fmt, ", file: {:?}", filename)?;
+        }
+        if let Some(lineno) = info.lineno {
+            write!(fmt, ", line: {}", lineno)?;
+        }
+        fmt.write_str(" }")
+    }
+}
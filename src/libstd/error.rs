@@ -17,6 +17,7 @@ use core::array;
 
 use crate::alloc::{AllocErr, LayoutErr, CannotReallocInPlace};
 use crate::any::TypeId;
+use crate::backtrace::Backtrace;
 use crate::borrow::Cow;
 use crate::cell;
 use crate::char;
@@ -196,6 +197,64 @@ pub trait Error: Debug + Display {
     #[stable(feature = "error_source", since = "1.30.0")]
     fn source(&self) -> Option<&(dyn Error + 'static)> { None }
 
+    /// Returns a stack backtrace, if available, of where this error occurred.
+    ///
+    /// This allows error-reporting code to show where an error originated from, rather than
+    /// where it was reported. An error type providing a backtrace typically captures it with
+    /// [`Backtrace::capture`] when the error is created, and stores it in the error.
+    ///
+    /// Not all errors contain a `Backtrace`, and the ones returned may be disabled or
+    /// unsupported, which their [`status`] tells.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(backtrace)]
+    ///
+    /// use std::backtrace::Backtrace;
+    /// use std::error::Error;
+    /// use std::fmt;
+    ///
+    /// #[derive(Debug)]
+    /// struct ParseError {
+    ///     backtrace: Backtrace,
+    /// }
+    ///
+    /// impl ParseError {
+    ///     fn new() -> ParseError {
+    ///         ParseError { backtrace: Backtrace::capture() }
+    ///     }
+    /// }
+    ///
+    /// impl fmt::Display for ParseError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "invalid syntax")
+    ///     }
+    /// }
+    ///
+    /// impl Error for ParseError {
+    ///     fn backtrace(&self) -> Option<&Backtrace> {
+    ///         Some(&self.backtrace)
+    ///     }
+    /// }
+    ///
+    /// fn report(err: &dyn Error) {
+    ///     eprintln!("error: {}", err);
+    ///     if let Some(backtrace) = err.backtrace() {
+    ///         eprintln!("{}", backtrace);
+    ///     }
+    /// }
+    ///
+    /// report(&ParseError::new());
+    /// ```
+    ///
+    /// [`Backtrace::capture`]: ../backtrace/struct.Backtrace.html#method.capture
+    /// [`status`]: ../backtrace/struct.Backtrace.html#method.status
+    #[unstable(feature = "backtrace", issue = "53487")]
+    fn backtrace(&self) -> Option<&Backtrace> {
+        None
+    }
+
     /// Gets the `TypeId` of `self`
     #[doc(hidden)]
     #[unstable(feature = "error_type_id",
@@ -564,6 +623,10 @@ impl<T: Error> Error for Box<T> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         Error::source(&**self)
     }
+
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Error::backtrace(&**self)
+    }
 }
 
 #[stable(feature = "fmt_error", since = "1.11.0")]
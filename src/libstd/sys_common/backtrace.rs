@@ -2,6 +2,8 @@
 /// supported platforms.
 
 use crate::env;
+use crate::ffi::c_void;
+use crate::fmt;
 use crate::io;
 use crate::io::prelude::*;
 use crate::mem;
@@ -10,17 +12,37 @@ use crate::ptr;
 use crate::sync::atomic::{self, Ordering};
 use crate::sys::mutex::Mutex;
 
-use backtrace::{BytesOrWideString, Frame, Symbol};
+use backtrace::{BytesOrWideString, Symbol, SymbolName};
 
 pub const HEX_WIDTH: usize = 2 + 2 * mem::size_of::<usize>();
 
 /// Max number of frames to print.
 const MAX_NB_FRAMES: usize = 100;
 
-/// Prints the current backtrace.
-pub fn print(w: &mut dyn Write, format: PrintFormat) -> io::Result<()> {
+/// Takes the lock serializing the unwinding and the symbolization of backtraces.
+///
+/// Some platforms require it, like `SymFromAddr` on Windows, and it prevents mixed output
+/// in multithreading context.
+pub fn lock() -> impl Drop {
+    struct Guard;
     static LOCK: Mutex = Mutex::new();
 
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            unsafe {
+                LOCK.unlock();
+            }
+        }
+    }
+
+    unsafe {
+        LOCK.lock();
+        Guard
+    }
+}
+
+/// Prints the current backtrace.
+pub fn print(w: &mut dyn Write, format: PrintFormat) -> io::Result<()> {
     // There are issues currently linking libbacktrace into tests, and in
     // general during libstd's own unit tests we're not testing this path. In
     // test mode immediately return here to optimize away any references to the
@@ -29,41 +51,35 @@ pub fn print(w: &mut dyn Write, format: PrintFormat) -> io::Result<()> {
         return Ok(());
     }
 
-    // Use a lock to prevent mixed output in multithreading context.
-    // Some platforms also requires it, like `SymFromAddr` on Windows.
-    unsafe {
-        LOCK.lock();
-        let res = _print(w, format);
-        LOCK.unlock();
-        res
-    }
+    let _lock = lock();
+    write!(w, "{}", DisplayBacktrace { format })
+}
+
+/// Traces and prints the current backtrace when formatted. The caller holds the lock.
+struct DisplayBacktrace {
+    format: PrintFormat,
 }
 
-fn _print(w: &mut dyn Write, format: PrintFormat) -> io::Result<()> {
-    writeln!(w, "stack backtrace:")?;
+impl fmt::Display for DisplayBacktrace {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(fmt, "stack backtrace:")?;
 
-    let mut printer = Printer::new(format, w);
-    unsafe {
-        backtrace::trace_unsynchronized(|frame| {
-            let mut hit = false;
-            backtrace::resolve_frame_unsynchronized(frame, |symbol| {
-                hit = true;
-                printer.output(frame, Some(symbol));
+        let mut printer = Printer::new(self.format, fmt);
+        unsafe {
+            backtrace::trace_unsynchronized(|frame| {
+                let mut hit = false;
+                backtrace::resolve_frame_unsynchronized(frame, |symbol| {
+                    hit = true;
+                    printer.output(frame.ip(), Some(SymbolInfo::new(symbol)));
+                });
+                if !hit {
+                    printer.output(frame.ip(), None);
+                }
+                !printer.is_done()
             });
-            if !hit {
-                printer.output(frame, None);
-            }
-            !printer.done
-        });
-    }
-    if printer.skipped {
-        writeln!(
-            w,
-            "note: Some details are omitted, \
-             run with `RUST_BACKTRACE=full` for a verbose backtrace."
-        )?;
+        }
+        printer.finish()
     }
-    Ok(())
 }
 
 /// Fixed frame used to clean the backtrace with `RUST_BACKTRACE=1`.
@@ -116,38 +132,74 @@ pub fn log_enabled() -> Option<PrintFormat> {
     val
 }
 
-struct Printer<'a, 'b> {
+/// The parts of a resolved symbol that get printed, borrowed either from the symbol being
+/// resolved or from a captured `Backtrace`.
+pub struct SymbolInfo<'a> {
+    pub name: Option<SymbolName<'a>>,
+    pub filename: Option<BytesOrWideString<'a>>,
+    pub lineno: Option<u32>,
+}
+
+impl<'a> SymbolInfo<'a> {
+    fn new(symbol: &'a Symbol) -> SymbolInfo<'a> {
+        SymbolInfo {
+            name: symbol.name(),
+            filename: symbol.filename_raw(),
+            lineno: symbol.lineno(),
+        }
+    }
+}
+
+/// Prints the frames of a backtrace, one symbol at a time, in the format of `print`.
+pub struct Printer<'a, 'b> {
     format: PrintFormat,
     done: bool,
     skipped: bool,
     idx: usize,
-    out: &'a mut (dyn Write + 'b),
+    out: &'a mut fmt::Formatter<'b>,
 }
 
 impl<'a, 'b> Printer<'a, 'b> {
-    fn new(format: PrintFormat, out: &'a mut (dyn Write + 'b)) -> Printer<'a, 'b> {
+    pub fn new(format: PrintFormat, out: &'a mut fmt::Formatter<'b>) -> Printer<'a, 'b> {
         Printer { format, done: false, skipped: false, idx: 0, out }
     }
 
+    /// Whether the remaining frames are not to be printed.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
     /// Prints the symbol of the backtrace frame.
     ///
     /// These output functions should now be used everywhere to ensure consistency.
     /// You may want to also use `output_fileline`.
-    fn output(&mut self, frame: &Frame, symbol: Option<&Symbol>) {
+    pub fn output(&mut self, ip: *mut c_void, symbol: Option<SymbolInfo<'_>>) {
         if self.idx > MAX_NB_FRAMES {
             self.done = true;
             self.skipped = true;
             return;
         }
-        if self._output(frame, symbol).is_err() {
+        if self._output(ip, symbol.as_ref()).is_err() {
             self.done = true;
         }
         self.idx += 1;
     }
 
-    fn _output(&mut self, frame: &Frame, symbol: Option<&Symbol>) -> io::Result<()> {
+    /// Ends the backtrace, with a note if frames were omitted.
+    pub fn finish(self) -> fmt::Result {
+        if self.skipped {
+            writeln!(
+                self.out,
+                "note: Some details are omitted, \
+                 run with `RUST_BACKTRACE=full` for a verbose backtrace."
+            )?;
+        }
+        Ok(())
+    }
+
+    fn _output(&mut self, ip: *mut c_void, symbol: Option<&SymbolInfo<'_>>) -> fmt::Result {
         if self.format == PrintFormat::Short {
-            if let Some(sym) = symbol.and_then(|s| s.name()).and_then(|s| s.as_str()) {
+            if let Some(sym) = symbol.and_then(|s| s.name.as_ref()).and_then(|s| s.as_str()) {
                 if sym.contains("__rust_begin_short_backtrace") {
                     self.skipped = true;
                     self.done = true;
@@ -156,7 +208,7 @@ impl<'a, 'b> Printer<'a, 'b> {
             }
 
             // Remove the `17: 0x0 - <unknown>` line.
-            if self.format == PrintFormat::Short && frame.ip() == ptr::null_mut() {
+            if self.format == PrintFormat::Short && ip == ptr::null_mut() {
                 self.skipped = true;
                 return Ok(());
             }
@@ -164,12 +216,12 @@ impl<'a, 'b> Printer<'a, 'b> {
 
         match self.format {
             PrintFormat::Full => {
-                write!(self.out, "  {:2}: {:2$?} - ", self.idx, frame.ip(), HEX_WIDTH)?
+                write!(self.out, "  {:2}: {:2$?} - ", self.idx, ip, HEX_WIDTH)?
             }
             PrintFormat::Short => write!(self.out, "  {:2}: ", self.idx)?,
         }
 
-        match symbol.and_then(|s| s.name()) {
+        match symbol.and_then(|s| s.name.as_ref()) {
             Some(symbol) => {
                 match self.format {
                     PrintFormat::Full => write!(self.out, "{}", symbol)?,
@@ -177,9 +229,9 @@ impl<'a, 'b> Printer<'a, 'b> {
                     PrintFormat::Short => write!(self.out, "{:#}", symbol)?,
                 }
             }
-            None => self.out.write_all(b"<unknown>")?,
+            None => self.out.write_str("<unknown>")?,
         }
-        self.out.write_all(b"\n")?;
+        self.out.write_str("\n")?;
         if let Some(sym) = symbol {
             self.output_fileline(sym)?;
         }
@@ -189,10 +241,10 @@ impl<'a, 'b> Printer<'a, 'b> {
     /// Prints the filename and line number of the backtrace frame.
     ///
     /// See also `output`.
-    fn output_fileline(&mut self, symbol: &Symbol) -> io::Result<()> {
+    fn output_fileline(&mut self, symbol: &SymbolInfo<'_>) -> fmt::Result {
         #[cfg(windows)]
         let path_buf;
-        let file = match symbol.filename_raw() {
+        let file = match symbol.filename {
             #[cfg(unix)]
             Some(BytesOrWideString::Bytes(bytes)) => {
                 use crate::os::unix::prelude::*;
@@ -214,12 +266,11 @@ impl<'a, 'b> Printer<'a, 'b> {
             }
             None => return Ok(()),
         };
-        let line = match symbol.lineno() {
+        let line = match symbol.lineno {
             Some(line) => line,
             None => return Ok(()),
         };
         // prior line: "  ##: {:2$} - func"
-        self.out.write_all(b"")?;
         match self.format {
             PrintFormat::Full => write!(self.out, "           {:1$}", "", HEX_WIDTH)?,
             PrintFormat::Short => write!(self.out, "           ")?,
@@ -240,6 +291,6 @@ impl<'a, 'b> Printer<'a, 'b> {
             write!(self.out, "  at {}:{}", file.display(), line)?;
         }
 
-        self.out.write_all(b"\n")
+        self.out.write_str("\n")
     }
 }
@@ -78,6 +78,25 @@
 //! });
 //! ```
 //!
+//! ## Scoped threads
+//!
+//! Threads spawned with [`spawn`] may outlive their parent, so their closure can only
+//! borrow `'static` data. The threads spawned in a [`scope`] are all joined before it
+//! returns, so they can borrow the local variables of the thread creating the scope:
+//!
+//! ```rust
+//! #![feature(scoped_threads)]
+//! use std::thread;
+//!
+//! let mut numbers = vec![1, 2, 3, 4];
+//! thread::scope(|s| {
+//!     for chunk in numbers.chunks_mut(2) {
+//!         s.spawn(move || chunk.iter_mut().for_each(|n| *n *= 2));
+//!     }
+//! });
+//! assert_eq!(numbers, [2, 4, 6, 8]);
+//! ```
+//!
 //! ## The `Thread` type
 //!
 //! Threads are represented via the [`Thread`] type, which you can get in one of
@@ -153,6 +172,7 @@
 //! [`RefCell`]: ../cell/struct.RefCell.html
 //! [`thread_local!`]: ../macro.thread_local.html
 //! [`with`]: struct.LocalKey.html#method.with
+//! [`scope`]: ../../std/thread/fn.scope.html
 
 #![stable(feature = "rust1", since = "1.0.0")]
 
@@ -185,6 +205,17 @@ use crate::time::Duration;
 #[stable(feature = "rust1", since = "1.0.0")]
 pub use self::local::{LocalKey, AccessError};
 
+////////////////////////////////////////////////////////////////////////////////
+// Scoped threads
+////////////////////////////////////////////////////////////////////////////////
+
+mod scoped;
+
+#[unstable(feature = "scoped_threads", issue = "0")]
+pub use self::scoped::{scope, Scope, ScopedJoinHandle};
+
+use self::scoped::ScopeData;
+
 // The types used by the thread_local! macro to access TLS keys. Note that there
 // are two types, the "OS" type and the "fast" type. The OS thread local key
 // type is accessed via platform-specific API calls and is slow, while the fast
@@ -447,6 +478,18 @@ impl Builder {
     #[unstable(feature = "thread_spawn_unchecked", issue = "55132")]
     pub unsafe fn spawn_unchecked<'a, F, T>(self, f: F) -> io::Result<JoinHandle<T>> where
         F: FnOnce() -> T, F: Send + 'a, T: Send + 'a
+    {
+        self.spawn_unchecked_(f, None).map(JoinHandle)
+    }
+
+    /// Spawns a thread like `spawn_unchecked`, which is counted as running in `scope_data`
+    /// if it belongs to a scope, until it is done with everything it borrows.
+    unsafe fn spawn_unchecked_<'a, F, T>(
+        self,
+        f: F,
+        scope_data: Option<Arc<ScopeData>>,
+    ) -> io::Result<JoinInner<T>> where
+        F: FnOnce() -> T, F: Send + 'a, T: Send + 'a
     {
         let Builder { name, stack_size } = self;
 
@@ -458,6 +501,7 @@ impl Builder {
         let my_packet : Arc<UnsafeCell<Option<Result<T>>>>
             = Arc::new(UnsafeCell::new(None));
         let their_packet = my_packet.clone();
+        let their_scope_data = scope_data.clone();
 
         let main = move || {
             if let Some(name) = their_thread.cname() {
@@ -471,30 +515,48 @@ impl Builder {
             }));
             #[cfg(not(feature = "backtrace"))]
             let try_result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+            let panicked = try_result.is_err();
             *their_packet.get() = Some(try_result);
+
+            // The result may borrow from the scope, so it has to be dropped, if the join
+            // handle already was, before the scope learns that this thread is done.
+            drop(their_packet);
+            if let Some(scope_data) = their_scope_data {
+                scope_data.decrement_num_running_threads(panicked);
+            }
         };
 
-        Ok(JoinHandle(JoinInner {
-            // `imp::Thread::new` takes a closure with a `'static` lifetime, since it's passed
-            // through FFI or otherwise used with low-level threading primitives that have no
-            // notion of or way to enforce lifetimes.
-            //
-            // As mentioned in the `Safety` section of this function's documentation, the caller of
-            // this function needs to guarantee that the passed-in lifetime is sufficiently long
-            // for the lifetime of the thread.
-            //
-            // Similarly, the `sys` implementation must guarantee that no references to the closure
-            // exist after the thread has terminated, which is signaled by `Thread::join`
-            // returning.
-            native: Some(imp::Thread::new(
-                stack_size,
-                mem::transmute::<Box<dyn FnOnce() + 'a>, Box<dyn FnOnce() + 'static>>(Box::new(
-                    main,
-                )),
-            )?),
+        if let Some(scope_data) = &scope_data {
+            scope_data.increment_num_running_threads();
+        }
+        // `imp::Thread::new` takes a closure with a `'static` lifetime, since it's passed
+        // through FFI or otherwise used with low-level threading primitives that have no
+        // notion of or way to enforce lifetimes.
+        //
+        // As mentioned in the `Safety` section of this function's documentation, the caller of
+        // this function needs to guarantee that the passed-in lifetime is sufficiently long
+        // for the lifetime of the thread.
+        //
+        // Similarly, the `sys` implementation must guarantee that no references to the closure
+        // exist after the thread has terminated, which is signaled by `Thread::join`
+        // returning.
+        let native = imp::Thread::new(
+            stack_size,
+            mem::transmute::<Box<dyn FnOnce() + 'a>, Box<dyn FnOnce() + 'static>>(Box::new(
+                main,
+            )),
+        );
+        if native.is_err() {
+            if let Some(scope_data) = &scope_data {
+                scope_data.decrement_num_running_threads(false);
+            }
+        }
+
+        Ok(JoinInner {
+            native: Some(native?),
             thread: my_thread,
             packet: Packet(my_packet),
-        }))
+        })
     }
 }
 
@@ -1480,6 +1542,8 @@ fn _assert_sync_and_send() {
     fn _assert_both<T: Send + Sync>() {}
     _assert_both::<JoinHandle<()>>();
     _assert_both::<Thread>();
+    _assert_both::<Scope<'_, '_>>();
+    _assert_both::<ScopedJoinHandle<'_, ()>>();
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -1742,6 +1806,84 @@ mod tests {
         assert!(thread::current().id() != spawned_id);
     }
 
+    #[test]
+    fn test_scope_borrows() {
+        let mut numbers = vec![1, 2, 3, 4];
+        let total = crate::sync::atomic::AtomicUsize::new(0);
+        thread::scope(|s| {
+            for chunk in numbers.chunks_mut(2) {
+                let total = &total;
+                s.spawn(move || {
+                    for n in chunk {
+                        *n *= 2;
+                        total.fetch_add(*n, crate::sync::atomic::Ordering::SeqCst);
+                    }
+                });
+            }
+        });
+        assert_eq!(numbers, [2, 4, 6, 8]);
+        assert_eq!(total.into_inner(), 20);
+    }
+
+    #[test]
+    fn test_scope_join() {
+        let message = String::from("hello");
+        let len = thread::scope(|s| {
+            let handle = s.spawn(|| message.len());
+            assert!(handle.thread().name().is_none());
+            handle.join().unwrap()
+        });
+        assert_eq!(len, 5);
+    }
+
+    #[test]
+    fn test_scope_nested_spawn() {
+        let done = crate::sync::atomic::AtomicBool::new(false);
+        thread::scope(|s| {
+            let done = &done;
+            s.spawn(move || {
+                s.spawn(move || done.store(true, crate::sync::atomic::Ordering::SeqCst));
+            });
+        });
+        assert!(done.into_inner());
+    }
+
+    #[test]
+    fn test_scope_joined_panic() {
+        let result = thread::scope(|s| {
+            let handle = s.spawn(|| panic!());
+            handle.join().is_err()
+        });
+        assert!(result);
+    }
+
+    #[test]
+    fn test_scope_unjoined_panic() {
+        let finished = crate::sync::atomic::AtomicBool::new(false);
+        let result = crate::panic::catch_unwind(crate::panic::AssertUnwindSafe(|| {
+            thread::scope(|s| {
+                s.spawn(|| panic!());
+                s.spawn(|| {
+                    super::sleep(Duration::from_millis(50));
+                    finished.store(true, crate::sync::atomic::Ordering::SeqCst);
+                });
+            });
+        }));
+        assert!(result.is_err());
+        // The scope panics only once all its threads are joined.
+        assert!(finished.load(crate::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_builder_spawn_scoped() {
+        let name = thread::scope(|s| {
+            Builder::new().name("scoped".to_string()).spawn_scoped(s, || {
+                thread::current().name().map(|name| name.to_string())
+            }).unwrap().join().unwrap()
+        });
+        assert_eq!(name.as_ref().map(|name| &name[..]), Some("scoped"));
+    }
+
     // NOTE: the corresponding test for stderr is in ui/thread-stderr, due
     // to the test harness apparently interfering with stderr configuration.
 }
@@ -559,6 +559,9 @@ declare_features! (
     // Allows the use of or-patterns, e.g. `0 | 1`.
     (active, or_patterns, "1.38.0", Some(54883), None),
 
+    // Allows rustdoc to derive `#[doc(cfg(...))]` from `#[cfg(...)]`, and `#![doc(cfg_hide(...))]`.
+    (active, doc_auto_cfg, "1.38.0", Some(43781), None),
+
     // -------------------------------------------------------------------------
     // feature-group-end: actual feature gates
     // -------------------------------------------------------------------------
@@ -1876,6 +1879,10 @@ impl<'a> Visitor<'a> for PostExpansionVisitor<'a> {
                     gate_feature_post!(&self, doc_cfg, attr.span,
                         "`#[doc(cfg(...))]` is experimental"
                     );
+                } else if content.iter().any(|c| c.check_name(sym::cfg_hide)) {
+                    gate_feature_post!(&self, doc_auto_cfg, attr.span,
+                        "`#[doc(cfg_hide(...))]` is experimental"
+                    );
                 } else if content.iter().any(|c| c.check_name(sym::masked)) {
                     gate_feature_post!(&self, doc_masked, attr.span,
                         "`#[doc(masked)]` is experimental"
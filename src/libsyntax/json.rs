@@ -25,6 +25,9 @@ use std::sync::{Arc, Mutex};
 
 use rustc_serialize::json::{as_json, as_pretty_json};
 
+#[cfg(test)]
+mod tests;
+
 pub struct JsonEmitter {
     dst: Box<dyn Write + Send>,
     registry: Option<Registry>,
@@ -99,6 +102,10 @@ impl Emitter for JsonEmitter {
         } else {
             writeln!(&mut self.dst, "{}", as_json(&data))
         };
+        // Build systems act on this notification while we keep running (for
+        // example, starting dependent crates as soon as `.rmeta` is ready),
+        // so don't leave it sitting in a buffer.
+        let result = result.and_then(|_| self.dst.flush());
         if let Err(e) = result {
             panic!("failed to print notification: {:?}", e);
         }
@@ -0,0 +1,36 @@
+use super::*;
+
+use errors::ColorConfig;
+use std::io::BufWriter;
+
+/// A writer to a buffer that can be read while the emitter still owns the writer.
+#[derive(Clone)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn artifact_notifications_are_flushed() {
+    let output = Shared(Arc::new(Mutex::new(Vec::new())));
+    let mut emitter = JsonEmitter::new(
+        Box::new(BufWriter::new(output.clone())),
+        None,
+        Lrc::new(SourceMap::new(FilePathMapping::empty())),
+        false,
+        HumanReadableErrorType::Default(ColorConfig::Never),
+    );
+    emitter.emit_artifact_notification(Path::new("libfoo.rmeta"), "metadata");
+
+    // The emitter, and so its `BufWriter`, is still alive: the notification only reached the
+    // buffer if it was flushed.
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(output, "{\"artifact\":\"libfoo.rmeta\",\"emit\":\"metadata\"}\n");
+}
@@ -107,6 +107,15 @@ impl StableSourceFileId {
                                          source_file.unmapped_path.as_ref())
     }
 
+    /// Like `new`, but leaves out the path of the file before remapping, so
+    /// that the id stays the same when the sources are moved, as long as
+    /// `--remap-path-prefix` maps them to the same name.
+    pub fn new_relocatable(source_file: &SourceFile) -> StableSourceFileId {
+        StableSourceFileId::new_from_pieces(&source_file.name,
+                                         source_file.name_was_remapped,
+                                         None)
+    }
+
     pub fn new_from_pieces(name: &FileName,
                            name_was_remapped: bool,
                            unmapped_path: Option<&FileName>) -> StableSourceFileId {
@@ -200,6 +209,13 @@ impl SourceMap {
         self.files.borrow().stable_id_to_source_file.get(&stable_id).map(|sf| sf.clone())
     }
 
+    pub fn source_file_by_relocatable_id(&self, stable_id: StableSourceFileId) ->
+    Option<Lrc<SourceFile>> {
+        self.files.borrow().source_files.iter()
+            .find(|sf| StableSourceFileId::new_relocatable(sf) == stable_id)
+            .cloned()
+    }
+
     fn next_start_pos(&self) -> usize {
         match self.files.borrow().source_files.last() {
             None => 0,
@@ -174,6 +174,7 @@ symbols! {
         cfg_attr,
         cfg_attr_multi,
         cfg_doctest,
+        cfg_hide,
         cfg_target_feature,
         cfg_target_has_atomic,
         cfg_target_thread_local,
@@ -241,6 +242,7 @@ symbols! {
         direct,
         doc,
         doc_alias,
+        doc_auto_cfg,
         doc_cfg,
         doc_keyword,
         doc_masked,
@@ -307,6 +309,7 @@ symbols! {
         from_generator,
         from_method,
         from_ok,
+        from_text,
         from_usize,
         fundamental,
         future,
@@ -551,6 +554,7 @@ symbols! {
         rlib,
         rt,
         rtm_target_feature,
+        run_pass,
         rust,
         rust_2015_preview,
         rust_2018_preview,
@@ -0,0 +1,10 @@
+#![crate_type = "lib"]
+
+#[inline]
+pub fn hinted(x: u32) -> u32 {
+    x ^ 1
+}
+
+pub const fn not_hinted(x: u32) -> u32 {
+    x ^ 1
+}
@@ -1,3 +1,5 @@
+// ignore-tidy-linelength
+
 fn main() {
     let x: u32 = [0, 1, 2, 3][2];
 }
@@ -23,7 +25,7 @@ fn main() {
 //  bb0: {
 //      ...
 //      _5 = const true;
-//      assert(const true, "index out of bounds: the len is move _4 but the index is _3") -> bb1;
+//      assert(const true, "index out of bounds: the len is const 4usize but the index is const 2usize") -> bb1;
 //  }
 //  bb1: {
 //      _1 = _2[_3];
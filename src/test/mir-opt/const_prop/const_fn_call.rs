@@ -0,0 +1,33 @@
+const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+#[inline(never)]
+fn foo(_: u32) { }
+
+fn main() {
+    match double(2) {
+        4 => foo(0),
+        _ => foo(1),
+    }
+}
+
+// END RUST SOURCE
+// START rustc.main.ConstProp.before.mir
+//  bb1: {
+//      ...
+//      switchInt(_1) -> [4u32: bb3, otherwise: bb2];
+//  }
+// END rustc.main.ConstProp.before.mir
+// START rustc.main.ConstProp.after.mir
+//  bb1: {
+//      ...
+//      switchInt(const 4u32) -> [4u32: bb3, otherwise: bb2];
+//  }
+// END rustc.main.ConstProp.after.mir
+// START rustc.main.SimplifyBranches-after-const-prop.after.mir
+//  bb1: {
+//      ...
+//      goto -> bb3;
+//  }
+// END rustc.main.SimplifyBranches-after-const-prop.after.mir
@@ -0,0 +1,19 @@
+const fn fib(n: u64) -> u64 {
+    if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+fn fib_60() -> u64 {
+    fib(60)
+}
+
+fn main() {
+    // Only take a pointer: calling `fib_60` at runtime would take too long.
+    let _f: fn() -> u64 = fib_60;
+}
+
+// END RUST SOURCE
+// START rustc.fib_60.ConstProp.after.mir
+//  bb0: {
+//      _0 = const fib(const 60u64) -> bb1;
+//  }
+// END rustc.fib_60.ConstProp.after.mir
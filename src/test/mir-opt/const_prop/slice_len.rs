@@ -1,3 +1,5 @@
+// ignore-tidy-linelength
+
 fn main() {
     (&[1u32, 2, 3] as &[u32])[1];
 }
@@ -31,7 +33,7 @@ fn main() {
 //      _6 = const 1usize;
 //      _7 = const 3usize;
 //      _8 = const true;
-//      assert(const true, "index out of bounds: the len is move _7 but the index is _6") -> bb1;
+//      assert(const true, "index out of bounds: the len is const 3usize but the index is const 1usize") -> bb1;
 //  }
 //  bb1: {
 //      _1 = (*_2)[_6];
@@ -0,0 +1,35 @@
+// Check that stores to locals that are never read again are removed.
+
+fn dummy(x: u8) -> u8 {
+    x
+}
+
+fn dead_store(mut x: u8) {
+    dummy(x);
+    x = 5;
+}
+
+fn main() {
+    // Make sure the function actually gets instantiated.
+    dead_store(0);
+}
+
+// END RUST SOURCE
+// START rustc.dead_store.DeadStoreElimination.before.mir
+// bb1: {
+//     StorageDead(_3);
+//     StorageDead(_2);
+//     _1 = const 5u8;
+//     ...
+//     return;
+// }
+// END rustc.dead_store.DeadStoreElimination.before.mir
+// START rustc.dead_store.DeadStoreElimination.after.mir
+// bb1: {
+//     StorageDead(_3);
+//     StorageDead(_2);
+//     nop;
+//     ...
+//     return;
+// }
+// END rustc.dead_store.DeadStoreElimination.after.mir
@@ -0,0 +1,131 @@
+// Check that values are built directly in the local they are moved or copied to, when the two
+// locals never hold different values at the same time.
+
+fn nrvo() -> [u64; 32] {
+    let buf = [0u64; 32];
+    buf
+}
+
+fn self_assign(mut x: i32) {
+    x = x;
+}
+
+fn arg_src(mut x: i32) -> i32 {
+    let y = x;
+    x = 123; // `x` is overwritten while `y` is live, so `y` can only be merged into `_0`.
+    y
+}
+
+#[inline(never)]
+fn id(x: [u64; 32]) -> [u64; 32] {
+    x
+}
+
+fn reassign_call() -> [u64; 32] {
+    let mut x = [0u64; 32];
+    // The destination of the call is never merged with its argument: once `x` is merged into
+    // the argument, the call must not become `_3 = const id(move _3)`.
+    x = id(x);
+    x
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    nrvo();
+    self_assign(0);
+    arg_src(0);
+    reassign_call();
+}
+
+// END RUST SOURCE
+// START rustc.nrvo.DestinationPropagation.before.mir
+// bb0: {
+//     ...
+//     _1 = [const 0u64; 32];
+//     ...
+//     _0 = _1;
+//     ...
+//     return;
+// }
+// END rustc.nrvo.DestinationPropagation.before.mir
+// START rustc.nrvo.DestinationPropagation.after.mir
+// bb0: {
+//     ...
+//     _0 = [const 0u64; 32];
+//     ...
+//     return;
+// }
+// END rustc.nrvo.DestinationPropagation.after.mir
+// START rustc.self_assign.DestinationPropagation.before.mir
+// bb0: {
+//     ...
+//     _2 = _1;
+//     _1 = move _2;
+//     ...
+//     return;
+// }
+// END rustc.self_assign.DestinationPropagation.before.mir
+// START rustc.self_assign.DestinationPropagation.after.mir
+// bb0: {
+//     ...
+//     nop;
+//     nop;
+//     nop;
+//     nop;
+//     ...
+//     return;
+// }
+// END rustc.self_assign.DestinationPropagation.after.mir
+// START rustc.arg_src.DestinationPropagation.before.mir
+// bb0: {
+//     ...
+//     _2 = _1;
+//     ...
+//     _1 = const 123i32;
+//     ...
+//     _0 = _2;
+//     ...
+//     return;
+// }
+// END rustc.arg_src.DestinationPropagation.before.mir
+// START rustc.arg_src.DestinationPropagation.after.mir
+// bb0: {
+//     ...
+//     _0 = _1;
+//     ...
+//     _1 = const 123i32;
+//     ...
+//     return;
+// }
+// END rustc.arg_src.DestinationPropagation.after.mir
+// START rustc.reassign_call.DestinationPropagation.before.mir
+// bb0: {
+//     ...
+//     _1 = [const 0u64; 32];
+//     ...
+//     _3 = _1;
+//     _2 = const id(move _3) -> bb1;
+// }
+// bb1: {
+//     ...
+//     _1 = move _2;
+//     ...
+//     _0 = _1;
+//     ...
+//     return;
+// }
+// END rustc.reassign_call.DestinationPropagation.before.mir
+// START rustc.reassign_call.DestinationPropagation.after.mir
+// bb0: {
+//     ...
+//     _0 = [const 0u64; 32];
+//     ...
+//     _2 = const id(move _0) -> bb1;
+// }
+// bb1: {
+//     ...
+//     _0 = move _2;
+//     ...
+//     return;
+// }
+// END rustc.reassign_call.DestinationPropagation.after.mir
@@ -0,0 +1,60 @@
+// Check that repeated computations are replaced with copies of their first result, and that
+// the bounds and overflow checks made redundant by this are removed. The first result is then
+// copied instead of moved, since it is read again.
+
+fn index_twice(x: &[u32], i: usize) -> u32 {
+    x[i] + x[i]
+}
+
+fn add_twice(a: u32, b: u32) -> (u32, u32) {
+    (a + b, a + b)
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    index_twice(&[1, 2, 3], 1);
+    add_twice(1, 2);
+}
+
+// END RUST SOURCE
+// START rustc.index_twice.GlobalValueNumbering.before.mir
+// bb1: {
+//     ...
+//     _9 = Len((*_1));
+//     _10 = Lt(_8, _9);
+//     assert(move _10, "index out of bounds: the len is move _9 but the index is _8") -> bb2;
+// }
+// END rustc.index_twice.GlobalValueNumbering.before.mir
+// START rustc.index_twice.GlobalValueNumbering.after.mir
+// bb0: {
+//     ...
+//     _6 = Lt(_4, _5);
+//     assert(_6, "index out of bounds: the len is _5 but the index is _4") -> bb1;
+// }
+// bb1: {
+//     ...
+//     _9 = _5;
+//     _10 = _6;
+//     goto -> bb2;
+// }
+// END rustc.index_twice.GlobalValueNumbering.after.mir
+// START rustc.add_twice.GlobalValueNumbering.before.mir
+// bb1: {
+//     ...
+//     _10 = CheckedAdd(move _8, move _9);
+//     assert(!move (_10.1: bool), "attempt to add with overflow") -> bb2;
+// }
+// END rustc.add_twice.GlobalValueNumbering.before.mir
+// START rustc.add_twice.GlobalValueNumbering.after.mir
+// bb0: {
+//     ...
+//     _6 = CheckedAdd(move _4, move _5);
+//     assert(!(_6.1: bool), "attempt to add with overflow") -> bb1;
+// }
+// bb1: {
+//     _3 = (_6.0: u32);
+//     ...
+//     _10 = _6;
+//     goto -> bb2;
+// }
+// END rustc.add_twice.GlobalValueNumbering.after.mir
@@ -0,0 +1,61 @@
+// Tests that calls in cleanup blocks are inlined, and that the calls of the inlined callee
+// have no unwind edge there, since panicking while unwinding aborts.
+
+#![feature(rustc_attrs)]
+
+#[rustc_mir(run_pass = "Inline", from_text = r#"
+fn caller(_1: u32) -> u32 {
+    let mut _0: u32;                     // return place
+    let mut _2: u32;
+
+    bb0: {
+        _0 = const forward::<u32>(_1) -> [return: bb1, unwind: bb2];
+    }
+
+    bb1: {
+        return;
+    }
+
+    bb2 (cleanup): {
+        _2 = const forward::<u32>(_1) -> bb3;
+    }
+
+    bb3 (cleanup): {
+        resume;
+    }
+}
+"#)]
+fn caller(x: u32) -> u32 {
+    forward(x)
+}
+
+fn main() {
+    caller(0);
+}
+
+fn forward<T>(x: T) -> T {
+    identity(x)
+}
+
+#[inline(never)]
+fn identity<T>(x: T) -> T {
+    x
+}
+
+// END RUST SOURCE
+// START rustc.caller.Inline.after.mir
+// bb0: {
+//     ...
+//     _0 = const identity::<u32>(move _3) -> [return: bb1, unwind: bb2];
+// }
+// bb1: {
+//     return;
+// }
+// bb2 (cleanup): {
+//     ...
+//     _2 = const identity::<u32>(move _4) -> bb3;
+// }
+// bb3 (cleanup): {
+//     resume;
+// }
+// END rustc.caller.Inline.after.mir
@@ -0,0 +1,20 @@
+// aux-build:inline_aux.rs
+
+// Tests that the `#[inline]` functions of other crates are inlined, but not the other functions
+// whose MIR is available, like `const fn`s, since it may reference symbols which aren't
+// exported.
+
+extern crate inline_aux;
+
+fn main() {
+    inline_aux::hinted(1);
+    inline_aux::not_hinted(1);
+}
+
+// END RUST SOURCE
+// START rustc.main.Inline.decisions.mir
+// | inline_aux::hinted: inlined, trivial callee [cost=10 <= threshold=125]
+// | inline_aux::not_hinted: not inlined, the callee isn't inlinable across crates
+// |
+// ...
+// END rustc.main.Inline.decisions.mir
@@ -0,0 +1,26 @@
+// Tests that the calls in the body of a generator are inlined, before it is transformed.
+
+#![feature(generators, generator_trait)]
+
+use std::ops::Generator;
+use std::pin::Pin;
+
+fn main() {
+    let mut gen = || {
+        let x = flip(20);
+        yield x;
+    };
+    Pin::new(&mut gen).resume();
+}
+
+#[inline]
+fn flip(x: u32) -> u32 {
+    x ^ 1
+}
+
+// END RUST SOURCE
+// START rustc.main-{{closure}}.Inline.decisions.mir
+// | flip: inlined, trivial callee [cost=10 <= threshold=125]
+// |
+// ...
+// END rustc.main-{{closure}}.Inline.decisions.mir
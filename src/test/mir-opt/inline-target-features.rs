@@ -0,0 +1,54 @@
+// only-x86_64
+
+// Tests that a callee with target features is only inlined into callers which enable all of
+// them, and that `#[naked]` callees are never inlined.
+
+#![feature(naked_functions)]
+
+fn main() {
+    // The functions are only referenced, since the CPU running the test may lack AVX2.
+    let _ = (caller as unsafe fn(u32) -> u32, avx2_caller as unsafe fn(u32) -> u32);
+    let _ = naked_caller as fn(u32) -> u32;
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn avx2(x: u32) -> u32 {
+    x ^ 1
+}
+
+unsafe fn caller(x: u32) -> u32 {
+    avx2(x)
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn avx2_caller(x: u32) -> u32 {
+    avx2(x)
+}
+
+#[inline]
+#[naked]
+fn naked(x: u32) -> u32 {
+    x ^ 1
+}
+
+fn naked_caller(x: u32) -> u32 {
+    naked(x)
+}
+
+// END RUST SOURCE
+// START rustc.caller.Inline.decisions.mir
+// | avx2: not inlined, the callee has target features the caller lacks
+// |
+// ...
+// END rustc.caller.Inline.decisions.mir
+// START rustc.avx2_caller.Inline.decisions.mir
+// | avx2: inlined, trivial callee [cost=10 <= threshold=125]
+// |
+// ...
+// END rustc.avx2_caller.Inline.decisions.mir
+// START rustc.naked_caller.Inline.decisions.mir
+// | naked: not inlined, the callee is `#[naked]`
+// |
+// ...
+// END rustc.naked_caller.Inline.decisions.mir
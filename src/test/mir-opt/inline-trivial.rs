@@ -0,0 +1,44 @@
+// compile-flags: -Z span_free_formats
+
+// Tests that trivial callees are inlined whatever the thresholds, and that the
+// inlining decisions are dumped.
+
+use std::ops::Deref;
+
+fn main() {
+    println!("{}", get(&Wrapper(4)));
+}
+
+fn get(w: &Wrapper<i32>) -> i32 {
+    **w
+}
+
+struct Wrapper<T>(T);
+
+impl<T> Deref for Wrapper<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+// END RUST SOURCE
+// START rustc.get.Inline.after.mir
+// ...
+// bb0: {
+//     ...
+//     _3 = &(*_1);
+//     ...
+//     _2 = &((*_3).0: i32);
+//     ...
+//     _0 = (*_2);
+//     ...
+//     return;
+// }
+// END rustc.get.Inline.after.mir
+// START rustc.get.Inline.decisions.mir
+// | <Wrapper<T> as std::ops::Deref>::deref: inlined, trivial callee [cost=10 <= threshold=62]
+// |
+// ...
+// END rustc.get.Inline.decisions.mir
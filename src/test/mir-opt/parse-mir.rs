@@ -0,0 +1,60 @@
+// Tests that the MIR given with `#[rustc_mir(from_text)]` replaces the one of the function, and
+// that only the pass named by `run_pass` is run on it.
+
+#![feature(rustc_attrs)]
+
+#[rustc_mir(run_pass = "InstCombine", from_text = r#"
+fn len(_1: &[u32; 4]) -> usize {
+    let mut _0: usize;                   // return place
+    let mut _2: &[u32; 4];
+    let mut _3: usize;
+    scope 1 {
+        let _4: (usize, bool);
+    }
+
+    bb0: {
+        StorageLive(_2);
+        _2 = &(*_1);
+        _3 = Len((*_2));
+        StorageDead(_2);
+        _4 = CheckedAdd(_3, const 0usize);
+        assert(!move (_4.1: bool), "attempt to add with overflow") -> bb1;
+    }
+
+    bb1: {
+        _0 = move (_4.0: usize);
+        return;
+    }
+}
+"#)]
+fn len(x: &[u32; 4]) -> usize {
+    x.len()
+}
+
+fn main() {
+    assert_eq!(len(&[1, 2, 3, 4]), 4);
+}
+
+// END RUST SOURCE
+// START rustc.len.InstCombine.before.mir
+// bb0: {
+//     StorageLive(_2);
+//     _2 = &(*_1);
+//     _3 = Len((*_2));
+//     ...
+// }
+// END rustc.len.InstCombine.before.mir
+// START rustc.len.InstCombine.after.mir
+// bb0: {
+//     StorageLive(_2);
+//     _2 = _1;
+//     _3 = const 4usize;
+//     StorageDead(_2);
+//     _4 = CheckedAdd(_3, const 0usize);
+//     assert(!move (_4.1: bool), "attempt to add with overflow") -> bb1;
+// }
+// bb1: {
+//     _0 = move (_4.0: usize);
+//     return;
+// }
+// END rustc.len.InstCombine.after.mir
@@ -0,0 +1,103 @@
+// Tests that `SimplifyBranchSame` keeps the storage statements of the arms it removes, and that it
+// leaves the switch alone when the arms have different storage statements.
+
+#![feature(rustc_attrs)]
+
+#[rustc_mir(run_pass = "SimplifyBranchSame", from_text = r#"
+fn same_storage(_1: std::option::Option<u32>) -> std::option::Option<u32> {
+    let mut _0: std::option::Option<u32>; // return place
+    let mut _2: isize;
+    let mut _3: u32;
+
+    bb0: {
+        StorageLive(_3);
+        _2 = discriminant(_1);
+        switchInt(move _2) -> [0isize: bb2, 1isize: bb3, otherwise: bb1];
+    }
+
+    bb1: {
+        unreachable;
+    }
+
+    bb2: {
+        _0 = move _1;
+        StorageDead(_3);
+        goto -> bb4;
+    }
+
+    bb3: {
+        _0 = move _1;
+        StorageDead(_3);
+        goto -> bb4;
+    }
+
+    bb4: {
+        return;
+    }
+}
+"#)]
+fn same_storage(x: Option<u32>) -> Option<u32> {
+    x
+}
+
+#[rustc_mir(run_pass = "SimplifyBranchSame", from_text = r#"
+fn different_storage(_1: std::option::Option<u32>) -> std::option::Option<u32> {
+    let mut _0: std::option::Option<u32>; // return place
+    let mut _2: isize;
+    let mut _3: u32;
+
+    bb0: {
+        StorageLive(_3);
+        _2 = discriminant(_1);
+        switchInt(move _2) -> [0isize: bb2, 1isize: bb3, otherwise: bb1];
+    }
+
+    bb1: {
+        unreachable;
+    }
+
+    bb2: {
+        _0 = move _1;
+        StorageDead(_3);
+        goto -> bb4;
+    }
+
+    bb3: {
+        _0 = move _1;
+        goto -> bb4;
+    }
+
+    bb4: {
+        return;
+    }
+}
+"#)]
+fn different_storage(x: Option<u32>) -> Option<u32> {
+    x
+}
+
+fn main() {
+    assert_eq!(same_storage(Some(1)), Some(1));
+    assert_eq!(different_storage(None), None);
+}
+
+// END RUST SOURCE
+// START rustc.same_storage.SimplifyBranchSame.after.mir
+// bb0: {
+//     StorageLive(_3);
+//     _2 = discriminant(_1);
+//     _0 = move _1;
+//     StorageDead(_3);
+//     goto -> bb1;
+// }
+// bb1: {
+//     return;
+// }
+// END rustc.same_storage.SimplifyBranchSame.after.mir
+// START rustc.different_storage.SimplifyBranchSame.after.mir
+// bb0: {
+//     StorageLive(_3);
+//     _2 = discriminant(_1);
+//     switchInt(move _2) -> [0isize: bb2, 1isize: bb3, otherwise: bb1];
+// }
+// END rustc.different_storage.SimplifyBranchSame.after.mir
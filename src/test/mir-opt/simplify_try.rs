@@ -0,0 +1,48 @@
+// Tests that matches rebuilding their scrutinee are replaced by a copy of it.
+
+fn identity(x: Result<u32, i32>) -> Result<u32, i32> {
+    match x {
+        Ok(v) => Ok(v),
+        Err(e) => Err(e),
+    }
+}
+
+fn option_identity(x: Option<u32>) -> Option<u32> {
+    match x {
+        Some(v) => Some(v),
+        None => None,
+    }
+}
+
+fn main() {
+    identity(Ok(0));
+    option_identity(Some(0));
+}
+
+// END RUST SOURCE
+// START rustc.identity.SimplifyBranchSame.after.mir
+// bb0: {
+//     ...
+//     _2 = discriminant(_1);
+//     _0 = _1;
+//     goto -> bb1;
+// }
+// ...
+// END rustc.identity.SimplifyBranchSame.after.mir
+// START rustc.identity.PreCodegen.after.mir
+// bb0: {
+//     ...
+//     _0 = _1;
+//     ...
+//     return;
+// }
+// END rustc.identity.PreCodegen.after.mir
+// START rustc.option_identity.SimplifyBranchSame.after.mir
+// bb0: {
+//     ...
+//     _2 = discriminant(_1);
+//     _0 = _1;
+//     goto -> bb1;
+// }
+// ...
+// END rustc.option_identity.SimplifyBranchSame.after.mir
@@ -1,3 +1,6 @@
+// Dead store elimination would remove the unused references checked below.
+// compile-flags: -Z mir-enable-passes=-DeadStoreElimination
+
 #![feature(never_type)]
 
 pub enum Void {}
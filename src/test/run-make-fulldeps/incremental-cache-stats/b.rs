@@ -0,0 +1,7 @@
+pub fn callee(x: u32) -> u32 {
+    x * 2
+}
+
+pub fn caller() -> u32 {
+    callee(2)
+}
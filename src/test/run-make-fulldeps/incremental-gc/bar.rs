@@ -0,0 +1 @@
+pub fn bar() -> u32 { 2 }
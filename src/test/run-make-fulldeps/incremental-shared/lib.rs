@@ -0,0 +1,7 @@
+pub fn answer() -> u32 {
+    42
+}
+
+pub fn double(x: u32) -> u32 {
+    x * 2
+}
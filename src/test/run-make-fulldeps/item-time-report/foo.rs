@@ -0,0 +1,8 @@
+fn generic_item<T: Default>() -> T {
+    T::default()
+}
+
+fn main() {
+    let _: u32 = generic_item();
+    let _: String = generic_item();
+}
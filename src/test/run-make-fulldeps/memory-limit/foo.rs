@@ -0,0 +1,5 @@
+#![crate_type = "rlib"]
+
+pub fn foo() -> Vec<u32> {
+    (0..10).collect()
+}
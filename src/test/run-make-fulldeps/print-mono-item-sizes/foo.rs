@@ -0,0 +1,15 @@
+#![crate_type = "rlib"]
+
+pub fn bar() -> u64 {
+    outer(1u32) as u64 + outer(2u64)
+}
+
+#[inline(never)]
+fn outer<T: Copy>(x: T) -> T {
+    inner(x)
+}
+
+#[inline(never)]
+fn inner<T: Copy>(x: T) -> T {
+    x
+}
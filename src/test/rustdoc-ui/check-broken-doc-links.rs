@@ -0,0 +1,23 @@
+// compile-flags:-Z unstable-options --check
+
+#![deny(broken_doc_links)]
+
+/// Links to [a struct](struct.Foo.html), [a method](struct.Foo.html#method.bar),
+/// [a section](#examples) and [a module](inner/index.html) are fine.
+///
+/// # Examples
+pub struct Foo;
+
+impl Foo {
+    pub fn bar() {}
+}
+
+pub mod inner {
+    /// Back to [Foo](../struct.Foo.html), [bar](../struct.Foo.html#method.bar).
+    pub fn baz() {}
+}
+
+/// [missing page](struct.Missing.html) //~ ERROR
+/// [missing anchor](struct.Foo.html#method.qux) //~ ERROR
+/// [missing section](#nowhere) //~ ERROR
+pub fn qux() {}
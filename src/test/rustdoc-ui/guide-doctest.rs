@@ -0,0 +1,15 @@
+// build-pass
+// compile-flags:--test --test-args --test-threads=1
+// compile-flags:-Z unstable-options --guide {{src-base}}/auxiliary/guide
+// normalize-stdout-test: "src/test/rustdoc-ui" -> "$$DIR"
+
+// The code blocks of the guide's chapters are run along with the crate's doctests.
+
+#![crate_name = "guide_doctest"]
+
+/// ```
+/// assert_eq!(guide_doctest::answer(), 42);
+/// ```
+pub fn answer() -> u32 {
+    42
+}
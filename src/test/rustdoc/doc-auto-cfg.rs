@@ -0,0 +1,45 @@
+// compile-flags:--cfg feature="serde" --cfg feature="json"
+
+#![feature(doc_auto_cfg)]
+#![doc(cfg_hide(feature = "hidden"))]
+
+// @has doc_auto_cfg/fn.always.html
+// @count - '//*[@class="stab portability"]' 0
+pub fn always() {}
+
+// @has doc_auto_cfg/fn.with_serde.html
+// @has - '//*[@id="main"]/*[@class="stability"]/*[@class="stab portability"]' \
+//        'This is supported on feature="serde" only.'
+#[cfg(feature = "serde")]
+pub fn with_serde() {}
+
+// the `rustdoc` cfg is always set while documenting, so it is hidden by default
+// @has doc_auto_cfg/fn.unix_or_rustdoc.html
+// @has - '//*[@id="main"]/*[@class="stability"]/*[@class="stab portability"]' \
+//        'This is supported on Unix only.'
+#[cfg(any(unix, rustdoc))]
+pub fn unix_or_rustdoc() {}
+
+// @has doc_auto_cfg/fn.hidden_feature.html
+// @count - '//*[@class="stab portability"]' 0
+#[cfg(not(feature = "hidden"))]
+pub fn hidden_feature() {}
+
+// @has doc_auto_cfg/serde_only/index.html \
+//  '//*[@id="main"]/*[@class="stability"]/*[@class="stab portability"]' \
+//  'This is supported on feature="serde" only.'
+#[cfg(feature = "serde")]
+pub mod serde_only {
+    // the requirement is inherited from the parent module and isn't repeated
+    // @has doc_auto_cfg/serde_only/fn.repeated.html \
+    //  '//*[@id="main"]/*[@class="stability"]/*[@class="stab portability"]' \
+    //  'This is supported on feature="serde" only.'
+    #[cfg(feature = "serde")]
+    pub fn repeated() {}
+
+    // @has doc_auto_cfg/serde_only/fn.serde_and_json.html \
+    //  '//*[@id="main"]/*[@class="stability"]/*[@class="stab portability"]' \
+    //  'This is supported on feature="serde" and feature="json" only.'
+    #[cfg(feature = "json")]
+    pub fn serde_and_json() {}
+}
@@ -0,0 +1,26 @@
+// compile-flags: -Z unstable-options --guide {{src-base}}/auxiliary/guide
+
+#![crate_name = "foo"]
+
+// @has foo/index.html
+// @has - '//*[@class="block guide"]//a[@href="guide/intro.html"]' 'Getting started'
+// @has - '//*[@class="block guide"]//a[@href="guide/advanced.html"]' 'Advanced usage'
+
+// @has foo/guide/intro.html
+// @has - '//h1[@class="fqn"]' 'Getting started'
+// @has - '//*[@class="block guide"]//a[@class="current"]' 'Getting started'
+// @has - '//a[@href="../../foo/struct.Widget.html"]' 'Widget'
+// @has - '//a[@href="advanced.html"]' 'advanced usage'
+// @has - '//a[@class="next"][@href="advanced.html"]' 'Advanced usage'
+// @!has - '//a[@class="prev"]'
+
+// @has foo/guide/advanced.html
+// @has - '//a[@href="../../foo/fn.combine.html"]' 'combine'
+// @has - '//a[@class="prev"][@href="intro.html"]' 'Getting started'
+// @!has - '//a[@class="next"]'
+
+pub struct Widget;
+
+pub fn combine(_: Widget, _: Widget) -> Widget {
+    Widget
+}
@@ -0,0 +1,20 @@
+// check-pass
+// edition:2018
+// ignore-32bit
+
+// Tests that `large_futures` ignores the futures too large to have a layout, instead of
+// crashing. They are reported as too big for the target when they are compiled.
+
+#![deny(large_futures)]
+
+async fn ready() {}
+
+async fn overflowing() {
+    let a = [0u8; 1 << 50];
+    ready().await;
+    drop(a);
+}
+
+fn main() {
+    drop(overflowing());
+}
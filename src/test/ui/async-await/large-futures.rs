@@ -0,0 +1,44 @@
+// edition:2018
+// compile-flags: -Z future-size-limit=1024
+
+#![deny(large_futures)]
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+struct Ready;
+
+impl Future for Ready {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
+}
+
+async fn small() {
+    let buf = [0u8; 512];
+    Ready.await;
+    drop(buf);
+}
+
+async fn held_across_await() { //~ ERROR this future is 2052 bytes large
+    let buf = [0u8; 2048];
+    Ready.await;
+    drop(buf);
+}
+
+async fn generic<T>(x: T) {
+    Ready.await;
+    drop(x);
+}
+
+fn captured() -> impl Future<Output = ()> {
+    let buf = [0u8; 2048];
+    async move { drop(buf) } //~ ERROR this future is 2052 bytes large
+}
+
+fn main() {
+    drop((small(), held_across_await(), generic([0u8; 2048]), captured()));
+}
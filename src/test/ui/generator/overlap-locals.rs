@@ -26,4 +26,21 @@ fn main() {
         }
     };
     assert_eq!(8, std::mem::size_of_val(&a));
+
+    // Locals live across several yields can overlap too.
+    let b = || {
+        {
+            let w: i32 = 4;
+            yield;
+            yield;
+            println!("{:?}", w);
+        }
+        {
+            let x: i32 = 5;
+            yield;
+            yield;
+            println!("{:?}", x);
+        }
+    };
+    assert_eq!(8, std::mem::size_of_val(&b));
 }
@@ -0,0 +1,5 @@
+// Tests that `-Z mir-enable-passes` only accepts the names of MIR optimization passes.
+
+// compile-flags: -Z mir-enable-passes=-ElaborateDrops,+NoSuchPass,-Inline
+
+fn main() {}
@@ -0,0 +1,29 @@
+// build-pass
+
+// Tests that `-Z mir-opt-bisect-limit` logs each change made by the MIR optimizations, and stops
+// applying them once the limit is reached. Also tests that `+Pass` in `-Z mir-enable-passes`
+// overrides an earlier `-Pass`, and runs the pass even when the `-Z mir-opt-level` wouldn't.
+
+// compile-flags: --error-format human -Z mir-opt-level=0 -Z mir-opt-bisect-limit=1
+// compile-flags: -Z mir-enable-passes=-InstCombine,+InstCombine
+
+#![crate_type = "lib"]
+#![feature(rustc_attrs)]
+
+#[rustc_mir(run_pass = "InstCombine", from_text = r#"
+fn len(_1: &[u32; 4]) -> usize {
+    let mut _0: usize;                   // return place
+    let mut _2: &[u32; 4];
+
+    bb0: {
+        StorageLive(_2);
+        _2 = &(*_1);
+        _0 = Len((*_2));
+        StorageDead(_2);
+        return;
+    }
+}
+"#)]
+pub fn len(x: &[u32; 4]) -> usize {
+    x.len()
+}
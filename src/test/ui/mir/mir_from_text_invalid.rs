@@ -0,0 +1,16 @@
+// ignore-tidy-linelength
+
+// Tests that the errors in the MIR given with `#[rustc_mir(from_text)]` are reported, like an
+// index too large to be stored.
+
+#![feature(rustc_attrs)]
+
+#[rustc_mir(from_text = "fn f(_1: u32) -> u32 { let mut _0: u32; bb0: { _0 = _18446744073709551616; return; } }")]
+//~^ ERROR invalid MIR: 1:54: expected an index up to 65535
+fn f(x: u32) -> u32 {
+    x
+}
+
+fn main() {
+    f(0);
+}
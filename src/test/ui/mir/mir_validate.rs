@@ -0,0 +1,59 @@
+// run-pass
+// compile-flags: -Z validate-mir -Z mir-opt-level=3
+// ignore-wasm32-bare compiled with panic=abort by default
+
+// Tests that the MIR of code exercising drops, unwinding, matches, closures and generators is
+// still valid after each pass.
+
+#![feature(generators, generator_trait)]
+
+use std::ops::{Generator, GeneratorState};
+use std::pin::Pin;
+
+struct Droppable(u32);
+
+impl Drop for Droppable {
+    fn drop(&mut self) {}
+}
+
+fn parse(s: &str) -> Result<u32, std::num::ParseIntError> {
+    let x = s.parse::<u32>()?;
+    Ok(x + 1)
+}
+
+fn replace(mut d: Droppable, n: u32) -> u32 {
+    if n > 2 {
+        d = Droppable(n);
+    }
+    d.0
+}
+
+fn main() {
+    assert_eq!(parse("1"), Ok(2));
+    assert!(parse("a").is_err());
+    assert_eq!(replace(Droppable(1), 3), 3);
+
+    let v: Vec<Droppable> = (0..4).map(Droppable).collect();
+    let sum = v.iter().fold(0, |acc, d| acc + d.0);
+    assert_eq!(sum, 6);
+
+    let result = std::panic::catch_unwind(|| {
+        let _d = Droppable(0);
+        panic!();
+    });
+    assert!(result.is_err());
+
+    let mut generator = || {
+        let d = Droppable(5);
+        yield d.0;
+        d.0 + 1
+    };
+    match Pin::new(&mut generator).resume() {
+        GeneratorState::Yielded(5) => {}
+        _ => panic!(),
+    }
+    match Pin::new(&mut generator).resume() {
+        GeneratorState::Complete(6) => {}
+        _ => panic!(),
+    }
+}
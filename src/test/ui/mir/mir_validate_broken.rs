@@ -0,0 +1,34 @@
+// compile-flags: -Z validate-mir
+// failure-status: 101
+// rustc-env:RUST_BACKTRACE=0
+// normalize-stderr-test "note: rustc 1.* running on .*" -> "note: rustc VERSION running on TARGET"
+// normalize-stderr-test "note: compiler flags: .*" -> "note: compiler flags: FLAGS"
+// normalize-stderr-test "DefId\([^)]*\)" -> "DefId(..)"
+// normalize-stderr-test "librustc_errors/lib.rs:[0-9]+:[0-9]+" -> "librustc_errors/lib.rs:LL:CC"
+
+// Tests that the MIR validator reports broken MIR, naming the pass which was run last. Here
+// the MIR reads a local after its `StorageDead`.
+
+#![feature(rustc_attrs)]
+
+#[rustc_mir(run_pass = "InstCombine", from_text = r#"
+fn broken(_1: u32) -> u32 {
+    let mut _0: u32;                     // return place
+    let mut _2: u32;
+
+    bb0: {
+        StorageLive(_2);
+        _2 = _1;
+        StorageDead(_2);
+        _0 = _2;
+        return;
+    }
+}
+"#)]
+fn broken(x: u32) -> u32 {
+    x
+}
+
+fn main() {
+    broken(0);
+}
@@ -0,0 +1,39 @@
+// compile-flags: -Z print-type-sizes
+// build-pass (FIXME(62277): could be check-pass?)
+// ignore-pass
+// ^-- needed because `--pass check` does not emit the output needed.
+//     FIXME: consider using an attribute instead of side-effects.
+// normalize-stdout-test "\[generator@[^`]*\]" -> "[generator]"
+
+// This file illustrates how generators are shown: there is a variant for
+// each suspension point, with the upvars and the locals live there as its
+// fields. The saved locals are then listed with the suspension points they
+// are live at. `a` and `b` are never live at the same time, so they share
+// their bytes even though `b` is live at two suspension points.
+
+#![feature(generators, generator_trait, start)]
+
+use std::ops::Generator;
+
+fn generator(array: [u8; 1]) -> impl Generator<Yield = (), Return = ()> {
+    move || {
+        {
+            let a = [0u8; 8];
+            yield;
+            drop(a);
+        }
+        {
+            let b = [0u8; 4];
+            yield;
+            yield;
+            drop(b);
+        }
+        drop(array);
+    }
+}
+
+#[start]
+fn start(_: isize, _: *const *const u8) -> isize {
+    let _gen = generator([0; 1]);
+    0
+}
@@ -0,0 +1,116 @@
+// run-pass
+// ignore-android FIXME #17520
+// ignore-cloudabi spawning processes is not supported
+// ignore-emscripten spawning processes is not supported
+// ignore-openbsd no support for libbacktrace without filename
+// ignore-sgx no processes
+// ignore-msvc see #62897 and `backtrace-debuginfo.rs` test
+// compile-flags:-g
+
+#![feature(backtrace)]
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::env;
+use std::fmt::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::Command;
+use std::str;
+
+#[derive(Debug)]
+struct MyError {
+    backtrace: Backtrace,
+}
+
+impl fmt::Display for MyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("my error")
+    }
+}
+
+impl std::error::Error for MyError {
+    fn backtrace(&self) -> Option<&Backtrace> {
+        Some(&self.backtrace)
+    }
+}
+
+struct PanickingWriter;
+
+impl Write for PanickingWriter {
+    fn write_str(&mut self, _: &str) -> fmt::Result {
+        panic!("failed to write the backtrace");
+    }
+}
+
+#[inline(never)]
+fn make_error() -> MyError {
+    MyError { backtrace: Backtrace::capture() }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() >= 2 && args[1] == "force" {
+        let backtrace = Backtrace::force_capture();
+        assert_eq!(backtrace.status(), BacktraceStatus::Captured);
+        println!("{}", backtrace);
+    } else if args.len() >= 2 && args[1] == "debug" {
+        println!("{:?}", Backtrace::force_capture());
+    } else if args.len() >= 2 && args[1] == "poisoned" {
+        // A panic while the backtrace is written out must not stop it from being printed again.
+        let backtrace = Backtrace::force_capture();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            write!(PanickingWriter, "{}", backtrace)
+        }));
+        assert!(result.is_err());
+        println!("{}", backtrace);
+    } else if args.len() >= 2 {
+        let err: Box<dyn std::error::Error> = Box::new(make_error());
+        println!("{}", err.backtrace().unwrap());
+    } else {
+        runtest(&args[0]);
+    }
+}
+
+fn run(me: &str, arg: &str, vars: &[(&str, &str)]) -> String {
+    let mut cmd = Command::new(me);
+    cmd.arg(arg).env_remove("RUST_BACKTRACE").env_remove("RUST_LIB_BACKTRACE");
+    for &(key, value) in vars {
+        cmd.env(key, value);
+    }
+    let out = cmd.output().unwrap();
+    assert!(out.status.success());
+    String::from_utf8(out.stdout).unwrap()
+}
+
+fn runtest(me: &str) {
+    // The backtrace starts at the caller of `Backtrace::capture`.
+    let s = run(me, "a", &[("RUST_BACKTRACE", "1")]);
+    assert!(s.contains("stack backtrace:\n"), "bad output: {}", s);
+    assert!(s.contains("  0: std_backtrace::make_error"), "bad output: {}", s);
+    assert!(s.contains("std_backtrace::main"), "bad output: {}", s);
+    assert!(!s.contains("Backtrace::capture"), "bad output: {}", s);
+
+    let s = run(me, "a", &[]);
+    assert_eq!(s, "disabled backtrace\n");
+
+    let s = run(me, "a", &[("RUST_BACKTRACE", "0")]);
+    assert_eq!(s, "disabled backtrace\n");
+
+    let s = run(me, "a", &[("RUST_LIB_BACKTRACE", "1")]);
+    assert!(s.contains("stack backtrace:\n"), "bad output: {}", s);
+
+    // `RUST_LIB_BACKTRACE` takes precedence over `RUST_BACKTRACE`.
+    let s = run(me, "a", &[("RUST_LIB_BACKTRACE", "0"), ("RUST_BACKTRACE", "1")]);
+    assert_eq!(s, "disabled backtrace\n");
+
+    let s = run(me, "force", &[("RUST_LIB_BACKTRACE", "0")]);
+    assert!(s.contains("stack backtrace:\n"), "bad output: {}", s);
+    assert!(s.contains("std_backtrace::main"), "bad output: {}", s);
+
+    let s = run(me, "poisoned", &[]);
+    assert!(s.contains("stack backtrace:\n"), "bad output: {}", s);
+    assert!(s.contains("std_backtrace::main"), "bad output: {}", s);
+
+    let s = run(me, "debug", &[]);
+    assert!(s.starts_with("[{ fn: \""), "bad output: {}", s);
+    assert!(s.contains("std_backtrace::main"), "bad output: {}", s);
+}
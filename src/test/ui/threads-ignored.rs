@@ -0,0 +1,7 @@
+// check-pass
+// ignore-parallel-compiler
+// compile-flags: -Z threads=2
+
+// Tests that a compiler built without the parallel front-end warns that it ignores `-Z threads`.
+
+fn main() {}
@@ -98,6 +98,7 @@ impl EarlyProps {
 
         let rustc_has_profiler_support = env::var_os("RUSTC_PROFILER_SUPPORT").is_some();
         let rustc_has_sanitizer_support = env::var_os("RUSTC_SANITIZER_SUPPORT").is_some();
+        let rustc_is_parallel = env::var_os("RUSTC_PARALLEL_COMPILER").is_some();
 
         iter_header(testfile, None, &mut |ln| {
             // we should check if any only-<platform> exists and if it exists
@@ -137,6 +138,11 @@ impl EarlyProps {
                    config.parse_needs_sanitizer_support(ln) {
                     props.ignore = Ignore::Ignore;
                 }
+
+                if rustc_is_parallel &&
+                   config.parse_ignore_parallel_compiler(ln) {
+                    props.ignore = Ignore::Ignore;
+                }
             }
 
             if (config.mode == common::DebugInfoGdb || config.mode == common::DebugInfoGdbLldb) &&
@@ -808,6 +814,10 @@ impl Config {
         self.parse_name_directive(line, "needs-sanitizer-support")
     }
 
+    fn parse_ignore_parallel_compiler(&self, line: &str) -> bool {
+        self.parse_name_directive(line, "ignore-parallel-compiler")
+    }
+
     /// Parses a name-value directive which contains config-specific information, e.g., `ignore-x86`
     /// or `normalize-stderr-32bit`.
     fn parse_cfg_name_directive(&self, line: &str, prefix: &str) -> ParsedNameDirective {